@@ -0,0 +1,75 @@
+#!/usr/bin/env python3
+"""Pushes images to a running `miniview serve` instance.
+
+Start the server with `miniview serve --tcp 127.0.0.1:7878`, then run:
+
+    python3 serve_client.py image.png
+
+See the documentation of the `miniview::serve` module for the wire format.
+"""
+
+import socket
+import struct
+import sys
+
+MAGIC = b"MVF1"
+
+ENCODED = 0
+RAW = 1
+
+LUMA8, LUMAA8, RGB8, RGBA8, BGR8, BGRA8 = range(1, 7)
+
+
+def send_frame(sock, window, data, raw=None):
+    """Send a frame, and wait for the reply.
+
+    `raw` is a `(width, height, pixel_format)` tuple for raw pixels, or `None` for an encoded
+    image.
+    """
+    name = window.encode("utf-8")
+    header = MAGIC + struct.pack(">H", len(name)) + name
+
+    if raw is None:
+        header += struct.pack(">B", ENCODED)
+    else:
+        width, height, pixel_format = raw
+        header += struct.pack(">BIIB", RAW, width, height, pixel_format)
+
+    sock.sendall(header + struct.pack(">I", len(data)) + data)
+
+    status, length = struct.unpack(">BI", recv_exact(sock, 5))
+    message = recv_exact(sock, length).decode("utf-8")
+
+    if status != 0:
+        raise RuntimeError(message)
+
+
+def recv_exact(sock, length):
+    data = b""
+    while len(data) < length:
+        chunk = sock.recv(length - len(data))
+        if not chunk:
+            raise ConnectionError("connection closed by the server")
+        data += chunk
+    return data
+
+
+def main():
+    with socket.create_connection(("127.0.0.1", 7878)) as sock:
+        if len(sys.argv) > 1:
+            with open(sys.argv[1], "rb") as image:
+                send_frame(sock, "file", image.read())
+
+        # A 64x64 red to blue gradient, as raw RGB pixels
+        width, height = 64, 64
+        pixels = bytes(
+            channel
+            for y in range(height)
+            for x in range(width)
+            for channel in (x * 4, 0, y * 4)
+        )
+        send_frame(sock, "gradient", pixels, raw=(width, height, RGB8))
+
+
+if __name__ == "__main__":
+    main()
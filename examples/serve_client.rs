@@ -0,0 +1,54 @@
+//! Pushes images to a running `miniview serve` instance.
+//!
+//! Start the server with `miniview serve --tcp 127.0.0.1:7878`, then run this example with
+//! `cargo run --example serve_client`.
+
+#[cfg(unix)]
+fn main() {
+    use miniview::serve::{self, Address, Frame, FrameImage, PixelFormat};
+
+    let address = Address::Tcp("127.0.0.1:7878".parse().unwrap());
+
+    // An encoded image, e.g. a PNG or JPEG file
+    let encoded = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/plant.jpg"))
+        .expect("unable to read image");
+
+    serve::send(
+        &address,
+        &Frame {
+            window: "plant".to_string(),
+            image: FrameImage::Encoded(encoded),
+        },
+    )
+    .expect("unable to show the encoded image");
+
+    // Raw pixels: a gradient, which is updated a few times
+    let (width, height) = (256, 128);
+
+    for step in 0..64u32 {
+        let data = (0..height)
+            .flat_map(|y| (0..width).flat_map(move |x| [x as u8, y as u8, (step * 4) as u8]))
+            .collect();
+
+        serve::send(
+            &address,
+            &Frame {
+                window: "gradient".to_string(),
+                image: FrameImage::Raw {
+                    width,
+                    height,
+                    format: PixelFormat::Rgb8,
+                    data,
+                },
+            },
+        )
+        .expect("unable to show the raw image");
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("miniview serve is only supported on Unix platforms");
+}
@@ -0,0 +1,85 @@
+//! Futures to show, and wait on, a `miniview` window.
+//!
+//! The futures are not tied to a specific async runtime: the window is still managed by its own
+//! thread, and the futures complete when this thread signals a change over a channel.
+
+use crate::config::Config;
+use crate::handle::ViewEvent;
+use crate::{run_backend, MVResult, MiniView, MiniViewError, MiniViewHandle};
+use futures_core::Stream;
+use std::thread;
+
+impl MiniView {
+    /// Create the controls to a new `miniview` window, and wait until the window has been opened
+    ///
+    /// Compared to [`show`], the image is loaded by the thread which manages the window, so the
+    /// calling task is not blocked while the image is being loaded.
+    ///
+    /// [`show`]: struct.MiniView.html#method.show
+    pub async fn show_async(config: Config) -> MVResult<Self> {
+        let drop_behavior = config.drop_behavior();
+
+        let (controls, receiver) = MiniViewHandle::new(&config);
+        let shared = controls.shared();
+        let opened = shared.opened();
+
+        let handle = thread::spawn(move || {
+            let _exit = shared.exit_guard();
+            let (img, metadata) = config.source().load(config.apply_orientation())?;
+            run_backend(config, img, metadata, receiver, shared)
+        });
+
+        let mut view = MiniView {
+            controls,
+            handle: Some(handle),
+            drop_behavior,
+        };
+
+        match opened.await {
+            Ok(()) => Ok(view),
+            // The thread exited without opening the window
+            Err(_canceled) => view.join().and(Err(MiniViewError::UnableToCreateWindow)),
+        }
+    }
+
+    /// Waits until the thread managing the graphical window returns
+    ///
+    /// This is the async counterpart of [`wait_for_exit`].
+    ///
+    /// [`wait_for_exit`]: struct.MiniView.html#method.wait_for_exit
+    pub async fn closed(mut self) -> MVResult<()> {
+        let _ = self.controls.shared().exited().await;
+
+        self.join()
+    }
+
+    /// Sends a 'close window' event to the thread managing the graphical window and waits for the
+    /// thread to return
+    ///
+    /// This is the async counterpart of [`close`].
+    ///
+    /// [`close`]: struct.MiniView.html#method.close
+    pub async fn close_async(self) -> MVResult<()> {
+        if self.handle.is_none() {
+            return Ok(());
+        }
+
+        self.controls.request_close()?;
+        self.closed().await
+    }
+}
+
+impl MiniViewHandle {
+    /// Completes once the window has been closed, and the thread managing the window has returned
+    pub async fn closed(&self) {
+        let _ = self.shared().exited().await;
+    }
+
+    /// A stream of the events of the window
+    ///
+    /// Only events which occur after subscribing are received. The stream ends once the window
+    /// has been closed.
+    pub fn events(&self) -> impl Stream<Item = ViewEvent> + Unpin + Send {
+        self.shared().subscribe()
+    }
+}
@@ -1,52 +1,253 @@
 use crate::config::Config;
-use crate::{Action, MVResult, MiniView, MiniViewError, ResizableWhen};
-use clap::crate_name;
+use crate::gallery::Gallery;
+use crate::handle::Shared;
+use crate::keys::Key;
+use crate::metadata::Metadata;
+use crate::view::View;
+use crate::window::{self, Flow, WindowControl};
+use crate::{Action, MVResult, MiniViewError, ResizableWhen};
+use imagecrate::DynamicImage;
+use piston_glutin::{GlutinWindow, UserEvent};
 use piston_window::{
-    Button, ButtonArgs, Event, EventLoop, G2dTexture, G2dTextureContext, GenericEvent, Input, Key,
-    Loop, PistonWindow, Texture, TextureSettings, Window, WindowSettings,
+    AdvancedWindow, Button, ButtonArgs, ButtonState, Event, EventLoop, G2dTexture,
+    G2dTextureContext, GenericEvent, ImageSize, Input, Key as PistonKey, Loop, OpenGL,
+    PistonWindow, Texture, TextureSettings, Window, WindowSettings,
 };
+use piston_winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use piston_winit::event_loop::{EventLoopBuilder, EventLoopProxy};
+use piston_winit::window::{Fullscreen, WindowBuilder};
+use raw_window_handle::HasRawWindowHandle;
 use std::fmt::{Debug, Formatter};
-use std::sync::mpsc;
-use std::thread;
+use std::sync::{mpsc, Arc, Mutex};
+
+/// Frames per second at which a lazy window is redrawn while nothing happens
+///
+/// Piston's lazy event loop only returns on input events, so it can't be woken up to handle
+/// actions from the controlling handles. Instead, the window runs at this low frame rate, and is
+/// woken up whenever it should be redrawn sooner.
+const IDLE_FPS: u64 = 1;
 
 pub(crate) struct ImageWindow {
     window: PistonWindow,
+    texture_context: G2dTextureContext,
+    texture: Option<G2dTexture>,
+    // Wakes up the event loop, so the window is redrawn without waiting for the next frame
+    proxy: EventLoopProxy<UserEvent>,
+    view: View,
+    gallery: Option<Gallery>,
 }
 
 impl ImageWindow {
-    pub fn try_new(config: &Config, size: [u32; 2]) -> MVResult<ImageWindow> {
-        let mut window: PistonWindow = WindowSettings::new(crate_name!(), size)
+    pub fn try_new(
+        config: &Config,
+        image: DynamicImage,
+        metadata: Metadata,
+    ) -> MVResult<ImageWindow> {
+        let view = View::new(image, metadata, config);
+        let (width, height) = view.image_size();
+
+        let gallery = Gallery::from_config(config);
+        let title = gallery
+            .as_ref()
+            .map(Gallery::title)
+            .unwrap_or_else(|| config.window_name().to_string());
+
+        // if window resizing is not enabled, when setting fullscreen to true, the window won't go
+        // into fullscreen mode
+        let resizable = config.fullscreen() || config.resizable_window();
+
+        let settings = WindowSettings::new(title.clone(), [width, height])
             .fullscreen(config.fullscreen())
-            .exit_on_esc(true)
-            .resizable_when(|| {
-                // if window resizing is not enabled, when setting fullscreen to true, the window won't go
-                // into fullscreen mode
-                config.fullscreen() || config.resizable_window()
-            })
-            .build()
+            // escape is handled by the key bindings instead
+            .exit_on_esc(false)
+            .resizable_when(|| resizable)
+            .srgb(true);
+
+        // The event loop is created here, rather than by the glutin window, to keep a proxy to it
+        let event_loop = EventLoopBuilder::with_user_event().build();
+        let proxy = event_loop.create_proxy();
+        let fullscreen = config
+            .fullscreen()
+            .then(|| Fullscreen::Borderless(event_loop.primary_monitor()));
+        let builder = WindowBuilder::new()
+            .with_title(title)
+            .with_inner_size(LogicalSize::new(width, height))
+            .with_resizable(resizable)
+            .with_fullscreen(fullscreen);
+
+        let glutin_window = GlutinWindow::from_raw(&settings, event_loop, builder)
             .map_err(|_| MiniViewError::UnableToCreateWindow)?;
+        let mut window: PistonWindow =
+            PistonWindow::new(OpenGL::V3_2, settings.get_samples(), glutin_window);
 
-        window.set_lazy(config.lazy_window());
+        if config.lazy_window() {
+            window.set_ups(0);
+            window.set_max_fps(IDLE_FPS);
+        }
+
+        let texture_context = window.create_texture_context();
 
-        Ok(Self { window })
+        Ok(Self {
+            window,
+            texture_context,
+            texture: None,
+            proxy,
+            view,
+            gallery,
+        })
+    }
+
+    /// A function which wakes up the event loop, so actions sent by the handles are received
+    /// while the window is idle
+    fn waker(&self) -> impl Fn() + Send {
+        let proxy = Mutex::new(self.proxy.clone());
+
+        move || {
+            if let Ok(proxy) = proxy.lock() {
+                let _ = proxy.send_event(UserEvent::WakeUp);
+            }
+        }
     }
 
     fn next(&mut self) -> Option<Event> {
         self.window.next()
     }
 
-    fn draw_image<E: GenericEvent>(&mut self, event: &E, texture: &G2dTexture) {
-        self.window.draw_2d(event, |c, g, _device| {
-            piston_window::image(texture, c.transform, g);
+    /// Draws the view, if the event is a render event, and returns whether a frame was drawn
+    fn draw_image<E: GenericEvent>(&mut self, event: &E) -> MVResult<bool> {
+        let Some(args) = event.render_args() else {
+            return Ok(false);
+        };
+
+        let [width, height] = args.draw_size;
+
+        // Nothing to draw into while the window is minimized
+        if width == 0 || height == 0 {
+            return Ok(false);
+        }
+
+        // The view is drawn at the size of the window, and uploaded only if it changed
+        if self.view.update_frame((width, height)) || self.texture.is_none() {
+            let frame = self.view.frame();
+
+            match &mut self.texture {
+                Some(texture) if texture.get_size() == (width, height) => texture
+                    .update(&mut self.texture_context, frame)
+                    .map_err(|_| MiniViewError::UnableToMapImage)?,
+                _ => {
+                    let texture = Texture::from_image(
+                        &mut self.texture_context,
+                        frame,
+                        &TextureSettings::new(),
+                    )
+                    .map_err(|_| MiniViewError::UnableToMapImage)?;
+
+                    self.texture = Some(texture);
+                }
+            }
+        }
+
+        let Some(texture) = &self.texture else {
+            return Ok(false);
+        };
+
+        let texture_context = &mut self.texture_context;
+        let [window_width, window_height] = args.window_size;
+
+        self.window.draw_2d(event, |c, g, device| {
+            texture_context.encoder.flush(device);
+
+            piston_window::Image::new()
+                .rect([0.0, 0.0, window_width, window_height])
+                .draw(texture, &c.draw_state, c.transform, g);
         });
+
+        Ok(true)
     }
 
     fn close_window(&mut self) {
         self.window.set_should_close(true);
     }
 
-    fn create_texture_context(&mut self) -> G2dTextureContext {
-        self.window.create_texture_context()
+    fn winit_window(&self) -> &piston_winit::window::Window {
+        &self.window.window.window
+    }
+}
+
+impl WindowControl for ImageWindow {
+    fn view(&self) -> &View {
+        &self.view
+    }
+
+    fn view_mut(&mut self) -> &mut View {
+        &mut self.view
+    }
+
+    fn request_redraw(&mut self) {
+        // Piston redraws the window at its next frame, or as soon as its event loop wakes up
+        let _ = self.proxy.send_event(UserEvent::WakeUp);
+    }
+
+    fn title(&self) -> String {
+        self.window.get_title()
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.window.set_title(title.to_string());
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.winit_window().fullscreen().is_some()
+    }
+
+    fn set_fullscreen(&mut self, value: bool) {
+        let fullscreen = if value {
+            Some(Fullscreen::Borderless(None))
+        } else {
+            None
+        };
+
+        self.winit_window().set_fullscreen(fullscreen);
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let size = self.winit_window().inner_size();
+
+        (size.width, size.height)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.winit_window()
+            .set_inner_size(PhysicalSize::new(width, height));
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        self.winit_window()
+            .set_outer_position(PhysicalPosition::new(x, y));
+    }
+
+    fn set_visible(&mut self, value: bool) {
+        self.winit_window().set_visible(value);
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.winit_window().scale_factor()
+    }
+
+    fn focused(&self) -> bool {
+        self.winit_window().has_focus()
+    }
+
+    fn focus(&mut self) {
+        self.winit_window().focus_window();
+    }
+
+    fn gallery_mut(&mut self) -> Option<&mut Gallery> {
+        self.gallery.as_mut()
+    }
+
+    fn window_id(&self) -> Option<u64> {
+        window::platform_window_id(self.winit_window().raw_window_handle())
     }
 }
 
@@ -66,59 +267,162 @@ impl ResizableWhen for WindowSettings {
     }
 }
 
-pub(crate) fn show(config: Config) -> MVResult<MiniView> {
-    let (sender, receiver) = mpsc::channel();
+fn to_key(key: PistonKey) -> Option<Key> {
+    let key = match key {
+        PistonKey::A => Key::A,
+        PistonKey::B => Key::B,
+        PistonKey::C => Key::C,
+        PistonKey::D => Key::D,
+        PistonKey::E => Key::E,
+        PistonKey::F => Key::F,
+        PistonKey::G => Key::G,
+        PistonKey::H => Key::H,
+        PistonKey::I => Key::I,
+        PistonKey::J => Key::J,
+        PistonKey::K => Key::K,
+        PistonKey::L => Key::L,
+        PistonKey::M => Key::M,
+        PistonKey::N => Key::N,
+        PistonKey::O => Key::O,
+        PistonKey::P => Key::P,
+        PistonKey::Q => Key::Q,
+        PistonKey::R => Key::R,
+        PistonKey::S => Key::S,
+        PistonKey::T => Key::T,
+        PistonKey::U => Key::U,
+        PistonKey::V => Key::V,
+        PistonKey::W => Key::W,
+        PistonKey::X => Key::X,
+        PistonKey::Y => Key::Y,
+        PistonKey::Z => Key::Z,
+        PistonKey::D0 | PistonKey::NumPad0 => Key::Key0,
+        PistonKey::D1 | PistonKey::NumPad1 => Key::Key1,
+        PistonKey::D2 | PistonKey::NumPad2 => Key::Key2,
+        PistonKey::D3 | PistonKey::NumPad3 => Key::Key3,
+        PistonKey::D4 | PistonKey::NumPad4 => Key::Key4,
+        PistonKey::D5 | PistonKey::NumPad5 => Key::Key5,
+        PistonKey::D6 | PistonKey::NumPad6 => Key::Key6,
+        PistonKey::D7 | PistonKey::NumPad7 => Key::Key7,
+        PistonKey::D8 | PistonKey::NumPad8 => Key::Key8,
+        PistonKey::D9 | PistonKey::NumPad9 => Key::Key9,
+        PistonKey::F1 => Key::F1,
+        PistonKey::F2 => Key::F2,
+        PistonKey::F3 => Key::F3,
+        PistonKey::F4 => Key::F4,
+        PistonKey::F5 => Key::F5,
+        PistonKey::F6 => Key::F6,
+        PistonKey::F7 => Key::F7,
+        PistonKey::F8 => Key::F8,
+        PistonKey::F9 => Key::F9,
+        PistonKey::F10 => Key::F10,
+        PistonKey::F11 => Key::F11,
+        PistonKey::F12 => Key::F12,
+        PistonKey::Escape => Key::Escape,
+        PistonKey::Space => Key::Space,
+        PistonKey::Return | PistonKey::NumPadEnter => Key::Enter,
+        PistonKey::Tab => Key::Tab,
+        PistonKey::Backspace => Key::Backspace,
+        PistonKey::Left => Key::Left,
+        PistonKey::Right => Key::Right,
+        PistonKey::Up => Key::Up,
+        PistonKey::Down => Key::Down,
+        PistonKey::PageUp => Key::PageUp,
+        PistonKey::PageDown => Key::PageDown,
+        PistonKey::Home => Key::Home,
+        PistonKey::End => Key::End,
+        PistonKey::Plus | PistonKey::NumPadPlus => Key::Plus,
+        PistonKey::Minus | PistonKey::NumPadMinus => Key::Minus,
+        PistonKey::Equals | PistonKey::NumPadEquals => Key::Equals,
+        PistonKey::Comma => Key::Comma,
+        PistonKey::Period => Key::Period,
+        PistonKey::Slash => Key::Slash,
+        PistonKey::LeftBracket => Key::LeftBracket,
+        PistonKey::RightBracket => Key::RightBracket,
+        _ => return None,
+    };
 
-    let source = config.source();
-    let img = source.open()?;
+    Some(key)
+}
 
-    let width = img.width();
-    let height = img.height();
-    let img = img.to_rgba8();
+pub(crate) fn run(
+    config: Config,
+    img: DynamicImage,
+    metadata: Metadata,
+    receiver: mpsc::Receiver<Action>,
+    shared: Arc<Shared>,
+) -> MVResult<()> {
+    let mut window = ImageWindow::try_new(&config, img, metadata)?;
 
-    let handle = thread::spawn(move || {
-        let mut window = ImageWindow::try_new(&config, [width, height])?;
+    // Wake up the event loop when an action is sent, so actions are also received while the
+    // window is idle
+    shared.set_waker(window.waker());
 
-        let texture = Texture::from_image(
-            &mut window.create_texture_context(),
-            &img,
-            &TextureSettings::new(),
-        )
-        .map_err(|_| MiniViewError::UnableToMapImage)?;
+    let _open = shared.open();
+    shared.set_state(window::state(&window));
 
-        loop {
-            if let Ok(action) = receiver.try_recv() {
-                return match action {
-                    Action::Close => {
-                        window.close_window();
-                        Ok(())
-                    }
-                };
+    // The first frame is presented once the buffers have been swapped after it was drawn
+    let mut drawn = false;
+    let mut ready = false;
+
+    loop {
+        // Handle actions received from the controlling handles, and redraw the window right away
+        // to show their effect
+        let mut handled = false;
+
+        while let Ok(action) = receiver.try_recv() {
+            if window::handle_action(&mut window, action, &shared)? == Flow::Exit {
+                window.close_window();
+                return Ok(());
             }
 
-            if let Some(event) = window.next() {
-                match event {
-                    Event::Input(Input::Close(_), _) => {
-                        window.close_window();
-                        return Ok(());
-                    }
-                    Event::Input(
-                        Input::Button(ButtonArgs {
-                            button: Button::Keyboard(Key::Escape),
-                            ..
-                        }),
-                        _,
-                    ) => {
+            handled = true;
+        }
+
+        if handled {
+            window.request_redraw();
+        }
+
+        let Some(event) = window.next() else {
+            return Ok(());
+        };
+
+        match event {
+            Event::Input(Input::Close(_), _) => {
+                window.close_window();
+                return Ok(());
+            }
+            Event::Input(
+                Input::Button(ButtonArgs {
+                    button: Button::Keyboard(key),
+                    state: ButtonState::Press,
+                    ..
+                }),
+                _,
+            ) => {
+                let command = to_key(key).and_then(|key| config.key_bindings().command(key));
+
+                if let Some(command) = command {
+                    if window::handle_command(&mut window, command) == Flow::Exit {
                         window.close_window();
                         return Ok(());
                     }
-                    Event::Loop(Loop::AfterRender(_)) => continue,
-                    _ => {}
                 }
-                window.draw_image(&event, &texture);
             }
+            // Input events, such as resizing the window, are shown right away rather than at the
+            // next frame
+            Event::Input(_, _) => window.request_redraw(),
+            Event::Loop(Loop::AfterRender(_)) => {
+                if drawn && !ready {
+                    ready = true;
+                    window::notify_ready(&window, &config, &shared);
+                }
+
+                continue;
+            }
+            _ => {}
         }
-    });
 
-    Ok(MiniView { sender, handle })
+        shared.set_state(window::state(&window));
+        drawn |= window.draw_image(&event)?;
+    }
 }
@@ -1,13 +1,20 @@
 use crate::config::Config;
-use crate::{Action, FullscreenWhen, MVResult, MiniView, MiniViewError};
-use clap::crate_name;
-use imagecrate::EncodableLayout;
+use crate::gallery::Gallery;
+use crate::handle::Shared;
+use crate::keys::Key;
+use crate::metadata::Metadata;
+use crate::view::View;
+use crate::window::{self, Flow, WindowControl};
+use crate::{Action, FullscreenWhen, MVResult, MiniViewError};
+use imagecrate::{DynamicImage, EncodableLayout};
 use pixels::{Pixels, SurfaceTexture};
+use raw_window_handle::HasRawWindowHandle;
 use std::fmt::{Debug, Formatter};
-use std::sync::mpsc;
-use std::thread;
-use winit::event::{Event, VirtualKeyCode};
+use std::sync::{mpsc, Arc, Mutex};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
+use winit::platform::run_return::EventLoopExtRunReturn;
 #[cfg(any(
     target_os = "linux",
     target_os = "dragonfly",
@@ -22,26 +29,163 @@ use winit::window::{Fullscreen, WindowBuilder};
 use winit_input_helper::WinitInputHelper;
 
 struct ImageWindow {
+    // The pixels surface is declared before the window, so it will be dropped before the window
+    pixels: Pixels,
+    buffer_size: (u32, u32),
     window: winit::window::Window,
+    view: View,
+    gallery: Option<Gallery>,
+    title: String,
+    focused: bool,
 }
 
 impl ImageWindow {
     pub fn try_new(
         config: &Config,
-        size: [u32; 2],
+        image: DynamicImage,
+        metadata: Metadata,
         event_loop: &EventLoop<()>,
     ) -> MVResult<ImageWindow> {
-        let size = winit::dpi::PhysicalSize::new(size[0] as f64, size[1] as f64);
+        let view = View::new(image, metadata, config);
+        let (width, height) = view.image_size();
+        let size = PhysicalSize::new(width as f64, height as f64);
+
+        let gallery = Gallery::from_config(config);
+        let title = gallery
+            .as_ref()
+            .map(Gallery::title)
+            .unwrap_or_else(|| config.window_name().to_string());
 
         let window = WindowBuilder::new()
-            .with_title(crate_name!())
+            .with_title(&title)
             .with_inner_size(size)
             .fullscreen_when(|| config.fullscreen())
             .with_resizable(config.resizable_window())
             .build(event_loop)
             .map_err(|_| MiniViewError::UnableToCreateWindow)?;
 
-        Ok(ImageWindow { window })
+        // The view is drawn at the size of the window, so the buffer matches the surface
+        let buffer_size = {
+            let window_size = window.inner_size();
+            (window_size.width.max(1), window_size.height.max(1))
+        };
+
+        let pixels = {
+            let surface_texture = SurfaceTexture::new(buffer_size.0, buffer_size.1, &window);
+            Pixels::new(buffer_size.0, buffer_size.1, surface_texture)
+                .map_err(|_| MiniViewError::UnableToMapImage)?
+        };
+
+        Ok(ImageWindow {
+            pixels,
+            buffer_size,
+            window,
+            view,
+            gallery,
+            title,
+            focused: true,
+        })
+    }
+
+    fn resize_surface(&mut self, width: u32, height: u32) {
+        self.pixels.resize_surface(width, height);
+    }
+
+    /// Draws the view, and returns whether the frame has been presented
+    fn draw(&mut self) -> bool {
+        let size = self.size();
+
+        // Nothing to draw into while the window is minimized
+        if size.0 == 0 || size.1 == 0 {
+            return false;
+        }
+
+        if self.buffer_size != size {
+            self.pixels.resize_buffer(size.0, size.1);
+            self.buffer_size = size;
+        }
+
+        self.view.update_frame(size);
+
+        let frame = self.pixels.get_frame_mut();
+        frame.copy_from_slice(self.view.frame().as_bytes());
+
+        self.pixels.render().is_ok()
+    }
+}
+
+impl WindowControl for ImageWindow {
+    fn view(&self) -> &View {
+        &self.view
+    }
+
+    fn view_mut(&mut self) -> &mut View {
+        &mut self.view
+    }
+
+    fn request_redraw(&mut self) {
+        self.window.request_redraw();
+    }
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+        self.title = title.to_string();
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.window.fullscreen().is_some()
+    }
+
+    fn set_fullscreen(&mut self, value: bool) {
+        let fullscreen = if value {
+            Some(Fullscreen::Borderless(None))
+        } else {
+            None
+        };
+
+        self.window.set_fullscreen(fullscreen);
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let size = self.window.inner_size();
+
+        (size.width, size.height)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.window.set_inner_size(PhysicalSize::new(width, height));
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        self.window.set_outer_position(PhysicalPosition::new(x, y));
+    }
+
+    fn set_visible(&mut self, value: bool) {
+        self.window.set_visible(value);
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.window.scale_factor()
+    }
+
+    fn focused(&self) -> bool {
+        self.focused
+    }
+
+    fn focus(&mut self) {
+        self.window.focus_window();
+    }
+
+    fn gallery_mut(&mut self) -> Option<&mut Gallery> {
+        self.gallery.as_mut()
+    }
+
+    fn window_id(&self) -> Option<u64> {
+        window::platform_window_id(self.window.raw_window_handle())
     }
 }
 
@@ -63,87 +207,201 @@ impl FullscreenWhen for WindowBuilder {
     }
 }
 
-pub(crate) fn show(config: Config) -> MVResult<MiniView> {
-    let (sender, receiver) = mpsc::channel();
-
-    let source = config.source();
-    let img = source.open()?;
-
-    let width = img.width();
-    let height = img.height();
-    let img = img.to_rgba8();
-
-    let handle = thread::spawn(move || {
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd",
-            target_os = "windows"
-        ))]
-        let event_loop = EventLoopBuilder::new().with_any_thread(true).build();
-
-        // FIXME: this will probably crash, since we explicitly start the event loop off thread.
-        //   As a result, macos is not supported for now
-        #[cfg(target_os = "macos")]
-        let event_loop = EventLoop::new();
-
-        let mut input = WinitInputHelper::new();
-        let image_window = ImageWindow::try_new(&config, [width, height], &event_loop)?;
-
-        let mut pixels = {
-            let window_size = image_window.window.inner_size();
-            let surface_texture =
-                SurfaceTexture::new(window_size.width, window_size.height, &image_window.window);
-            Pixels::new(width, height, surface_texture)
-                .map_err(|_| MiniViewError::UnableToMapImage)?
-        };
+fn to_key(code: VirtualKeyCode) -> Option<Key> {
+    let key = match code {
+        VirtualKeyCode::A => Key::A,
+        VirtualKeyCode::B => Key::B,
+        VirtualKeyCode::C => Key::C,
+        VirtualKeyCode::D => Key::D,
+        VirtualKeyCode::E => Key::E,
+        VirtualKeyCode::F => Key::F,
+        VirtualKeyCode::G => Key::G,
+        VirtualKeyCode::H => Key::H,
+        VirtualKeyCode::I => Key::I,
+        VirtualKeyCode::J => Key::J,
+        VirtualKeyCode::K => Key::K,
+        VirtualKeyCode::L => Key::L,
+        VirtualKeyCode::M => Key::M,
+        VirtualKeyCode::N => Key::N,
+        VirtualKeyCode::O => Key::O,
+        VirtualKeyCode::P => Key::P,
+        VirtualKeyCode::Q => Key::Q,
+        VirtualKeyCode::R => Key::R,
+        VirtualKeyCode::S => Key::S,
+        VirtualKeyCode::T => Key::T,
+        VirtualKeyCode::U => Key::U,
+        VirtualKeyCode::V => Key::V,
+        VirtualKeyCode::W => Key::W,
+        VirtualKeyCode::X => Key::X,
+        VirtualKeyCode::Y => Key::Y,
+        VirtualKeyCode::Z => Key::Z,
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Key::Key0,
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => Key::Key1,
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => Key::Key2,
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => Key::Key3,
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => Key::Key4,
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => Key::Key5,
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => Key::Key6,
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => Key::Key7,
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => Key::Key8,
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => Key::Key9,
+        VirtualKeyCode::F1 => Key::F1,
+        VirtualKeyCode::F2 => Key::F2,
+        VirtualKeyCode::F3 => Key::F3,
+        VirtualKeyCode::F4 => Key::F4,
+        VirtualKeyCode::F5 => Key::F5,
+        VirtualKeyCode::F6 => Key::F6,
+        VirtualKeyCode::F7 => Key::F7,
+        VirtualKeyCode::F8 => Key::F8,
+        VirtualKeyCode::F9 => Key::F9,
+        VirtualKeyCode::F10 => Key::F10,
+        VirtualKeyCode::F11 => Key::F11,
+        VirtualKeyCode::F12 => Key::F12,
+        VirtualKeyCode::Escape => Key::Escape,
+        VirtualKeyCode::Space => Key::Space,
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => Key::Enter,
+        VirtualKeyCode::Tab => Key::Tab,
+        VirtualKeyCode::Back => Key::Backspace,
+        VirtualKeyCode::Left => Key::Left,
+        VirtualKeyCode::Right => Key::Right,
+        VirtualKeyCode::Up => Key::Up,
+        VirtualKeyCode::Down => Key::Down,
+        VirtualKeyCode::PageUp => Key::PageUp,
+        VirtualKeyCode::PageDown => Key::PageDown,
+        VirtualKeyCode::Home => Key::Home,
+        VirtualKeyCode::End => Key::End,
+        VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => Key::Plus,
+        VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => Key::Minus,
+        VirtualKeyCode::Equals | VirtualKeyCode::NumpadEquals => Key::Equals,
+        VirtualKeyCode::Comma => Key::Comma,
+        VirtualKeyCode::Period => Key::Period,
+        VirtualKeyCode::Slash => Key::Slash,
+        VirtualKeyCode::LBracket => Key::LeftBracket,
+        VirtualKeyCode::RBracket => Key::RightBracket,
+        _ => return None,
+    };
 
-        event_loop.run(move |event, _target, control_flow| {
-            // Pause event loop to save cpu time and power
-            if config.lazy_window() {
-                *control_flow = ControlFlow::Wait;
-            }
+    Some(key)
+}
 
-            // Exit when receiving the Close action
-            if let Ok(action) = receiver.try_recv() {
-                match action {
-                    Action::Close => {
-                        *control_flow = ControlFlow::Exit;
-                        return;
-                    }
-                };
-            }
+pub(crate) fn run(
+    config: Config,
+    img: DynamicImage,
+    metadata: Metadata,
+    receiver: mpsc::Receiver<Action>,
+    shared: Arc<Shared>,
+) -> MVResult<()> {
+    // The event loop may be driven from any thread on these platforms, so both `MiniView::show`
+    // and `MiniView::run` can be used
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "windows"
+    ))]
+    let mut event_loop = EventLoopBuilder::with_user_event()
+        .with_any_thread(true)
+        .build();
+
+    // FIXME: this will crash when started from `MiniView::show`, since the event loop is then
+    //   explicitly started off the main thread. As a result, only `MiniView::run` can be used on
+    //   macos
+    #[cfg(target_os = "macos")]
+    let mut event_loop = EventLoopBuilder::with_user_event().build();
+
+    let mut input = WinitInputHelper::new();
+    let mut image_window = ImageWindow::try_new(&config, img, metadata, &event_loop)?;
+
+    // Wake up the event loop when an action is sent, so actions are also received when the
+    // event loop is paused
+    let proxy = Mutex::new(event_loop.create_proxy());
+    shared.set_waker(move || {
+        if let Ok(proxy) = proxy.lock() {
+            let _ = proxy.send_event(());
+        }
+    });
 
-            if input.update(&event) {
-                // Exit when either pressing escape or the close button
-                if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+    let _open = shared.open();
+    shared.set_state(window::state(&image_window));
+
+    let mut result = Ok(());
+    let mut ready = false;
+
+    event_loop.run_return(|event, _target, control_flow| {
+        // Pause event loop to save cpu time and power
+        if config.lazy_window() {
+            *control_flow = ControlFlow::Wait;
+        }
+
+        // Handle actions received from the controlling handles
+        while let Ok(action) = receiver.try_recv() {
+            match window::handle_action(&mut image_window, action, &shared) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Exit) => {
                     *control_flow = ControlFlow::Exit;
                     return;
                 }
-
-                // Resize
-                if let Some(size) = input.window_resized() {
-                    pixels.resize_surface(size.width, size.height);
+                Err(err) => {
+                    result = Err(err);
+                    *control_flow = ControlFlow::Exit;
+                    return;
                 }
+            }
+        }
+
+        if let Event::WindowEvent { event, .. } = &event {
+            match event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(code),
+                            ..
+                        },
+                    ..
+                } => {
+                    let command = to_key(*code).and_then(|key| config.key_bindings().command(key));
 
-                // Redraw on change
-                image_window.window.request_redraw();
+                    if let Some(command) = command {
+                        if window::handle_command(&mut image_window, command) == Flow::Exit {
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                    }
+                }
+                WindowEvent::Focused(focused) => image_window.focused = *focused,
+                _ => {}
             }
+        }
 
-            // Redraw the image, if requested
-            if let Event::RedrawRequested(_id) = event {
-                let frame = pixels.get_frame_mut();
-                frame.copy_from_slice(img.as_bytes());
+        if input.update(&event) {
+            // Exit when pressing the close button
+            if input.quit() {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
 
-                let _ = pixels.render();
+            // Resize
+            if let Some(size) = input.window_resized() {
+                image_window.resize_surface(size.width, size.height);
             }
-        });
 
-        #[allow(unreachable_code)]
-        Ok(())
+            shared.set_state(window::state(&image_window));
+
+            // Redraw on change
+            image_window.request_redraw();
+        }
+
+        // Redraw the image, if requested
+        if let Event::RedrawRequested(_id) = event {
+            if image_window.draw() && !ready {
+                ready = true;
+                window::notify_ready(&image_window, &config, &shared);
+            }
+        }
     });
 
-    Ok(MiniView { sender, handle })
+    result
 }
@@ -0,0 +1,322 @@
+//! Which channels of an image are shown, and in which order the channels of the image are read.
+
+use crate::errors::SettingsError;
+use imagecrate::Rgba;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A channel of an RGBA pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Channel::Red => 0,
+            Channel::Green => 1,
+            Channel::Blue => 2,
+            Channel::Alpha => 3,
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Channel::Red => 'r',
+            Channel::Green => 'g',
+            Channel::Blue => 'b',
+            Channel::Alpha => 'a',
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Channel> {
+        match letter.to_ascii_lowercase() {
+            'r' => Some(Channel::Red),
+            'g' => Some(Channel::Green),
+            'b' => Some(Channel::Blue),
+            'a' => Some(Channel::Alpha),
+            _ => None,
+        }
+    }
+}
+
+/// Which channels of the image are shown
+///
+/// Written as `all`, `red`, `green`, `blue`, `alpha` or `luminance`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub enum Channels {
+    /// Show the image as it is
+    #[default]
+    All,
+    /// Show a single channel, as greyscale, or tinted with the colour of the channel
+    Single(Channel),
+    /// Show the luminance of the image, as greyscale
+    Luminance,
+}
+
+impl Channels {
+    /// The pixel as it is shown; `tinted` shows a single colour channel in its own colour instead
+    /// of as greyscale
+    ///
+    /// A single channel is shown opaque, so the alpha channel does not hide the other channels.
+    pub(crate) fn apply(self, pixel: Rgba<u8>, tinted: bool) -> Rgba<u8> {
+        match self {
+            Channels::All => pixel,
+            Channels::Single(channel) => {
+                let value = pixel[channel.index()];
+
+                match channel {
+                    Channel::Red if tinted => Rgba([value, 0, 0, 255]),
+                    Channel::Green if tinted => Rgba([0, value, 0, 255]),
+                    Channel::Blue if tinted => Rgba([0, 0, value, 255]),
+                    _ => Rgba([value, value, value, 255]),
+                }
+            }
+            Channels::Luminance => {
+                // Rec. 709 luma coefficients
+                let [red, green, blue, alpha] = pixel.0;
+                let luminance = (0.2126 * f32::from(red)
+                    + 0.7152 * f32::from(green)
+                    + 0.0722 * f32::from(blue))
+                .round() as u8;
+
+                Rgba([luminance, luminance, luminance, alpha])
+            }
+        }
+    }
+
+    /// Show the given channels, or all channels if they were already shown
+    pub(crate) fn toggle(self, channels: Channels) -> Channels {
+        if self == channels {
+            Channels::All
+        } else {
+            channels
+        }
+    }
+}
+
+impl Display for Channels {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Channels::All => "all",
+            Channels::Single(Channel::Red) => "red",
+            Channels::Single(Channel::Green) => "green",
+            Channels::Single(Channel::Blue) => "blue",
+            Channels::Single(Channel::Alpha) => "alpha",
+            Channels::Luminance => "luminance",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Channels {
+    type Err = SettingsError;
+
+    /// Parses `all`, `red`, `green`, `blue`, `alpha` or `luminance`, or the first letter of a
+    /// colour channel
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "all" => Ok(Channels::All),
+            "red" | "r" => Ok(Channels::Single(Channel::Red)),
+            "green" | "g" => Ok(Channels::Single(Channel::Green)),
+            "blue" | "b" => Ok(Channels::Single(Channel::Blue)),
+            "alpha" | "a" => Ok(Channels::Single(Channel::Alpha)),
+            "luminance" => Ok(Channels::Luminance),
+            _ => Err(SettingsError::InvalidChannels(value.to_string())),
+        }
+    }
+}
+
+impl From<Channels> for String {
+    fn from(channels: Channels) -> Self {
+        channels.to_string()
+    }
+}
+
+impl TryFrom<String> for Channels {
+    type Error = SettingsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// The order in which the channels of the image are read, e.g. to show an image of which the
+/// buffer was stored in BGR order
+///
+/// Written as the channels which are shown as red, green, blue and alpha, e.g. `bgra` or `bgr`;
+/// when the alpha channel is left out, it is read as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Swizzle([Channel; 4]);
+
+impl Swizzle {
+    /// Read the channels as they are
+    pub const RGBA: Swizzle =
+        Swizzle([Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha]);
+
+    /// Swap the red and blue channels
+    pub const BGRA: Swizzle =
+        Swizzle([Channel::Blue, Channel::Green, Channel::Red, Channel::Alpha]);
+
+    /// Read the channels which are shown as red, green, blue and alpha respectively
+    pub fn new(channels: [Channel; 4]) -> Self {
+        Swizzle(channels)
+    }
+
+    /// The channel of the image which is read as the given channel
+    pub(crate) fn source(self, channel: Channel) -> Channel {
+        self.0[channel.index()]
+    }
+
+    pub(crate) fn apply(self, pixel: Rgba<u8>) -> Rgba<u8> {
+        let [red, green, blue, alpha] = self.0.map(|channel| pixel[channel.index()]);
+
+        Rgba([red, green, blue, alpha])
+    }
+}
+
+impl Default for Swizzle {
+    fn default() -> Self {
+        Swizzle::RGBA
+    }
+}
+
+impl Display for Swizzle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0
+            .iter()
+            .try_for_each(|channel| write!(f, "{}", channel.letter()))
+    }
+}
+
+impl FromStr for Swizzle {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || SettingsError::InvalidSwizzle(value.to_string());
+
+        let mut channels = value
+            .chars()
+            .map(Channel::from_letter)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        if channels.len() == 3 {
+            channels.push(Channel::Alpha);
+        }
+
+        channels.try_into().map(Swizzle).map_err(|_| invalid())
+    }
+}
+
+impl From<Swizzle> for String {
+    fn from(swizzle: Swizzle) -> Self {
+        swizzle.to_string()
+    }
+}
+
+impl TryFrom<String> for Swizzle {
+    type Error = SettingsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channel, Channels, Swizzle};
+    use imagecrate::Rgba;
+
+    const PIXEL: Rgba<u8> = Rgba([200, 100, 50, 128]);
+
+    #[test]
+    fn show_all_channels() {
+        assert_eq!(Channels::All.apply(PIXEL, false), PIXEL);
+        assert_eq!(Channels::All.apply(PIXEL, true), PIXEL);
+    }
+
+    #[test]
+    fn isolate_channel_as_greyscale() {
+        let isolate = |channel| Channels::Single(channel).apply(PIXEL, false);
+
+        assert_eq!(isolate(Channel::Red), Rgba([200, 200, 200, 255]));
+        assert_eq!(isolate(Channel::Green), Rgba([100, 100, 100, 255]));
+        assert_eq!(isolate(Channel::Blue), Rgba([50, 50, 50, 255]));
+        assert_eq!(isolate(Channel::Alpha), Rgba([128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn isolate_channel_tinted() {
+        let isolate = |channel| Channels::Single(channel).apply(PIXEL, true);
+
+        assert_eq!(isolate(Channel::Red), Rgba([200, 0, 0, 255]));
+        assert_eq!(isolate(Channel::Green), Rgba([0, 100, 0, 255]));
+        assert_eq!(isolate(Channel::Blue), Rgba([0, 0, 50, 255]));
+
+        // the alpha channel has no colour of its own
+        assert_eq!(isolate(Channel::Alpha), Rgba([128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn luminance() {
+        // 0.2126 * 200 + 0.7152 * 100 + 0.0722 * 50 = 117.65; the alpha channel is kept
+        assert_eq!(
+            Channels::Luminance.apply(PIXEL, false),
+            Rgba([118, 118, 118, 128])
+        );
+        assert_eq!(
+            Channels::Luminance.apply(PIXEL, true),
+            Rgba([118, 118, 118, 128])
+        );
+        assert_eq!(
+            Channels::Luminance.apply(Rgba([255, 255, 255, 255]), false),
+            Rgba([255, 255, 255, 255])
+        );
+    }
+
+    #[test]
+    fn swizzle_order() {
+        assert_eq!(Swizzle::RGBA.apply(PIXEL), PIXEL);
+        assert_eq!(Swizzle::BGRA.apply(PIXEL), Rgba([50, 100, 200, 128]));
+        assert_eq!(
+            "argb".parse::<Swizzle>().unwrap().apply(PIXEL),
+            Rgba([128, 200, 100, 50])
+        );
+        assert_eq!(
+            "bgr".parse::<Swizzle>().unwrap().apply(PIXEL),
+            Rgba([50, 100, 200, 128])
+        );
+    }
+
+    #[test]
+    fn swizzle_before_isolating_channel() {
+        // the red channel of the swizzled pixel is the blue channel of the image
+        let swizzled = Swizzle::BGRA.apply(PIXEL);
+
+        assert_eq!(
+            Channels::Single(Channel::Red).apply(swizzled, true),
+            Rgba([50, 0, 0, 255])
+        );
+    }
+}
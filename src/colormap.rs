@@ -0,0 +1,266 @@
+//! Colormaps, which show the values of a single channel as colours.
+
+use crate::errors::SettingsError;
+use imagecrate::Rgba;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// Colours sampled at evenly spaced positions from the colormaps of matplotlib
+const VIRIDIS: &[u32] = &[
+    0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725,
+];
+const MAGMA: &[u32] = &[
+    0x000004, 0x1c1044, 0x4f127b, 0x812581, 0xb5367a, 0xe55064, 0xfb8761, 0xfec287, 0xfcfdbf,
+];
+const INFERNO: &[u32] = &[
+    0x000004, 0x1f0c48, 0x550f6d, 0x88226a, 0xba3655, 0xe35933, 0xf98e09, 0xf9cb35, 0xfcffa4,
+];
+const COOLWARM: &[u32] = &[0x3b4cc0, 0x8db0fe, 0xdddddd, 0xf49a7b, 0xb40426];
+const RDBU: &[u32] = &[
+    0x67001f, 0xb2182b, 0xd6604d, 0xf4a582, 0xfddbc7, 0xf7f7f7, 0xd1e5f0, 0x92c5de, 0x4393c3,
+    0x2166ac, 0x053061,
+];
+
+/// How the values of a single channel are shown
+///
+/// A colormap is applied when a single channel is shown: for greyscale images, and when a single
+/// channel or the luminance of an image is shown. The values within the range of the tone mapping
+/// are mapped from the first to the last colour of the colormap.
+///
+/// Written as `greyscale`, `viridis`, `magma`, `inferno`, `turbo`, `coolwarm` or `rdbu`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Colormap {
+    /// Show values as shades of grey
+    #[default]
+    #[cfg_attr(feature = "serde", serde(alias = "grayscale"))]
+    Greyscale,
+    /// The perceptually uniform colormap from dark blue, through green, to yellow
+    Viridis,
+    /// The perceptually uniform colormap from black, through purple, to light yellow
+    Magma,
+    /// The perceptually uniform colormap from black, through red, to yellow
+    Inferno,
+    /// Google's rainbow colormap, from dark blue, through green and yellow, to dark red
+    Turbo,
+    /// The diverging colormap from blue, through light grey, to red, for signed data
+    Coolwarm,
+    /// The diverging colormap from red, through white, to blue, for signed data
+    #[cfg_attr(feature = "serde", serde(rename = "rdbu"))]
+    RdBu,
+}
+
+impl Colormap {
+    /// The colour of the given value, between `0.0` and `1.0`
+    pub(crate) fn colour(self, value: f32) -> Rgba<u8> {
+        let value = if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+
+        match self {
+            Colormap::Greyscale => {
+                let value = (value * 255.0).round() as u8;
+                Rgba([value, value, value, 255])
+            }
+            Colormap::Viridis => interpolate(VIRIDIS, value),
+            Colormap::Magma => interpolate(MAGMA, value),
+            Colormap::Inferno => interpolate(INFERNO, value),
+            Colormap::Turbo => turbo(value),
+            Colormap::Coolwarm => interpolate(COOLWARM, value),
+            Colormap::RdBu => interpolate(RDBU, value),
+        }
+    }
+
+    /// The colours of the 256 values which can be displayed
+    pub(crate) fn table(self) -> [Rgba<u8>; 256] {
+        let mut table = [Rgba([0, 0, 0, 255]); 256];
+
+        for (value, colour) in table.iter_mut().enumerate() {
+            *colour = self.colour(value as f32 / 255.0);
+        }
+
+        table
+    }
+
+    /// The next colormap, to cycle through the colormaps
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Colormap::Greyscale => Colormap::Viridis,
+            Colormap::Viridis => Colormap::Magma,
+            Colormap::Magma => Colormap::Inferno,
+            Colormap::Inferno => Colormap::Turbo,
+            Colormap::Turbo => Colormap::Coolwarm,
+            Colormap::Coolwarm => Colormap::RdBu,
+            Colormap::RdBu => Colormap::Greyscale,
+        }
+    }
+}
+
+/// Linearly interpolate between the evenly spaced colours
+fn interpolate(colours: &[u32], value: f32) -> Rgba<u8> {
+    let position = value * (colours.len() - 1) as f32;
+    let index = (position.floor() as usize).min(colours.len() - 2);
+    let fraction = position - index as f32;
+
+    let channel = |colour: u32, shift: u32| ((colour >> shift) & 0xff) as f32;
+    let mix = |shift: u32| {
+        let from = channel(colours[index], shift);
+        let to = channel(colours[index + 1], shift);
+
+        (from + (to - from) * fraction).round() as u8
+    };
+
+    Rgba([mix(16), mix(8), mix(0), 255])
+}
+
+/// Anton Mikhailov's polynomial approximation of the turbo colormap
+fn turbo(value: f32) -> Rgba<u8> {
+    let polynomial = |coefficients: [f32; 6]| {
+        let result = coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |result, coefficient| result * value + coefficient);
+
+        (result.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+
+    Rgba([
+        polynomial([
+            0.135_721_38,
+            4.615_392_6,
+            -42.660_324,
+            132.131_08,
+            -152.942_4,
+            59.286_38,
+        ]),
+        polynomial([
+            0.091_402_61,
+            2.194_188_4,
+            4.842_966_6,
+            -14.185_033,
+            4.277_298_5,
+            2.829_566,
+        ]),
+        polynomial([
+            0.106_673_3,
+            12.641_946,
+            -60.582_047,
+            110.362_77,
+            -89.903_11,
+            27.348_25,
+        ]),
+        255,
+    ])
+}
+
+impl Display for Colormap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Colormap::Greyscale => "greyscale",
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::Inferno => "inferno",
+            Colormap::Turbo => "turbo",
+            Colormap::Coolwarm => "coolwarm",
+            Colormap::RdBu => "rdbu",
+        })
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "greyscale" | "grayscale" => Ok(Colormap::Greyscale),
+            "viridis" => Ok(Colormap::Viridis),
+            "magma" => Ok(Colormap::Magma),
+            "inferno" => Ok(Colormap::Inferno),
+            "turbo" => Ok(Colormap::Turbo),
+            "coolwarm" => Ok(Colormap::Coolwarm),
+            "rdbu" => Ok(Colormap::RdBu),
+            _ => Err(SettingsError::InvalidColormap(value.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Colormap;
+    use imagecrate::Rgba;
+
+    /// The colours of the values 0, 128 and 255 in the table of the colormap
+    fn samples(colormap: Colormap) -> [Rgba<u8>; 3] {
+        let table = colormap.table();
+
+        [table[0], table[128], table[255]]
+    }
+
+    fn rgb(colour: u32) -> Rgba<u8> {
+        Rgba([(colour >> 16) as u8, (colour >> 8) as u8, colour as u8, 255])
+    }
+
+    #[test]
+    fn greyscale() {
+        assert_eq!(
+            samples(Colormap::Greyscale),
+            [rgb(0x000000), rgb(0x808080), rgb(0xffffff)]
+        );
+    }
+
+    #[test]
+    fn sequential_colormaps() {
+        // the endpoints are the first and last colours of the colormaps of matplotlib
+        assert_eq!(
+            samples(Colormap::Viridis),
+            [rgb(0x440154), Rgba([33, 145, 140, 255]), rgb(0xfde725)]
+        );
+        assert_eq!(
+            samples(Colormap::Magma),
+            [rgb(0x000004), Rgba([182, 54, 122, 255]), rgb(0xfcfdbf)]
+        );
+        assert_eq!(
+            samples(Colormap::Inferno),
+            [rgb(0x000004), Rgba([187, 55, 84, 255]), rgb(0xfcffa4)]
+        );
+    }
+
+    #[test]
+    fn turbo() {
+        let [low, _, high] = samples(Colormap::Turbo);
+
+        // dark blue to dark red
+        assert_eq!(low, Rgba([35, 23, 27, 255]));
+        assert_eq!(high, Rgba([144, 13, 0, 255]));
+    }
+
+    #[test]
+    fn diverging_colormaps() {
+        // the middle is close to the neutral centre colour
+        assert_eq!(
+            samples(Colormap::Coolwarm),
+            [rgb(0x3b4cc0), Rgba([221, 220, 220, 255]), rgb(0xb40426)]
+        );
+        assert_eq!(
+            samples(Colormap::RdBu),
+            [rgb(0x67001f), Rgba([246, 247, 247, 255]), rgb(0x053061)]
+        );
+    }
+
+    #[test]
+    fn values_outside_of_the_range() {
+        for colormap in [Colormap::Greyscale, Colormap::Viridis, Colormap::Turbo] {
+            let table = colormap.table();
+
+            assert_eq!(colormap.colour(-1.0), table[0]);
+            assert_eq!(colormap.colour(f32::NAN), table[0]);
+            assert_eq!(colormap.colour(2.0), table[255]);
+        }
+    }
+}
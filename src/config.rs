@@ -6,19 +6,63 @@
 //! [`ConfigBuilder`]: struct.ConfigBuilder.html
 //! [`show`]: ../struct.MiniView.html#method.show
 
-use crate::Source;
+use crate::errors::SettingsError;
+use crate::keys::KeyBindings;
+use crate::settings::Settings;
+use crate::view::{DEFAULT_BACKGROUND, DEFAULT_PIXEL_GRID_THRESHOLD};
+use crate::{
+    Channels, Colormap, ColourProfile, Filter, Grid, HistogramScale, MVResult, Rotation, Source,
+    Swizzle, ToneMapOperator, ToneMapping, Transparency, ValueRange, ViewTransform, WindowReady,
+    Zoom,
+};
+use imagecrate::Rgba;
 use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A function which is called once the first frame of a window has been presented
+pub type ReadyCallback = Arc<dyn Fn(&WindowReady) + Send + Sync>;
 
 /// Configuration which can be [`provided`] to a miniview window controlling instance which enables
 /// different program behaviours
 ///
+/// With the `serde` feature, a configuration can be serialized and deserialized. The serialized
+/// form has a `version` field, and unknown fields are rejected. The [`on_ready`] callback is not
+/// serialized.
+///
 /// [`provided`]: ../struct.MiniView.html#method.show
+/// [`on_ready`]: struct.ConfigBuilder.html#method.on_ready
 pub struct Config {
     source: Source,
     fullscreen: bool,
     resizable_window: bool,
     lazy_window: bool,
-    window_name: &'static str,
+    window_name: String,
+    drop_behavior: DropBehavior,
+    on_ready: Option<ReadyCallback>,
+    background: Rgba<u8>,
+    transparency: Transparency,
+    channels: Channels,
+    tinted: bool,
+    swizzle: Swizzle,
+    tone_mapping: ToneMapping,
+    colormap: Colormap,
+    colour_bar: bool,
+    histogram: bool,
+    histogram_scale: HistogramScale,
+    info: bool,
+    apply_orientation: bool,
+    colour_management: bool,
+    output_profile: Option<ColourProfile>,
+    transform: ViewTransform,
+    filter: Filter,
+    pixel_grid: bool,
+    pixel_grid_threshold: f32,
+    grid: Option<Grid>,
+    ruler: bool,
+    initial_zoom: Zoom,
+    backend: Option<Backend>,
+    key_bindings: KeyBindings,
 }
 
 impl Config {
@@ -50,14 +94,147 @@ impl Config {
     }
 
     pub fn window_name(&self) -> &str {
-        self.window_name
+        &self.window_name
+    }
+
+    /// What happens with the window when its [`MiniView`] controls are dropped
+    ///
+    /// [`MiniView`]: ../struct.MiniView.html
+    pub fn drop_behavior(&self) -> DropBehavior {
+        self.drop_behavior
+    }
+
+    /// The function which is called once the first frame of the window has been presented
+    pub fn on_ready(&self) -> Option<&ReadyCallback> {
+        self.on_ready.as_ref()
+    }
+
+    /// Colour of the area of the window which is not covered by the image
+    pub fn background(&self) -> Rgba<u8> {
+        self.background
+    }
+
+    /// What transparent pixels of the image are composited over
+    pub fn transparency(&self) -> Transparency {
+        self.transparency
+    }
+
+    /// Which channels of the image are shown
+    pub fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Whether a single colour channel is shown in its own colour, instead of as greyscale
+    pub fn tinted(&self) -> bool {
+        self.tinted
+    }
+
+    /// The order in which the channels of the image are read
+    pub fn swizzle(&self) -> Swizzle {
+        self.swizzle
+    }
+
+    /// How the values of the image are mapped to the values which are displayed
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.tone_mapping
+    }
+
+    /// The colormap with which single channels are shown
+    pub fn colormap(&self) -> Colormap {
+        self.colormap
+    }
+
+    /// Whether the colour bar is shown
+    pub fn colour_bar(&self) -> bool {
+        self.colour_bar
+    }
+
+    /// Whether the histogram is shown
+    pub fn histogram(&self) -> bool {
+        self.histogram
+    }
+
+    /// The scale of the heights of the bars of the histogram
+    pub fn histogram_scale(&self) -> HistogramScale {
+        self.histogram_scale
+    }
+
+    /// Whether the info panel is shown
+    pub fn info(&self) -> bool {
+        self.info
+    }
+
+    /// Whether images are transformed according to their EXIF orientation
+    pub fn apply_orientation(&self) -> bool {
+        self.apply_orientation
+    }
+
+    /// Whether the colours of images with an embedded colour profile are converted to the colours
+    /// of the output profile, rather than shown as the raw values
+    pub fn colour_management(&self) -> bool {
+        self.colour_management
+    }
+
+    /// The colour profile of the display, to which colours are converted; sRGB if `None`
+    pub fn output_profile(&self) -> Option<&ColourProfile> {
+        self.output_profile.as_ref()
+    }
+
+    /// How the image is rotated and flipped when it is shown
+    pub fn transform(&self) -> ViewTransform {
+        self.transform
+    }
+
+    /// How the image is sampled when it is shown scaled up or down
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// Whether a grid around the pixels is shown, when zoomed in beyond the
+    /// [`pixel_grid_threshold`]
+    ///
+    /// [`pixel_grid_threshold`]: struct.Config.html#method.pixel_grid_threshold
+    pub fn pixel_grid(&self) -> bool {
+        self.pixel_grid
+    }
+
+    /// The zoom factor beyond which the pixel grid is shown
+    pub fn pixel_grid_threshold(&self) -> f32 {
+        self.pixel_grid_threshold
+    }
+
+    /// The grid of blocks of pixels which is shown, if any
+    pub fn grid(&self) -> Option<Grid> {
+        self.grid
+    }
+
+    /// Whether rulers with the coordinates of the image are shown along the edges of the window
+    pub fn ruler(&self) -> bool {
+        self.ruler
+    }
+
+    /// The zoom level at which an image is first shown
+    pub fn initial_zoom(&self) -> Zoom {
+        self.initial_zoom
+    }
+
+    /// The backend which is required to show the window, if any
+    ///
+    /// Backends are selected at compile time; if this backend was not compiled in, the window
+    /// can't be shown.
+    pub fn backend(&self) -> Option<Backend> {
+        self.backend
+    }
+
+    pub(crate) fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
     }
 }
 
 impl Debug for Config {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("Config(source = {:?}, fullscreen = {:?}, resizable_window = {:?}, window_name = {:?}, ...)",
-                             self.source, self.fullscreen, self.resizable_window, self.window_name))
+        f.write_str(&format!("Config(source = {:?}, fullscreen = {:?}, resizable_window = {:?}, window_name = {:?}, drop_behavior = {:?}, background = {:?}, transparency = {:?}, channels = {}, tinted = {:?}, swizzle = {}, tone_mapping = {:?}, colormap = {}, colour_bar = {}, histogram = {}, histogram_scale = {}, info = {}, apply_orientation = {}, colour_management = {}, output_profile = {:?}, transform = {:?}, filter = {:?}, pixel_grid = {}, pixel_grid_threshold = {}, grid = {:?}, ruler = {}, initial_zoom = {:?}, backend = {:?}, ...)",
+                             self.source, self.fullscreen, self.resizable_window, self.window_name, self.drop_behavior, self.background, self.transparency, self.channels, self.tinted, self.swizzle, self.tone_mapping, self.colormap, self.colour_bar, self.histogram, self.histogram_scale, self.info, self.apply_orientation, self.colour_management, self.output_profile, self.transform, self.filter, self.pixel_grid, self.pixel_grid_threshold, self.grid, self.ruler, self.initial_zoom, self.backend))
     }
 }
 
@@ -80,11 +257,53 @@ impl ConfigBuilder {
                 fullscreen: false,
                 resizable_window: false,
                 lazy_window: false,
-                window_name: "miniview",
+                window_name: String::from("miniview"),
+                drop_behavior: DropBehavior::default(),
+                on_ready: None,
+                background: DEFAULT_BACKGROUND,
+                transparency: Transparency::default(),
+                channels: Channels::default(),
+                tinted: false,
+                swizzle: Swizzle::default(),
+                tone_mapping: ToneMapping::default(),
+                colormap: Colormap::default(),
+                colour_bar: false,
+                histogram: false,
+                histogram_scale: HistogramScale::default(),
+                info: false,
+                apply_orientation: true,
+                colour_management: true,
+                output_profile: None,
+                transform: ViewTransform::default(),
+                filter: Filter::default(),
+                pixel_grid: true,
+                pixel_grid_threshold: DEFAULT_PIXEL_GRID_THRESHOLD,
+                grid: None,
+                ruler: false,
+                initial_zoom: Zoom::default(),
+                backend: None,
+                key_bindings: KeyBindings::default(),
             },
         }
     }
 
+    /// Create a builder from the provided [`source`], with the defaults preferred by the user
+    ///
+    /// The defaults are loaded from the `miniview/config.toml` file in the user's configuration
+    /// directory (`$XDG_CONFIG_HOME`, `~/.config`, or `%APPDATA%` on Windows), or from the file
+    /// at `$MINIVIEW_CONFIG` if set, and then from the `MINIVIEW_*` environment variables, which
+    /// take precedence over the file. A missing file is not an error. Values set on the builder
+    /// take precedence over both.
+    ///
+    /// See the readme for the supported settings.
+    ///
+    /// [`source`]: ../enum.Source.html
+    pub fn from_env_and_file(source: Source) -> MVResult<Self> {
+        let settings = Settings::from_env_and_file()?;
+
+        Ok(settings.apply(Self::new(source)))
+    }
+
     /// Creates a builder from the provided path
     ///
     /// Path should point to an image on the filesystem.
@@ -124,13 +343,546 @@ impl ConfigBuilder {
     }
 
     /// Title of the window; useful when trying to capture the window from another program.
-    pub fn window_name(mut self, value: &'static str) -> Self {
-        self.config.window_name = value;
+    pub fn window_name<S: Into<String>>(mut self, value: S) -> Self {
+        self.config.window_name = value.into();
+        self
+    }
+
+    /// What happens with the window when its [`MiniView`] controls are dropped
+    ///
+    /// [`MiniView`]: ../struct.MiniView.html
+    pub fn drop_behavior(mut self, value: DropBehavior) -> Self {
+        self.config.drop_behavior = value;
+        self
+    }
+
+    /// Call the given function once the first frame of the window has been presented, e.g. to
+    /// notify external tooling which captures the window
+    ///
+    /// The function is called on the thread which drives the window.
+    pub fn on_ready<F: Fn(&WindowReady) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.config.on_ready = Some(Arc::new(callback));
+        self
+    }
+
+    /// Colour of the area of the window which is not covered by the image; black by default
+    pub fn background(mut self, value: Rgba<u8>) -> Self {
+        self.config.background = value;
+        self
+    }
+
+    /// What transparent pixels of the image are composited over; a checkerboard by default
+    ///
+    /// While the window is shown, the `t` key cycles through alternatives.
+    pub fn transparency(mut self, value: Transparency) -> Self {
+        self.config.transparency = value;
+        self
+    }
+
+    /// Which channels of the image are shown; all channels by default
+    ///
+    /// While the window is shown, the `r`, `g`, `b`, `a` and `l` keys toggle showing a single
+    /// channel or the luminance.
+    pub fn channels(mut self, value: Channels) -> Self {
+        self.config.channels = value;
+        self
+    }
+
+    /// Show a single colour channel in its own colour, instead of as greyscale
+    pub fn tinted(mut self, value: bool) -> Self {
+        self.config.tinted = value;
+        self
+    }
+
+    /// The order in which the channels of the image are read, e.g. [`Swizzle::BGRA`] for images
+    /// of which the buffer was stored in BGR order
+    ///
+    /// [`Swizzle::BGRA`]: ../struct.Swizzle.html#associatedconstant.BGRA
+    pub fn swizzle(mut self, value: Swizzle) -> Self {
+        self.config.swizzle = value;
         self
     }
 
+    /// How the values of the image are mapped to the values which are displayed, e.g. to show
+    /// images with a high bit depth or HDR images; images are shown as they are by default
+    ///
+    /// While the window is shown, the exposure, gamma, range and operator can be adjusted with
+    /// keys.
+    pub fn tone_mapping(mut self, value: ToneMapping) -> Self {
+        self.config.tone_mapping = value;
+        self
+    }
+
+    /// Which values of the image are mapped to the displayed range; see [`tone_mapping`]
+    ///
+    /// [`tone_mapping`]: struct.ConfigBuilder.html#method.tone_mapping
+    pub fn value_range(mut self, value: ValueRange) -> Self {
+        self.config.tone_mapping.range = value;
+        self
+    }
+
+    /// The exposure in stops, by which values are scaled before they are displayed; see
+    /// [`tone_mapping`]
+    ///
+    /// [`tone_mapping`]: struct.ConfigBuilder.html#method.tone_mapping
+    pub fn exposure(mut self, value: f32) -> Self {
+        self.config.tone_mapping.exposure = value;
+        self
+    }
+
+    /// The gamma with which values are displayed; see [`tone_mapping`]
+    ///
+    /// [`tone_mapping`]: struct.ConfigBuilder.html#method.tone_mapping
+    pub fn gamma(mut self, value: f32) -> Self {
+        self.config.tone_mapping.gamma = value;
+        self
+    }
+
+    /// How values outside of the displayed range are shown; see [`tone_mapping`]
+    ///
+    /// [`tone_mapping`]: struct.ConfigBuilder.html#method.tone_mapping
+    pub fn tone_map_operator(mut self, value: ToneMapOperator) -> Self {
+        self.config.tone_mapping.operator = value;
+        self
+    }
+
+    /// The colormap with which single channels are shown: greyscale images, and a single channel
+    /// or the luminance of an image; greyscale by default
+    ///
+    /// While the window is shown, the colormap can be cycled through with a key.
+    pub fn colormap(mut self, value: Colormap) -> Self {
+        self.config.colormap = value;
+        self
+    }
+
+    /// Show a colour bar along the right edge of the window, which shows the values of the range
+    /// of the tone mapping and the colours they are shown as; hidden by default
+    pub fn show_colour_bar(mut self, value: bool) -> Self {
+        self.config.colour_bar = value;
+        self
+    }
+
+    /// Show the histogram of the channels which are shown in the bottom left corner of the window,
+    /// with markers for values which are clipped; hidden by default
+    ///
+    /// The histogram is updated when the image is replaced.
+    pub fn show_histogram(mut self, value: bool) -> Self {
+        self.config.histogram = value;
+        self
+    }
+
+    /// The scale of the heights of the bars of the histogram; linear by default
+    pub fn histogram_scale(mut self, value: HistogramScale) -> Self {
+        self.config.histogram_scale = value;
+        self
+    }
+
+    /// Show the info panel in the top left corner of the window, with the dimensions of the image
+    /// and its key EXIF fields: the camera, the exposure, when and where it was taken; hidden by
+    /// default
+    pub fn show_info(mut self, value: bool) -> Self {
+        self.config.info = value;
+        self
+    }
+
+    /// Transform images according to their EXIF orientation, so photos are shown upright; on by
+    /// default
+    pub fn apply_orientation(mut self, value: bool) -> Self {
+        self.config.apply_orientation = value;
+        self
+    }
+
+    /// Convert the colours of images with an embedded ICC profile, such as Display P3 or Adobe
+    /// RGB, to the colours of the [`output_profile`]; on by default
+    ///
+    /// When off, the raw values of the image are shown, as if they were sRGB. While the window is
+    /// shown, colour management can be toggled with a key.
+    ///
+    /// [`output_profile`]: struct.ConfigBuilder.html#method.output_profile
+    pub fn colour_management(mut self, value: bool) -> Self {
+        self.config.colour_management = value;
+        self
+    }
+
+    /// The colour profile of the display, to which the colours of images are converted; sRGB by
+    /// default
+    pub fn output_profile(mut self, value: ColourProfile) -> Self {
+        self.config.output_profile = Some(value);
+        self
+    }
+
+    /// How the image is rotated and flipped when it is shown; the image is shown as it is by
+    /// default
+    ///
+    /// The transform only changes how the image is shown, not the image itself. While the window
+    /// is shown, the image can be rotated and flipped further with keys.
+    pub fn transform(mut self, value: ViewTransform) -> Self {
+        self.config.transform = value;
+        self
+    }
+
+    /// Rotate the shown image clockwise; see [`transform`]
+    ///
+    /// [`transform`]: struct.ConfigBuilder.html#method.transform
+    pub fn rotation(mut self, value: Rotation) -> Self {
+        self.config.transform.rotation = value;
+        self
+    }
+
+    /// Mirror the shown image left to right, before it is rotated; see [`transform`]
+    ///
+    /// [`transform`]: struct.ConfigBuilder.html#method.transform
+    pub fn flip_horizontal(mut self, value: bool) -> Self {
+        self.config.transform.flip_horizontal = value;
+        self
+    }
+
+    /// Mirror the shown image top to bottom, before it is rotated; see [`transform`]
+    ///
+    /// [`transform`]: struct.ConfigBuilder.html#method.transform
+    pub fn flip_vertical(mut self, value: bool) -> Self {
+        self.config.transform.flip_vertical = value;
+        self
+    }
+
+    /// How the image is sampled when it is shown scaled up or down, e.g. after zooming, or when
+    /// it is scaled to fit a fullscreen window
+    ///
+    /// By default, [`Filter::Auto`] shows sharp pixels when the image is scaled up, and averages
+    /// pixels when it is scaled down. While the window is shown, the filter can be cycled through
+    /// with a key.
+    ///
+    /// [`Filter::Auto`]: ../enum.Filter.html#variant.Auto
+    pub fn filter(mut self, value: Filter) -> Self {
+        self.config.filter = value;
+        self
+    }
+
+    /// Show a thin grid around the pixels when zoomed in beyond the [`pixel_grid_threshold`], so
+    /// the boundaries of the pixels can be seen; on by default
+    ///
+    /// While the window is shown, the pixel grid can be toggled with a key.
+    ///
+    /// [`pixel_grid_threshold`]: struct.ConfigBuilder.html#method.pixel_grid_threshold
+    pub fn show_pixel_grid(mut self, value: bool) -> Self {
+        self.config.pixel_grid = value;
+        self
+    }
+
+    /// The zoom factor beyond which the pixel grid is shown, where a factor of `1.0` shows the
+    /// image at its actual size; `8.0` by default
+    pub fn pixel_grid_threshold(mut self, value: f32) -> Self {
+        self.config.pixel_grid_threshold = value;
+        self
+    }
+
+    /// Show a grid of blocks of pixels over the image, e.g. every 8 or 16 pixels, to check the
+    /// alignment of JPEG blocks or of tiles; no grid is shown by default
+    ///
+    /// While the window is shown, the grid can be cycled through a few common block sizes with a
+    /// key.
+    pub fn grid(mut self, value: Grid) -> Self {
+        self.config.grid = Some(value);
+        self
+    }
+
+    /// Show rulers with the coordinates of the shown image along the top and left edges of the
+    /// window; hidden by default
+    pub fn show_ruler(mut self, value: bool) -> Self {
+        self.config.ruler = value;
+        self
+    }
+
+    /// The zoom level at which an image is first shown; by default, the image is scaled down to
+    /// fit within the window
+    pub fn initial_zoom(mut self, value: Zoom) -> Self {
+        self.config.initial_zoom = value;
+        self
+    }
+
+    /// Require the given backend to show the window
+    ///
+    /// Showing the window fails if this backend was not compiled in.
+    pub fn backend(mut self, value: Backend) -> Self {
+        self.config.backend = Some(value);
+        self
+    }
+
+    pub(crate) fn key_bindings_mut(&mut self) -> &mut KeyBindings {
+        &mut self.config.key_bindings
+    }
+
     /// Construct a configuration from the default and overridden configuration values.
     pub fn build(self) -> Config {
         self.config
     }
 }
+
+/// Defines what happens with a window when its [`MiniView`] controls are dropped
+///
+/// [`MiniView`]: ../struct.MiniView.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DropBehavior {
+    /// Close the window, and wait for the thread managing the window to return
+    CloseAndJoin,
+
+    /// Keep showing the window until the user closes it, without blocking the dropping thread
+    #[default]
+    Detach,
+
+    /// Block the dropping thread until the user closes the window
+    WaitForUser,
+}
+
+impl FromStr for DropBehavior {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "close_and_join" => Ok(DropBehavior::CloseAndJoin),
+            "detach" => Ok(DropBehavior::Detach),
+            "wait_for_user" => Ok(DropBehavior::WaitForUser),
+            _ => Err(SettingsError::InvalidDropBehavior(value.to_string())),
+        }
+    }
+}
+
+/// The backends which can be used to show a window
+///
+/// Which backend is used, is selected at compile time, by enabling the feature of the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Backend {
+    /// The `backend_pixels` feature
+    Pixels,
+    /// The `backend_piston_window` feature
+    PistonWindow,
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Pixels => f.write_str("pixels"),
+            Backend::PistonWindow => f.write_str("piston_window"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pixels" => Ok(Backend::Pixels),
+            "piston_window" => Ok(Backend::PistonWindow),
+            _ => Err(SettingsError::InvalidBackend(value.to_string())),
+        }
+    }
+}
+
+impl Backend {
+    /// The backend which was compiled in
+    pub fn compiled() -> Backend {
+        #[cfg(feature = "backend_piston_window")]
+        {
+            Backend::PistonWindow
+        }
+
+        #[cfg(feature = "backend_pixels")]
+        {
+            Backend::Pixels
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialization {
+    use super::{Backend, Config, DropBehavior};
+    use crate::keys::{Key, KeyBindings};
+    use crate::settings::{Binding, Colour};
+    use crate::{
+        Channels, Colormap, ColourProfile, Filter, Grid, HistogramScale, Source, Swizzle,
+        ToneMapping, Transparency, ViewTransform, Zoom,
+    };
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    /// Version of the serialized form of a configuration; incremented on incompatible changes
+    const VERSION: u32 = 1;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct SerializedConfig {
+        version: u32,
+        source: Source,
+        fullscreen: bool,
+        resizable_window: bool,
+        lazy_window: bool,
+        window_name: String,
+        drop_behavior: DropBehavior,
+        background: Colour,
+        transparency: SerializedTransparency,
+        channels: Channels,
+        tinted: bool,
+        swizzle: Swizzle,
+        tone_mapping: ToneMapping,
+        colormap: Colormap,
+        colour_bar: bool,
+        histogram: bool,
+        histogram_scale: HistogramScale,
+        info: bool,
+        apply_orientation: bool,
+        colour_management: bool,
+        output_profile: Option<ColourProfile>,
+        transform: ViewTransform,
+        filter: Filter,
+        pixel_grid: bool,
+        pixel_grid_threshold: f32,
+        grid: Option<Grid>,
+        ruler: bool,
+        initial_zoom: Zoom,
+        backend: Option<Backend>,
+        keys: BTreeMap<Key, Binding>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case", deny_unknown_fields)]
+    enum SerializedTransparency {
+        Checkerboard { size: u32, colours: [Colour; 2] },
+        Solid(Colour),
+    }
+
+    impl From<Transparency> for SerializedTransparency {
+        fn from(transparency: Transparency) -> Self {
+            match transparency {
+                Transparency::Checkerboard {
+                    size,
+                    colours: [first, second],
+                } => SerializedTransparency::Checkerboard {
+                    size,
+                    colours: [Colour(first), Colour(second)],
+                },
+                Transparency::Solid(colour) => SerializedTransparency::Solid(Colour(colour)),
+            }
+        }
+    }
+
+    impl From<SerializedTransparency> for Transparency {
+        fn from(transparency: SerializedTransparency) -> Self {
+            match transparency {
+                SerializedTransparency::Checkerboard {
+                    size,
+                    colours: [first, second],
+                } => Transparency::Checkerboard {
+                    size,
+                    colours: [first.0, second.0],
+                },
+                SerializedTransparency::Solid(colour) => Transparency::Solid(colour.0),
+            }
+        }
+    }
+
+    impl Serialize for Config {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            SerializedConfig {
+                version: VERSION,
+                source: self.source.clone(),
+                fullscreen: self.fullscreen,
+                resizable_window: self.resizable_window,
+                lazy_window: self.lazy_window,
+                window_name: self.window_name.clone(),
+                drop_behavior: self.drop_behavior,
+                background: Colour(self.background),
+                transparency: self.transparency.into(),
+                channels: self.channels,
+                tinted: self.tinted,
+                swizzle: self.swizzle,
+                tone_mapping: self.tone_mapping,
+                colormap: self.colormap,
+                colour_bar: self.colour_bar,
+                histogram: self.histogram,
+                histogram_scale: self.histogram_scale,
+                info: self.info,
+                apply_orientation: self.apply_orientation,
+                colour_management: self.colour_management,
+                output_profile: self.output_profile.clone(),
+                transform: self.transform,
+                filter: self.filter,
+                pixel_grid: self.pixel_grid,
+                pixel_grid_threshold: self.pixel_grid_threshold,
+                grid: self.grid,
+                ruler: self.ruler,
+                initial_zoom: self.initial_zoom,
+                backend: self.backend,
+                keys: self
+                    .key_bindings
+                    .overrides()
+                    .map(|(key, command)| (key, Binding(command)))
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Config {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let config = SerializedConfig::deserialize(deserializer)?;
+
+            if config.version != VERSION {
+                return Err(D::Error::custom(format!(
+                    "unsupported configuration version {}, expected version {}",
+                    config.version, VERSION
+                )));
+            }
+
+            let mut key_bindings = KeyBindings::default();
+
+            for (key, Binding(command)) in config.keys {
+                key_bindings.bind(key, command);
+            }
+
+            Ok(Config {
+                source: config.source,
+                fullscreen: config.fullscreen,
+                resizable_window: config.resizable_window,
+                lazy_window: config.lazy_window,
+                window_name: config.window_name,
+                drop_behavior: config.drop_behavior,
+                on_ready: None,
+                background: config.background.0,
+                transparency: config.transparency.into(),
+                channels: config.channels,
+                tinted: config.tinted,
+                swizzle: config.swizzle,
+                tone_mapping: config.tone_mapping,
+                colormap: config.colormap,
+                colour_bar: config.colour_bar,
+                histogram: config.histogram,
+                histogram_scale: config.histogram_scale,
+                info: config.info,
+                apply_orientation: config.apply_orientation,
+                colour_management: config.colour_management,
+                output_profile: config.output_profile,
+                transform: config.transform,
+                filter: config.filter,
+                pixel_grid: config.pixel_grid,
+                pixel_grid_threshold: config.pixel_grid_threshold,
+                grid: config.grid,
+                ruler: config.ruler,
+                initial_zoom: config.initial_zoom,
+                backend: config.backend,
+                key_bindings,
+            })
+        }
+    }
+}
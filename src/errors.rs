@@ -3,6 +3,8 @@
 //! MiniView attempts to always return an error in case of faulty behaviour instead of crashing
 //! by virtue of panicking.
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// The top-level error type
@@ -27,10 +29,36 @@ pub enum MiniViewError {
     #[error("Unable to signal window to stop showing")]
     SendStopError,
 
+    /// Returned if an action could not be send to the window, for example because the window has
+    /// already been closed
+    #[error("Unable to send an action to the window")]
+    SendActionError,
+
+    /// Returned if the window did not respond to a request, for example because the window was
+    /// closed before it could respond
+    #[error("The window did not respond.")]
+    NoResponse,
+
     /// Created when it was not possible to create a graphical window
     #[error("Unable to create a window to display the image.")]
     UnableToCreateWindow,
 
+    /// Returned if an image could not be saved to a path
+    #[error("Unable to save the image.")]
+    UnableToSaveImage,
+
+    /// Returned if a window could not be controlled remotely
+    #[error(transparent)]
+    Remote(#[from] RemoteError),
+
+    /// Returned if the defaults preferred by the user could not be loaded
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+
+    /// Returned if the configuration requires a backend which was not compiled in
+    #[error("The {0} backend is not available; miniview was compiled with the {1} backend.")]
+    BackendNotAvailable(String, String),
+
     /// Returned if an image could not be mapped to the texture which is shown by the image view
     /// in the window
     #[error("Unable to map the image to a texture.")]
@@ -65,4 +93,152 @@ pub enum ImportError {
     /// stdin, but the given bytes could not be decoded
     #[error("The input received from stdin could not be loaded.")]
     OnStdinBytesUnableToGuessOrLoadFormat,
+
+    /// Returned when the content received by stdin is neither an image, nor a path or list of
+    /// paths, nor a base64 encoded image or data URI
+    #[error("The input received from stdin could not be recognized.")]
+    OnStdinUnrecognizedContent,
+
+    /// Returned when the program expects an ordered stream of bytes (the formatted image) from
+    /// memory, but the given bytes could not be decoded
+    #[error("The given bytes could not be decoded as an image.")]
+    UnableToDecodeBytes,
+}
+
+/// Errors related to controlling a window remotely
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    /// Returned when a socket could not be created at the given path
+    #[error("Unable to listen on socket '{}'.", .0.display())]
+    UnableToBind(PathBuf),
+
+    /// Returned when a window or server is already listening on the socket at the given path
+    #[error("Unable to listen on socket '{}': the address is in use.", .0.display())]
+    AddressInUse(PathBuf),
+
+    /// Returned when the path at which to listen exists, but is not a socket
+    #[error("Unable to listen on '{}': the path exists and is not a socket.", .0.display())]
+    NotASocket(PathBuf),
+
+    /// Returned when no window is listening at the given socket path
+    #[error("Unable to connect to socket '{}'.", .0.display())]
+    UnableToConnect(PathBuf),
+
+    /// Returned when the connection closed before a response was received
+    #[error("The connection was lost.")]
+    ConnectionLost,
+
+    /// Returned when the directory which holds the per-user sockets could not be created
+    #[error("Unable to create runtime directory '{}'.", .0.display())]
+    UnableToCreateRuntimeDirectory(PathBuf),
+
+    /// Returned when a window was unable to apply a request
+    #[error("The request was rejected: {0}")]
+    Rejected(String),
+
+    /// Returned when a list of images is handed over to a running instance, which can only be
+    /// given a single image
+    #[error("A list of images can't be shown by a running instance.")]
+    UnableToHandOffGallery,
+
+    /// Returned when a frame sent to a server could not be read or decoded
+    #[error("Malformed frame: {0}.")]
+    MalformedFrame(String),
+
+    /// Returned when a server is asked to listen on a TCP address which is not a loopback
+    /// address, at which other machines could open windows
+    #[error("Unable to serve on '{0}': only loopback addresses, such as 127.0.0.1, are allowed.")]
+    NonLocalAddress(SocketAddr),
+
+    /// Returned when a server was unable to start a window process
+    #[error("Unable to start a window.")]
+    UnableToStartWindow,
+
+    /// Returned when a request could not be parsed
+    #[error("Invalid request: {0}.")]
+    InvalidRequest(String),
+
+    /// Returned when a response could not be parsed
+    #[error("Received a malformed response.")]
+    MalformedResponse,
+}
+
+/// Errors related to loading the defaults preferred by the user, from the configuration file and
+/// the environment
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    /// Returned if the configuration file exists, but could not be read
+    #[error("Unable to read the configuration file '{0}'.")]
+    UnableToReadFile(PathBuf),
+
+    /// Returned if the configuration file is not valid
+    #[error("Invalid configuration file '{0}': {1}")]
+    InvalidFile(PathBuf, String),
+
+    /// Returned if an environment variable has a value which is not valid for its setting
+    #[error("Invalid value '{1}' for environment variable {0}.")]
+    InvalidEnvironmentVariable(String, String),
+
+    /// Returned if a key is bound to a command which does not exist
+    #[error("Unknown command '{0}' in the key bindings.")]
+    UnknownCommand(String),
+
+    /// Returned if a colour is not written as '#rrggbb' or '#rrggbbaa'
+    #[error("Invalid colour '{0}'; expected '#rrggbb' or '#rrggbbaa'.")]
+    InvalidColour(String),
+
+    /// Returned if the channels to show are not one of the supported channel views
+    #[error(
+        "Invalid channels '{0}'; expected 'all', 'red', 'green', 'blue', 'alpha' or 'luminance'."
+    )]
+    InvalidChannels(String),
+
+    /// Returned if a value range is not written as `full`, `auto`, `percentile:<LOW>,<HIGH>` or
+    /// `window:<LEVEL>,<WIDTH>`
+    #[error("Invalid value range '{0}'; expected 'full', 'auto', 'percentile:<LOW>,<HIGH>' or 'window:<LEVEL>,<WIDTH>'.")]
+    InvalidValueRange(String),
+
+    /// Returned if a tone mapping operator is not one of the supported operators
+    #[error("Invalid tone mapping operator '{0}'; expected 'clamp', 'reinhard' or 'aces'.")]
+    InvalidToneMapOperator(String),
+
+    /// Returned if a colormap is not one of the supported colormaps
+    #[error("Invalid colormap '{0}'; expected 'greyscale', 'viridis', 'magma', 'inferno', 'turbo', 'coolwarm' or 'rdbu'.")]
+    InvalidColormap(String),
+
+    /// Returned if a histogram scale is not one of the supported scales
+    #[error("Invalid histogram scale '{0}'; expected 'linear' or 'log'.")]
+    InvalidHistogramScale(String),
+
+    /// Returned if a swizzle is not written as three or four of the letters 'r', 'g', 'b' and 'a'
+    #[error("Invalid swizzle '{0}'; expected e.g. 'bgr' or 'bgra'.")]
+    InvalidSwizzle(String),
+
+    /// Returned if a colour profile could not be read, or is not a supported ICC profile
+    #[error("Unable to read an RGB or greyscale ICC profile from '{}'.", .0.display())]
+    InvalidColourProfile(PathBuf),
+
+    /// Returned if a rotation is not a multiple of 90 degrees
+    #[error("Invalid rotation '{0}'; expected 0, 90, 180 or 270 degrees.")]
+    InvalidRotation(String),
+
+    /// Returned if a filter is not one of the supported filters
+    #[error("Invalid filter '{0}'; expected 'auto', 'nearest', 'bilinear' or 'area'.")]
+    InvalidFilter(String),
+
+    /// Returned if a grid is not written as the size of its blocks
+    #[error(
+        "Invalid grid '{0}'; expected the size of the blocks in pixels, e.g. '16' or '64x32'."
+    )]
+    InvalidGrid(String),
+
+    /// Returned if a drop behavior is not one of the supported behaviors
+    #[error(
+        "Invalid drop behavior '{0}'; expected 'close_and_join', 'detach' or 'wait_for_user'."
+    )]
+    InvalidDropBehavior(String),
+
+    /// Returned if a backend is not one of the supported backends
+    #[error("Invalid backend '{0}'; expected 'pixels' or 'piston_window'.")]
+    InvalidBackend(String),
 }
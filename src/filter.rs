@@ -0,0 +1,332 @@
+//! Filters with which the image is sampled, when it is shown scaled up or down.
+
+use crate::errors::SettingsError;
+use imagecrate::{Rgba, RgbaImage};
+use std::str::FromStr;
+
+/// How the image is sampled when it is shown scaled up or down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Filter {
+    /// Nearest neighbour when the image is shown at its actual size or scaled up, and area when
+    /// it is scaled down
+    #[default]
+    Auto,
+    /// Show each pixel as a sharp square, e.g. for pixel art or to inspect single pixels
+    Nearest,
+    /// Blend the four pixels nearest to each position
+    Bilinear,
+    /// Average the pixels covered by each pixel of the window, using mipmaps; smooth when
+    /// photos are scaled down
+    Area,
+}
+
+impl Filter {
+    /// The filter which is used when the image is scaled by the given factor
+    pub(crate) fn at_scale(self, scale: f32) -> Filter {
+        match self {
+            Filter::Auto if scale >= 1.0 => Filter::Nearest,
+            Filter::Auto => Filter::Area,
+            filter => filter,
+        }
+    }
+
+    /// The next filter, to cycle through the filters
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Filter::Auto => Filter::Nearest,
+            Filter::Nearest => Filter::Bilinear,
+            Filter::Bilinear => Filter::Area,
+            Filter::Area => Filter::Auto,
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Filter::Auto),
+            "nearest" => Ok(Filter::Nearest),
+            "bilinear" => Ok(Filter::Bilinear),
+            "area" => Ok(Filter::Area),
+            _ => Err(SettingsError::InvalidFilter(value.to_string())),
+        }
+    }
+}
+
+/// The image at halved dimensions, level after level down to a single pixel, where each pixel
+/// averages the pixels it covers of the previous level
+#[derive(Debug)]
+pub(crate) struct Mipmaps {
+    levels: Vec<RgbaImage>,
+}
+
+impl Mipmaps {
+    pub(crate) fn new(image: &RgbaImage) -> Self {
+        let mut levels: Vec<RgbaImage> = Vec::new();
+
+        loop {
+            let previous = levels.last().unwrap_or(image);
+            let (width, height) = previous.dimensions();
+
+            if width <= 1 && height <= 1 {
+                break;
+            }
+
+            let level = halve(previous);
+            levels.push(level);
+        }
+
+        Mipmaps { levels }
+    }
+}
+
+/// Halve the dimensions of the image, rounded up; the colours are weighted by their alpha, so
+/// transparent pixels don't darken the edges of opaque areas
+fn halve(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+
+    RgbaImage::from_fn((width + 1) / 2, (height + 1) / 2, |x, y| {
+        let mut sum = [0u32; 4];
+        let mut count = 0;
+
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| (2 * x + dx, 2 * y + dy)) {
+            if x < width && y < height {
+                let pixel = image.get_pixel(x, y).0;
+                let alpha = u32::from(pixel[3]);
+
+                for channel in 0..3 {
+                    sum[channel] += u32::from(pixel[channel]) * alpha;
+                }
+
+                sum[3] += alpha;
+                count += 1;
+            }
+        }
+
+        let colour = |channel: usize| match sum[3] {
+            0 => 0,
+            alpha => ((sum[channel] + alpha / 2) / alpha) as u8,
+        };
+
+        Rgba([
+            colour(0),
+            colour(1),
+            colour(2),
+            ((sum[3] + count / 2) / count) as u8,
+        ])
+    })
+}
+
+/// Samples an image with a filter, for a frame in which the image is scaled by a fixed factor
+pub(crate) struct Sampler<'a> {
+    image: &'a RgbaImage,
+    filter: Filter,
+    // For the area filter, the two mipmap levels between which is blended, and the weight of the
+    // second level
+    levels: [&'a RgbaImage; 2],
+    blend: f32,
+}
+
+impl<'a> Sampler<'a> {
+    /// A sampler with the given filter for an image scaled by the given factor; the mipmaps of
+    /// the image are required by the area filter, and nearest neighbour is used without them
+    pub(crate) fn new(
+        image: &'a RgbaImage,
+        mipmaps: Option<&'a Mipmaps>,
+        filter: Filter,
+        scale: f32,
+    ) -> Self {
+        let filter = filter.at_scale(scale);
+        let mut sampler = Sampler {
+            image,
+            filter,
+            levels: [image, image],
+            blend: 0.0,
+        };
+
+        match (filter, mipmaps) {
+            (Filter::Area, Some(mipmaps)) => {
+                // The level of detail: each level halves the dimensions of the previous level
+                let detail = (1.0 / scale).log2().max(0.0);
+                let level = |index: usize| match index {
+                    0 => image,
+                    index => mipmaps
+                        .levels
+                        .get(index - 1)
+                        .or(mipmaps.levels.last())
+                        .unwrap_or(image),
+                };
+
+                sampler.levels = [level(detail as usize), level(detail as usize + 1)];
+                sampler.blend = detail.fract();
+            }
+            (Filter::Area, None) => sampler.filter = Filter::Nearest,
+            _ => {}
+        }
+
+        sampler
+    }
+
+    /// The colour of the image at the given position, in image pixels
+    pub(crate) fn sample(&self, (x, y): (f32, f32)) -> Rgba<u8> {
+        match self.filter {
+            Filter::Auto | Filter::Nearest => {
+                let (width, height) = self.image.dimensions();
+                let x = (x.max(0.0) as u32).min(width - 1);
+                let y = (y.max(0.0) as u32).min(height - 1);
+
+                *self.image.get_pixel(x, y)
+            }
+            Filter::Bilinear => round(bilinear(self.image, (x, y), self.image.dimensions())),
+            Filter::Area => {
+                let [first, second] = self.levels;
+                let first = bilinear(first, (x, y), self.image.dimensions());
+
+                if self.blend == 0.0 {
+                    return round(first);
+                }
+
+                let second = bilinear(second, (x, y), self.image.dimensions());
+
+                round(std::array::from_fn(|channel| {
+                    first[channel] + (second[channel] - first[channel]) * self.blend
+                }))
+            }
+        }
+    }
+}
+
+/// Blend the four pixels of the level nearest to the given position, in pixels of the image of
+/// the given dimensions
+fn bilinear(level: &RgbaImage, (x, y): (f32, f32), dimensions: (u32, u32)) -> [f32; 4] {
+    let (width, height) = level.dimensions();
+
+    // Positions are relative to the centers of the pixels of the level
+    let x = x * width as f32 / dimensions.0 as f32 - 0.5;
+    let y = y * height as f32 / dimensions.1 as f32 - 0.5;
+
+    let (left, top) = (x.floor(), y.floor());
+    let (dx, dy) = (x - left, y - top);
+    let column = |x: f32| (x.max(0.0) as u32).min(width - 1);
+    let row = |y: f32| (y.max(0.0) as u32).min(height - 1);
+    let (left, right) = (column(left), column(left + 1.0));
+    let (top, bottom) = (row(top), row(top + 1.0));
+
+    let pixel = |x: u32, y: u32| level.get_pixel(x, y).0.map(f32::from);
+    let [top_left, top_right, bottom_left, bottom_right] = [
+        pixel(left, top),
+        pixel(right, top),
+        pixel(left, bottom),
+        pixel(right, bottom),
+    ];
+
+    std::array::from_fn(|channel| {
+        let top = top_left[channel] + (top_right[channel] - top_left[channel]) * dx;
+        let bottom = bottom_left[channel] + (bottom_right[channel] - bottom_left[channel]) * dx;
+
+        top + (bottom - top) * dy
+    })
+}
+
+fn round(pixel: [f32; 4]) -> Rgba<u8> {
+    Rgba(pixel.map(|value| value.round().clamp(0.0, 255.0) as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, Mipmaps, Sampler};
+    use imagecrate::{Rgba, RgbaImage};
+
+    /// A 4x4 image of which the columns are red with the intensities 0, 100, 200 and 40
+    fn image() -> RgbaImage {
+        RgbaImage::from_fn(4, 4, |x, _| {
+            Rgba([[0, 100, 200, 40][x as usize], 0, 0, 255])
+        })
+    }
+
+    /// The red channel of the image, sampled with the filter at the given positions, for a frame
+    /// in which the image is scaled by the given factor
+    fn sample(filter: Filter, scale: f32, positions: &[(f32, f32)]) -> Vec<u8> {
+        let image = image();
+        let mipmaps = Mipmaps::new(&image);
+        let sampler = Sampler::new(&image, Some(&mipmaps), filter, scale);
+
+        positions
+            .iter()
+            .map(|&position| sampler.sample(position)[0])
+            .collect()
+    }
+
+    #[test]
+    fn mipmap_levels() {
+        let mipmaps = Mipmaps::new(&image());
+        let levels = &mipmaps.levels;
+
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].dimensions(), (2, 2));
+        assert_eq!(levels[0].get_pixel(0, 0), &Rgba([50, 0, 0, 255]));
+        assert_eq!(levels[0].get_pixel(1, 1), &Rgba([120, 0, 0, 255]));
+        assert_eq!(levels[1].dimensions(), (1, 1));
+        assert_eq!(levels[1].get_pixel(0, 0), &Rgba([85, 0, 0, 255]));
+    }
+
+    #[test]
+    fn transparent_pixels_are_not_averaged() {
+        let image = RgbaImage::from_fn(2, 1, |x, _| match x {
+            0 => Rgba([255, 0, 0, 255]),
+            _ => Rgba([0, 0, 0, 0]),
+        });
+        let mipmaps = Mipmaps::new(&image);
+
+        assert_eq!(mipmaps.levels[0].get_pixel(0, 0), &Rgba([255, 0, 0, 128]));
+    }
+
+    #[test]
+    fn upscale() {
+        let positions = [(1.0, 0.5), (1.25, 0.5), (2.75, 0.5)];
+
+        assert_eq!(sample(Filter::Nearest, 4.0, &positions), [100, 100, 200]);
+        assert_eq!(sample(Filter::Bilinear, 4.0, &positions), [50, 75, 160]);
+
+        // the image itself is sampled when it is not scaled down
+        assert_eq!(sample(Filter::Area, 4.0, &positions), [50, 75, 160]);
+        assert_eq!(sample(Filter::Auto, 4.0, &positions), [100, 100, 200]);
+    }
+
+    #[test]
+    fn downscale() {
+        let positions = [(2.0, 1.0), (0.0, 0.0)];
+
+        assert_eq!(sample(Filter::Nearest, 0.5, &positions), [200, 0]);
+        assert_eq!(sample(Filter::Bilinear, 0.5, &positions), [150, 0]);
+
+        // the first mipmap level, of which the columns average 0 and 100, and 200 and 40
+        assert_eq!(sample(Filter::Area, 0.5, &positions), [85, 50]);
+        assert_eq!(sample(Filter::Auto, 0.5, &positions), [85, 50]);
+    }
+
+    #[test]
+    fn select_mipmap_level() {
+        // the last level, a single pixel which averages the whole image
+        assert_eq!(sample(Filter::Area, 0.25, &[(0.0, 0.0)]), [85]);
+        assert_eq!(sample(Filter::Area, 0.1, &[(0.0, 0.0)]), [85]);
+
+        // between the first two levels, 50 and 85, at a level of detail of log2(1 / 0.35) = 1.51
+        assert_eq!(sample(Filter::Area, 0.35, &[(0.0, 0.0)]), [68]);
+    }
+
+    #[test]
+    fn area_without_mipmaps() {
+        let image = image();
+        let sampler = Sampler::new(&image, None, Filter::Area, 0.5);
+
+        assert_eq!(sampler.sample((2.0, 1.0)), Rgba([200, 0, 0, 255]));
+    }
+}
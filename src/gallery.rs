@@ -0,0 +1,83 @@
+//! A list of images of which one at a time is shown, e.g. when a list of paths was received by
+//! stdin.
+
+use crate::config::Config;
+use crate::io::open_with_metadata;
+use crate::metadata::Metadata;
+use crate::Source;
+use imagecrate::DynamicImage;
+use std::path::{Path, PathBuf};
+
+/// The images of a gallery, and which of them is currently shown
+#[derive(Debug)]
+pub(crate) struct Gallery {
+    paths: Vec<PathBuf>,
+    index: usize,
+    window_name: String,
+    // Whether images are transformed according to their EXIF orientation
+    orient: bool,
+}
+
+impl Gallery {
+    /// A gallery for sources which consist of more than one image
+    pub(crate) fn from_config(config: &Config) -> Option<Gallery> {
+        let Source::ByPaths(paths) = config.source() else {
+            return None;
+        };
+
+        if paths.len() < 2 {
+            return None;
+        }
+
+        // Matches the image which is opened first by Source::open
+        let index = paths
+            .iter()
+            .position(|path| imagecrate::image_dimensions(path).is_ok())
+            .unwrap_or_default();
+
+        Some(Gallery {
+            paths: paths.clone(),
+            index,
+            window_name: config.window_name().to_string(),
+            orient: config.apply_orientation(),
+        })
+    }
+
+    /// Move by the given number of images, wrapping around at either end, and open the image and
+    /// its metadata
+    ///
+    /// Images which can't be opened are skipped. Returns `None` if no other image could be opened.
+    pub(crate) fn step(&mut self, offset: isize) -> Option<(DynamicImage, Metadata)> {
+        let count = self.paths.len() as isize;
+
+        (1..count).find_map(|attempt| {
+            let index = (self.index as isize + offset * attempt).rem_euclid(count) as usize;
+            let image = open_with_metadata(&self.paths[index], self.orient)?;
+
+            self.index = index;
+            Some(image)
+        })
+    }
+
+    /// The path of the shown image
+    pub(crate) fn path(&self) -> &Path {
+        &self.paths[self.index]
+    }
+
+    /// The title of the window, which names the shown image and its position in the gallery
+    pub(crate) fn title(&self) -> String {
+        let path = &self.paths[self.index];
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_else(|| path.to_string_lossy());
+
+        format!(
+            "{} ({}/{}) - {}",
+            name,
+            self.index + 1,
+            self.paths.len(),
+            self.window_name
+        )
+    }
+}
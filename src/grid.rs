@@ -0,0 +1,123 @@
+//! Grids which are drawn over the image, to show where its pixels or blocks of pixels begin.
+
+use crate::errors::SettingsError;
+use crate::transform::ViewTransform;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A grid of blocks of pixels, e.g. to check the alignment of JPEG blocks or of tiles
+///
+/// Written as the size of the blocks, e.g. `16` for blocks of 16 by 16 pixels, or `64x32` for
+/// blocks which are 64 pixels wide and 32 pixels high. The grid starts at the top left corner of
+/// the image as it is stored, so it stays aligned with the blocks when the image is rotated or
+/// flipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Grid {
+    width: u32,
+    height: u32,
+}
+
+impl Grid {
+    /// A grid of blocks of the given width and height, in pixels; blocks are at least a single
+    /// pixel in size
+    pub fn new(width: u32, height: u32) -> Self {
+        Grid {
+            width: width.max(1),
+            height: height.max(1),
+        }
+    }
+
+    /// A grid of square blocks of the given size, in pixels
+    pub fn square(size: u32) -> Self {
+        Grid::new(size, size)
+    }
+
+    /// The width of the blocks, in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the blocks, in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The positions of the vertical and of the horizontal lines of the grid, over an image of
+    /// the given dimensions which is shown with the given transform, in pixels of the shown image
+    pub(crate) fn lines(
+        self,
+        transform: &ViewTransform,
+        (width, height): (u32, u32),
+    ) -> (Vec<f32>, Vec<f32>) {
+        let mut columns = Vec::new();
+        let mut rows = Vec::new();
+
+        // A line of the image may be shown as a vertical or as a horizontal line, depending on
+        // the rotation; the positions are whole pixels, so they can be compared exactly
+        let mut add = |start: (u32, u32), end: (u32, u32)| {
+            let position =
+                |(x, y): (u32, u32)| transform.view_position((x as f32, y as f32), (width, height));
+            let (start, end) = (position(start), position(end));
+
+            if start.0 == end.0 {
+                columns.push(start.0);
+            } else {
+                rows.push(start.1);
+            }
+        };
+
+        for x in (0..=width).step_by(self.width as usize) {
+            add((x, 0), (x, height));
+        }
+
+        for y in (0..=height).step_by(self.height as usize) {
+            add((0, y), (width, y));
+        }
+
+        (columns, rows)
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.width == self.height {
+            write!(f, "{}", self.width)
+        } else {
+            write!(f, "{}x{}", self.width, self.height)
+        }
+    }
+}
+
+impl FromStr for Grid {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let size = |size: &str| size.trim().parse::<u32>().ok().filter(|size| *size > 0);
+
+        match value.split_once(['x', 'X']) {
+            Some((width, height)) => size(width).zip(size(height)),
+            None => size(value).map(|size| (size, size)),
+        }
+        .map(|(width, height)| Grid::new(width, height))
+        .ok_or_else(|| SettingsError::InvalidGrid(value.to_string()))
+    }
+}
+
+impl From<Grid> for String {
+    fn from(grid: Grid) -> Self {
+        grid.to_string()
+    }
+}
+
+impl TryFrom<String> for Grid {
+    type Error = SettingsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
@@ -0,0 +1,642 @@
+//! Thread safe controls for a `miniview` window.
+//!
+//! A [`MiniViewHandle`] can be used to control a window which is driven by another thread, for
+//! example when the window is driven on the main thread by [`MiniView::run_with_handle`].
+//!
+//! [`MiniViewHandle`]: struct.MiniViewHandle.html
+//! [`MiniView::run_with_handle`]: ../struct.MiniView.html#method.run_with_handle
+
+use crate::config::Config;
+use crate::metadata::Metadata;
+use crate::view::Zoom;
+use crate::{Action, MVResult, MiniViewError, Source};
+use imagecrate::{DynamicImage, RgbaImage};
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// A handle which can be used to control a `miniview` window from any thread
+///
+/// Handles are cheap to clone, and can be shared between threads. A handle for a window shown
+/// with [`MiniView::show`] can be obtained by calling [`MiniView::handle`].
+///
+/// [`MiniView::show`]: ../struct.MiniView.html#method.show
+/// [`MiniView::handle`]: ../struct.MiniView.html#method.handle
+pub struct MiniViewHandle {
+    sender: Arc<Mutex<mpsc::Sender<Action>>>,
+    shared: Arc<Shared>,
+}
+
+impl MiniViewHandle {
+    /// Create a new handle for a window shown with the given configuration, and the receiving
+    /// end which the window should poll for actions
+    pub(crate) fn new(config: &Config) -> (Self, mpsc::Receiver<Action>) {
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = MiniViewHandle {
+            sender: Arc::new(Mutex::new(sender)),
+            shared: Arc::new(Shared {
+                apply_orientation: config.apply_orientation(),
+                ..Shared::default()
+            }),
+        };
+
+        (handle, receiver)
+    }
+
+    /// Whether the window is currently open
+    ///
+    /// Returns `false` before the window has been created, and after it has been closed.
+    pub fn is_open(&self) -> bool {
+        self.shared.open.load(Ordering::SeqCst)
+    }
+
+    /// Request the window to close, without waiting for it to be closed
+    pub fn request_close(&self) -> MVResult<()> {
+        self.send(Action::Close)
+            .map_err(|_| MiniViewError::SendStopError)
+    }
+
+    /// Replace the image shown by the window
+    ///
+    /// The image is shown without metadata; see [`set_image_with_metadata`].
+    ///
+    /// [`set_image_with_metadata`]: struct.MiniViewHandle.html#method.set_image_with_metadata
+    pub fn set_image(&self, image: DynamicImage) -> MVResult<()> {
+        self.set_image_with_metadata(image, Metadata::default())
+    }
+
+    /// Replace the image shown by the window, and the metadata which is shown by the info panel,
+    /// e.g. as read by [`Source::metadata`]
+    ///
+    /// The image is shown as it is; its orientation is not applied again.
+    ///
+    /// [`Source::metadata`]: ../enum.Source.html#method.metadata
+    pub fn set_image_with_metadata(&self, image: DynamicImage, metadata: Metadata) -> MVResult<()> {
+        self.send(Action::SetImage(image, Box::new(metadata), None))
+    }
+
+    /// Load the image shown by the window again, from the path or bytes it was loaded from, e.g.
+    /// after the file has changed
+    ///
+    /// Returns `false` if the image can't be loaded again, because it was set from memory by
+    /// [`set_image`], or was read from stdin. Blocks until the image has been loaded.
+    ///
+    /// [`set_image`]: struct.MiniViewHandle.html#method.set_image
+    pub fn reload(&self) -> MVResult<bool> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(Action::Source(sender))?;
+
+        match receiver.recv().map_err(|_| MiniViewError::NoResponse)? {
+            Some(source) => self.load(source).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Load the image and its metadata from the given source, and show it
+    ///
+    /// The image is transformed according to its EXIF orientation if the window was configured to
+    /// apply it, and the source is remembered, so the image can be reloaded.
+    pub(crate) fn load(&self, source: Source) -> MVResult<()> {
+        let (image, metadata) = source.load(self.apply_orientation())?;
+
+        self.send(Action::SetImage(image, Box::new(metadata), Some(source)))
+    }
+
+    /// Replace the title of the window
+    pub fn set_title<T: Into<String>>(&self, title: T) -> MVResult<()> {
+        self.send(Action::SetTitle(title.into()))
+    }
+
+    /// Switch the window to, or out of, fullscreen mode
+    pub fn set_fullscreen(&self, value: bool) -> MVResult<()> {
+        self.send(Action::SetFullscreen(value))
+    }
+
+    /// Switch the window to fullscreen mode if it is windowed, and vice versa
+    pub fn toggle_fullscreen(&self) -> MVResult<()> {
+        self.send(Action::ToggleFullscreen)
+    }
+
+    /// Resize the window, such that its inner size is `width` by `height` physical pixels
+    ///
+    /// Note: the window manager may not honour the requested size
+    pub fn resize(&self, width: u32, height: u32) -> MVResult<()> {
+        self.send(Action::Resize(width, height))
+    }
+
+    /// Move the top left corner of the window to the given position on the desktop, in physical
+    /// pixels
+    ///
+    /// Note: the window manager may not honour the requested position
+    pub fn move_to(&self, x: i32, y: i32) -> MVResult<()> {
+        self.send(Action::Move(x, y))
+    }
+
+    /// Show or hide the window
+    pub fn set_visible(&self, value: bool) -> MVResult<()> {
+        self.send(Action::SetVisible(value))
+    }
+
+    /// Bring the window to the front, and request input focus
+    pub fn focus(&self) -> MVResult<()> {
+        self.send(Action::Focus)
+    }
+
+    /// The inner size of the window in physical pixels, or `None` if the window is not open
+    pub fn window_size(&self) -> Option<(u32, u32)> {
+        self.state().map(|state| state.size)
+    }
+
+    /// The scale factor of the monitor which shows the window, or `None` if the window is not
+    /// open
+    pub fn scale_factor(&self) -> Option<f64> {
+        self.state().map(|state| state.scale_factor)
+    }
+
+    /// Whether the window has input focus
+    pub fn is_focused(&self) -> bool {
+        self.state().is_some_and(|state| state.focused)
+    }
+
+    /// Whether the window is in fullscreen mode
+    pub fn is_fullscreen(&self) -> bool {
+        self.state().is_some_and(|state| state.fullscreen)
+    }
+
+    /// Set the zoom level of the view
+    pub fn set_zoom(&self, zoom: Zoom) -> MVResult<()> {
+        self.send(Action::SetZoom(zoom))
+    }
+
+    /// Center the view on the given position of the image, in image pixels
+    pub fn pan_to(&self, x: f32, y: f32) -> MVResult<()> {
+        self.send(Action::PanTo(x, y))
+    }
+
+    /// Capture the contents of the window, as currently shown
+    ///
+    /// Blocks until the window has responded.
+    pub fn screenshot(&self) -> MVResult<RgbaImage> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(Action::Screenshot(sender))?;
+
+        receiver.recv().map_err(|_| MiniViewError::NoResponse)
+    }
+
+    /// The value of the pixel at the given position of the image, at its original precision, or
+    /// `None` if the position is outside of the image
+    ///
+    /// The position is a position of the image as it is shown, i.e. after it has been rotated and
+    /// flipped; see [`ViewTransform`]. The channels are returned in RGBA order. Values of images
+    /// with integer values are normalized to the range from `0.0` to `1.0`, while floating point
+    /// values are returned as they are, regardless of how the image is displayed.
+    ///
+    /// Blocks until the window has responded.
+    ///
+    /// [`ViewTransform`]: ../struct.ViewTransform.html
+    pub fn pixel(&self, x: u32, y: u32) -> MVResult<Option<[f32; 4]>> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(Action::Pixel(x, y, sender))?;
+
+        receiver.recv().map_err(|_| MiniViewError::NoResponse)
+    }
+
+    /// The image as it is shown: rotated and flipped by the view, at its original precision and in
+    /// its original colour type; e.g. to save the transformed image
+    ///
+    /// Blocks until the window has responded.
+    pub fn image(&self) -> MVResult<DynamicImage> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(Action::Image(sender))?;
+
+        receiver.recv().map_err(|_| MiniViewError::NoResponse)
+    }
+
+    /// Blocks until the window has processed all actions which were sent before
+    pub(crate) fn sync(&self) -> MVResult<()> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(Action::Sync(sender))?;
+
+        receiver.recv().map_err(|_| MiniViewError::NoResponse)
+    }
+
+    /// Information about the window, once its first frame has been presented
+    ///
+    /// Returns `None` if the first frame has not been presented yet.
+    pub fn ready(&self) -> Option<WindowReady> {
+        self.shared
+            .ready
+            .lock()
+            .ok()
+            .and_then(|ready| ready.clone())
+    }
+
+    /// Blocks until the first frame of the window has been presented, or the timeout elapsed
+    ///
+    /// Returns `None` if the timeout elapsed, or if the window was closed before its first frame
+    /// was presented.
+    pub fn wait_ready(&self, timeout: Duration) -> Option<WindowReady> {
+        let ready = self.shared.ready.lock().ok()?;
+
+        self.shared
+            .ready_signal
+            .wait_timeout_while(ready, timeout, |ready| {
+                ready.is_none() && !self.shared.exited.lock().is_ok_and(|exited| *exited)
+            })
+            .ok()
+            .and_then(|(ready, _)| ready.clone())
+    }
+
+    /// A snapshot of the state of the window, or `None` if the window is not open
+    pub fn state(&self) -> Option<WindowState> {
+        if !self.is_open() {
+            return None;
+        }
+
+        self.shared.state.lock().ok().map(|state| state.clone())
+    }
+
+    pub(crate) fn send(&self, action: Action) -> MVResult<()> {
+        self.sender
+            .lock()
+            .map_err(|_| MiniViewError::SendActionError)?
+            .send(action)
+            .map_err(|_| MiniViewError::SendActionError)?;
+
+        self.shared.wake();
+
+        Ok(())
+    }
+
+    /// Whether images loaded for the window are transformed according to their EXIF orientation,
+    /// as configured for the window
+    fn apply_orientation(&self) -> bool {
+        self.shared.apply_orientation
+    }
+
+    pub(crate) fn shared(&self) -> Arc<Shared> {
+        Arc::clone(&self.shared)
+    }
+}
+
+impl Clone for MiniViewHandle {
+    fn clone(&self) -> Self {
+        MiniViewHandle {
+            sender: Arc::clone(&self.sender),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Debug for MiniViewHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiniViewHandle")
+            .field("open", &self.is_open())
+            .finish()
+    }
+}
+
+type Waker = Box<dyn Fn() + Send>;
+
+/// Events which signal a change of the state of the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewEvent {
+    /// The window has been opened
+    Opened,
+    /// The inner size of the window changed, in physical pixels
+    Resized { width: u32, height: u32 },
+    /// The scale factor of the monitor which shows the window changed
+    ScaleFactorChanged(f64),
+    /// The window gained, or lost, input focus
+    FocusChanged(bool),
+    /// The window went into, or out of, fullscreen mode
+    FullscreenChanged(bool),
+    /// The first frame of the window has been presented
+    Ready {
+        /// The platform specific identifier of the window, see [`WindowReady::window_id`]
+        ///
+        /// [`WindowReady::window_id`]: struct.WindowReady.html#structfield.window_id
+        window_id: Option<u64>,
+    },
+    /// The window has been closed
+    Closed,
+}
+
+/// A snapshot of the state of the window, as last reported by the window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowState {
+    /// The inner size of the window, in physical pixels
+    pub size: (u32, u32),
+    /// The scale factor of the monitor which shows the window
+    pub scale_factor: f64,
+    /// Whether the window has input focus
+    pub focused: bool,
+    /// Whether the window is in fullscreen mode
+    pub fullscreen: bool,
+    /// The title of the window
+    pub title: String,
+    /// The dimensions of the shown image
+    pub image_size: (u32, u32),
+    /// The factor by which the image is scaled, where `1.0` is the actual size of the image
+    pub zoom: f32,
+    /// The position of the image, in image pixels, which is shown at the center of the window
+    pub center: (f32, f32),
+}
+
+/// Information about a window whose first frame has been presented
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowReady {
+    /// The platform specific identifier of the window, which other programs can use to find the
+    /// window: the X11 window ID on X11 and the `HWND` on Windows; `None` on platforms which do
+    /// not expose a global window identifier, such as Wayland
+    pub window_id: Option<u64>,
+    /// The inner size of the window, in physical pixels
+    pub size: (u32, u32),
+}
+
+/// State shared between the handles and the window which they control
+#[derive(Default)]
+pub(crate) struct Shared {
+    apply_orientation: bool,
+    open: AtomicBool,
+    exited: Mutex<bool>,
+    exit_signal: Condvar,
+    waker: Mutex<Option<Waker>>,
+    state: Mutex<WindowState>,
+    ready: Mutex<Option<WindowReady>>,
+    ready_signal: Condvar,
+    #[cfg(feature = "async")]
+    subscribers: Mutex<Vec<futures_channel::mpsc::UnboundedSender<ViewEvent>>>,
+    #[cfg(feature = "async")]
+    open_waiters: Mutex<Vec<futures_channel::oneshot::Sender<()>>>,
+    #[cfg(feature = "async")]
+    exit_waiters: Mutex<Vec<futures_channel::oneshot::Sender<()>>>,
+}
+
+impl Shared {
+    /// Registers a function which wakes up the event loop of the window, so actions are also
+    /// received while the window waits for input events
+    #[allow(unused)] // Only used by some backends
+    pub(crate) fn set_waker<F: Fn() + Send + 'static>(&self, waker: F) {
+        if let Ok(mut guard) = self.waker.lock() {
+            *guard = Some(Box::new(waker));
+        }
+    }
+
+    fn wake(&self) {
+        if let Ok(guard) = self.waker.lock() {
+            if let Some(wake) = guard.as_ref() {
+                wake();
+            }
+        }
+    }
+
+    /// Publishes the current state of the window to the handles
+    pub(crate) fn set_state(&self, value: WindowState) {
+        let previous = match self.state.lock() {
+            Ok(mut state) => std::mem::replace(&mut *state, value.clone()),
+            Err(_) => return,
+        };
+
+        if previous.size != value.size {
+            let (width, height) = value.size;
+            self.publish(ViewEvent::Resized { width, height });
+        }
+
+        if previous.scale_factor != value.scale_factor {
+            self.publish(ViewEvent::ScaleFactorChanged(value.scale_factor));
+        }
+
+        if previous.focused != value.focused {
+            self.publish(ViewEvent::FocusChanged(value.focused));
+        }
+
+        if previous.fullscreen != value.fullscreen {
+            self.publish(ViewEvent::FullscreenChanged(value.fullscreen));
+        }
+    }
+
+    /// Marks the first frame of the window as presented
+    pub(crate) fn set_ready(&self, value: WindowReady) {
+        let window_id = value.window_id;
+
+        if let Ok(mut ready) = self.ready.lock() {
+            *ready = Some(value);
+        }
+
+        self.ready_signal.notify_all();
+        self.publish(ViewEvent::Ready { window_id });
+    }
+
+    /// Sends an event to the subscribers of the window events
+    #[allow(unused_variables)] // Only used with the async feature
+    fn publish(&self, event: ViewEvent) {
+        #[cfg(feature = "async")]
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.unbounded_send(event).is_ok());
+        }
+    }
+
+    /// Marks the window as open, until the returned guard is dropped
+    pub(crate) fn open(self: &Arc<Self>) -> OpenGuard {
+        self.open.store(true, Ordering::SeqCst);
+        self.publish(ViewEvent::Opened);
+
+        #[cfg(feature = "async")]
+        if let Ok(mut waiters) = self.open_waiters.lock() {
+            for waiter in waiters.drain(..) {
+                let _ = waiter.send(());
+            }
+        }
+
+        OpenGuard {
+            shared: Arc::clone(self),
+        }
+    }
+
+    /// Marks the thread driving the window as exited, once the returned guard is dropped
+    pub(crate) fn exit_guard(self: &Arc<Self>) -> ExitGuard {
+        ExitGuard {
+            shared: Arc::clone(self),
+        }
+    }
+
+    /// Blocks until the thread driving the window has exited, or the timeout elapsed
+    ///
+    /// Returns whether the thread has exited.
+    pub(crate) fn wait_for_exit_timeout(&self, timeout: Duration) -> bool {
+        let Ok(exited) = self.exited.lock() else {
+            return false;
+        };
+
+        self.exit_signal
+            .wait_timeout_while(exited, timeout, |exited| !*exited)
+            .map(|(exited, _)| *exited)
+            .unwrap_or(false)
+    }
+
+    /// Subscribe to the events of the window
+    ///
+    /// The receiver is closed once the thread driving the window has exited.
+    #[cfg(feature = "async")]
+    pub(crate) fn subscribe(&self) -> futures_channel::mpsc::UnboundedReceiver<ViewEvent> {
+        let (sender, receiver) = futures_channel::mpsc::unbounded();
+
+        if let (Ok(exited), Ok(mut subscribers)) = (self.exited.lock(), self.subscribers.lock()) {
+            if !*exited {
+                subscribers.push(sender);
+            }
+        }
+
+        receiver
+    }
+
+    /// A receiver which completes successfully once the window has been opened, and is canceled
+    /// if the thread driving the window exits without opening the window
+    #[cfg(feature = "async")]
+    pub(crate) fn opened(&self) -> futures_channel::oneshot::Receiver<()> {
+        let (sender, receiver) = futures_channel::oneshot::channel();
+
+        if let (Ok(exited), Ok(mut waiters)) = (self.exited.lock(), self.open_waiters.lock()) {
+            if self.open.load(Ordering::SeqCst) {
+                let _ = sender.send(());
+            } else if !*exited {
+                waiters.push(sender);
+            }
+        }
+
+        receiver
+    }
+
+    /// A receiver which completes once the thread driving the window has exited
+    #[cfg(feature = "async")]
+    pub(crate) fn exited(&self) -> futures_channel::oneshot::Receiver<()> {
+        let (sender, receiver) = futures_channel::oneshot::channel();
+
+        if let (Ok(exited), Ok(mut waiters)) = (self.exited.lock(), self.exit_waiters.lock()) {
+            if *exited {
+                let _ = sender.send(());
+            } else {
+                waiters.push(sender);
+            }
+        }
+
+        receiver
+    }
+}
+
+/// Marks the window as closed once dropped, also when the window thread panics
+pub(crate) struct OpenGuard {
+    shared: Arc<Shared>,
+}
+
+impl Drop for OpenGuard {
+    fn drop(&mut self) {
+        self.shared.open.store(false, Ordering::SeqCst);
+        self.shared.publish(ViewEvent::Closed);
+
+        if let Ok(mut guard) = self.shared.waker.lock() {
+            *guard = None;
+        }
+    }
+}
+
+/// Marks the thread driving the window as exited once dropped, also when the thread panics
+pub(crate) struct ExitGuard {
+    shared: Arc<Shared>,
+}
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        if let Ok(mut exited) = self.shared.exited.lock() {
+            *exited = true;
+
+            #[cfg(feature = "async")]
+            {
+                if let Ok(mut waiters) = self.shared.exit_waiters.lock() {
+                    for waiter in waiters.drain(..) {
+                        let _ = waiter.send(());
+                    }
+                }
+
+                // Dropping the senders closes the event streams, and cancels the open waiters
+                if let Ok(mut subscribers) = self.shared.subscribers.lock() {
+                    subscribers.clear();
+                }
+
+                if let Ok(mut waiters) = self.shared.open_waiters.lock() {
+                    waiters.clear();
+                }
+            }
+        }
+
+        self.shared.exit_signal.notify_all();
+
+        // Wake up the threads which wait for the window to become ready; taking the lock ensures
+        // that none of them is in between checking whether the thread exited, and waiting
+        drop(self.shared.ready.lock());
+        self.shared.ready_signal.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MiniViewHandle;
+    use crate::config::ConfigBuilder;
+    use crate::{Action, Source};
+    use imagecrate::{DynamicImage, ImageOutputFormat, RgbImage};
+    use std::io::Cursor;
+    use std::thread;
+
+    /// Reloads the image of a window which shows an image from the given source, and returns the
+    /// result and the image and source which were sent to the window
+    fn reloaded(current: Option<Source>) -> (bool, Option<(DynamicImage, Option<Source>)>) {
+        let (handle, receiver) = MiniViewHandle::new(&ConfigBuilder::from_path("").build());
+
+        let reload = thread::spawn(move || handle.reload());
+        let mut image = None;
+
+        // ends once the handle has been dropped
+        for action in receiver {
+            match action {
+                Action::Source(reply) => {
+                    let _ = reply.send(current.clone());
+                }
+                Action::SetImage(shown, _, source) => image = Some((shown, source)),
+                _ => {}
+            }
+        }
+
+        (reload.join().unwrap().unwrap(), image)
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn reload_the_shown_source() {
+        let source = Source::Bytes(png(3, 2));
+
+        let (reloaded, image) = reloaded(Some(source));
+        let (image, source) = image.expect("no image was sent to the window");
+
+        assert!(reloaded);
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert!(matches!(source, Some(Source::Bytes(bytes)) if bytes == png(3, 2)));
+    }
+
+    #[test]
+    fn reload_images_set_from_memory() {
+        let (reloaded, image) = reloaded(None);
+
+        assert!(!reloaded);
+        assert!(image.is_none());
+    }
+}
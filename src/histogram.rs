@@ -0,0 +1,178 @@
+//! The distribution of the values of an image, per channel and for the luminance.
+
+use crate::channels::Channel;
+use crate::errors::SettingsError;
+use crate::tonemap::Original;
+use imagecrate::DynamicImage;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The number of bins of a histogram
+pub const BINS: usize = 256;
+
+/// The distribution of the values of a single channel
+///
+/// Values are normalized, as for [`ValueRange`], and divided over [`BINS`] bins of equal width
+/// from `0.0` to `1.0`. Values outside of this range are counted in the first or last bin.
+///
+/// [`ValueRange`]: ../enum.ValueRange.html
+/// [`BINS`]: constant.BINS.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelHistogram {
+    bins: Vec<u64>,
+    clipped_low: u64,
+    clipped_high: u64,
+    sum: f64,
+}
+
+impl ChannelHistogram {
+    fn new() -> Self {
+        ChannelHistogram {
+            bins: vec![0; BINS],
+            clipped_low: 0,
+            clipped_high: 0,
+            sum: 0.0,
+        }
+    }
+
+    fn add(&mut self, value: f32) {
+        if value.is_nan() {
+            return;
+        }
+
+        let bin = (value * (BINS - 1) as f32)
+            .round()
+            .clamp(0.0, (BINS - 1) as f32) as usize;
+        self.bins[bin] += 1;
+
+        if value <= 0.0 {
+            self.clipped_low += 1;
+        } else if value >= 1.0 {
+            self.clipped_high += 1;
+        }
+
+        self.sum += f64::from(value);
+    }
+
+    /// The number of values in each bin, from the smallest to the largest values
+    pub fn bins(&self) -> &[u64] {
+        &self.bins
+    }
+
+    /// The number of values which were counted; values which are not a number are left out
+    pub fn count(&self) -> u64 {
+        self.bins.iter().sum()
+    }
+
+    /// The number of values at, or below, the smallest value which can be displayed (`0.0`)
+    pub fn clipped_low(&self) -> u64 {
+        self.clipped_low
+    }
+
+    /// The number of values at, or above, the largest value which can be displayed (`1.0`)
+    pub fn clipped_high(&self) -> u64 {
+        self.clipped_high
+    }
+
+    /// The mean of the values, or `None` if no values were counted
+    pub fn mean(&self) -> Option<f32> {
+        match self.count() {
+            0 => None,
+            count => Some((self.sum / count as f64) as f32),
+        }
+    }
+}
+
+/// The distributions of the values of each channel of an image, and of its luminance
+///
+/// A histogram can be computed for an image with [`new`], or for the image of a source with
+/// [`Source::histogram`].
+///
+/// [`new`]: struct.Histogram.html#method.new
+/// [`Source::histogram`]: ../enum.Source.html#method.histogram
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    channels: [ChannelHistogram; 4],
+    luminance: ChannelHistogram,
+}
+
+impl Histogram {
+    /// Compute the histogram of the image
+    pub fn new(image: &DynamicImage) -> Self {
+        Histogram::of(&Original::new(image.clone()))
+    }
+
+    pub(crate) fn of(original: &Original) -> Self {
+        let mut histogram = Histogram {
+            channels: [
+                ChannelHistogram::new(),
+                ChannelHistogram::new(),
+                ChannelHistogram::new(),
+                ChannelHistogram::new(),
+            ],
+            luminance: ChannelHistogram::new(),
+        };
+
+        for pixel in original.pixels() {
+            for (channel, value) in histogram.channels.iter_mut().zip(pixel) {
+                channel.add(value);
+            }
+
+            // Rec. 709 luma coefficients, as for the luminance view
+            histogram
+                .luminance
+                .add(0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2]);
+        }
+
+        histogram
+    }
+
+    /// The distribution of the values of the given channel
+    pub fn channel(&self, channel: Channel) -> &ChannelHistogram {
+        &self.channels[channel.index()]
+    }
+
+    /// The distribution of the luminance of the pixels
+    pub fn luminance(&self) -> &ChannelHistogram {
+        &self.luminance
+    }
+}
+
+/// The scale of the heights of the bars of the histogram overlay
+///
+/// Written as `linear` or `log`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum HistogramScale {
+    /// The height of a bar is proportional to the number of values in its bin
+    #[default]
+    Linear,
+    /// The height of a bar is proportional to the logarithm of the number of values in its bin,
+    /// so bins with few values remain visible
+    Log,
+}
+
+impl Display for HistogramScale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HistogramScale::Linear => "linear",
+            HistogramScale::Log => "log",
+        })
+    }
+}
+
+impl FromStr for HistogramScale {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "linear" => Ok(HistogramScale::Linear),
+            "log" => Ok(HistogramScale::Log),
+            _ => Err(SettingsError::InvalidHistogramScale(value.to_string())),
+        }
+    }
+}
@@ -2,11 +2,70 @@
 //! from the stdin pipe.
 
 use std::io::{stdin, Read};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
 
 use crate::errors::{ImportError, MiniViewError};
+use crate::metadata::Metadata;
+use crate::profile;
+use crate::Source;
+use imagecrate::{DynamicImage, ImageFormat};
 
 /// Load an image from stdin (blocks the thread)
 pub fn import_image_from_stdin_bytes_block() -> Result<image::DynamicImage, MiniViewError> {
+    let buffer = import_image_bytes_from_stdin_block()?;
+
+    imagecrate::load_from_memory(&buffer).map_err(|_| {
+        MiniViewError::FailedToImport(ImportError::OnStdinBytesUnableToGuessOrLoadFormat)
+    })
+}
+
+/// Open the image file and read its metadata; the image is transformed according to its EXIF
+/// orientation if `orient` is set
+pub(crate) fn open_with_metadata(path: &Path, orient: bool) -> Option<(DynamicImage, Metadata)> {
+    let bytes = std::fs::read(path).ok()?;
+
+    decode_with_metadata(&bytes, Some(path), orient)
+}
+
+/// Decode the image and read its metadata and colour profile; the image is transformed according
+/// to its EXIF orientation if `orient` is set
+///
+/// The format is determined by the extension of the path, if any, like `image::open` does, and
+/// is guessed from the contents otherwise.
+pub(crate) fn decode_with_metadata(
+    bytes: &[u8],
+    path: Option<&Path>,
+    orient: bool,
+) -> Option<(DynamicImage, Metadata)> {
+    let reader = imagecrate::io::Reader::new(std::io::Cursor::new(bytes));
+    let reader = match path.and_then(|path| ImageFormat::from_path(path).ok()) {
+        Some(format) => {
+            let mut reader = reader;
+            reader.set_format(format);
+            reader
+        }
+        None => reader.with_guessed_format().ok()?,
+    };
+
+    let format = reader.format()?;
+    let image = reader.decode().ok()?;
+    let metadata = Metadata {
+        colour_profile: profile::embedded(bytes, format),
+        ..Metadata::from_bytes(bytes)
+    };
+
+    let image = match metadata.orientation {
+        Some(orientation) if orient => orientation.apply(image),
+        _ => image,
+    };
+
+    Some((image, metadata))
+}
+
+/// Read the bytes of an encoded image from stdin, without decoding them (blocks the thread)
+pub fn import_image_bytes_from_stdin_block() -> Result<Vec<u8>, MiniViewError> {
     let mut buffer = Vec::new();
 
     stdin()
@@ -20,9 +79,7 @@ pub fn import_image_from_stdin_bytes_block() -> Result<image::DynamicImage, Mini
         ));
     }
 
-    imagecrate::load_from_memory(&buffer).map_err(|_| {
-        MiniViewError::FailedToImport(ImportError::OnStdinBytesUnableToGuessOrLoadFormat)
-    })
+    Ok(buffer)
 }
 
 // Read a path which should point to an image file from stdin (blocks the thread)
@@ -42,3 +99,89 @@ pub fn read_path_from_stdin_block() -> Result<String, MiniViewError> {
 
     Ok(path.trim().to_string())
 }
+
+/// Read from stdin, and detect which source of an image it describes (blocks the thread)
+///
+/// See [`detect_source`] for the kinds of input which are recognized.
+///
+/// [`detect_source`]: fn.detect_source.html
+pub fn read_source_from_stdin_block() -> Result<Source, MiniViewError> {
+    detect_source(import_image_bytes_from_stdin_block()?)
+}
+
+/// Detect which source of an image the given input describes
+///
+/// The input is recognized as:
+/// * a path, or a list of paths with one path per line, if each line is a path to an existing
+///   file; a list of paths is shown as a gallery;
+/// * an image, if it starts with the magic number of a supported image format;
+/// * a `data:` URI, with a base64 encoded image;
+/// * a base64 encoded image;
+/// * otherwise, as a path or list of paths, which will fail to load if they don't exist.
+///
+/// Existing paths are recognized before images, since the magic numbers of some formats are
+/// only two characters long, and are also the start of common file names, such as `BMW.png` or
+/// `P1000123.JPG`.
+pub fn detect_source(input: Vec<u8>) -> Result<Source, MiniViewError> {
+    let unrecognized = || MiniViewError::FailedToImport(ImportError::OnStdinUnrecognizedContent);
+
+    let text = std::str::from_utf8(&input).ok().map(str::trim);
+    let lines = text
+        .map(|text| {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let paths_exist = !lines.is_empty() && lines.iter().all(|line| Path::new(line).is_file());
+
+    if !paths_exist && imagecrate::guess_format(&input).is_ok() {
+        return Ok(Source::Bytes(input));
+    }
+
+    let text = text.ok_or_else(unrecognized)?;
+
+    if let Some(uri) = text.strip_prefix("data:") {
+        return match uri.split_once(',') {
+            Some((metadata, data)) if metadata.ends_with(";base64") => decode_base64_image(data)
+                .map(Source::Bytes)
+                .ok_or_else(unrecognized),
+            _ => Err(unrecognized()),
+        };
+    }
+
+    if lines.is_empty() {
+        return Err(MiniViewError::FailedToImport(
+            ImportError::OnStdinPathWasEmpty,
+        ));
+    }
+
+    if !paths_exist {
+        if let Some(bytes) = decode_base64_image(text) {
+            return Ok(Source::Bytes(bytes));
+        }
+    }
+
+    let mut paths = lines.into_iter().map(PathBuf::from).collect::<Vec<_>>();
+
+    if paths.len() == 1 {
+        Ok(Source::ByPath(paths.remove(0)))
+    } else {
+        Ok(Source::ByPaths(paths))
+    }
+}
+
+/// Decode base64 encoded text, if it encodes an image in a supported format
+fn decode_base64_image(text: &str) -> Option<Vec<u8>> {
+    let text = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .ok()
+        .filter(|bytes| imagecrate::guess_format(bytes).is_ok())
+}
@@ -0,0 +1,257 @@
+//! Keyboard keys, and the commands which are bound to them.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Keyboard keys which may be bound to a [`Command`]
+///
+/// [`Command`]: enum.Command.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Key {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    #[serde(rename = "0")]
+    Key0,
+    #[serde(rename = "1")]
+    Key1,
+    #[serde(rename = "2")]
+    Key2,
+    #[serde(rename = "3")]
+    Key3,
+    #[serde(rename = "4")]
+    Key4,
+    #[serde(rename = "5")]
+    Key5,
+    #[serde(rename = "6")]
+    Key6,
+    #[serde(rename = "7")]
+    Key7,
+    #[serde(rename = "8")]
+    Key8,
+    #[serde(rename = "9")]
+    Key9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Escape,
+    Space,
+    Enter,
+    Tab,
+    Backspace,
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Plus,
+    Minus,
+    Equals,
+    Comma,
+    Period,
+    Slash,
+    LeftBracket,
+    RightBracket,
+}
+
+/// Commands which can be triggered by pressing a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Command {
+    /// Close the window
+    Quit,
+    /// Switch the window to, or out of, fullscreen mode
+    ToggleFullscreen,
+    /// Leave fullscreen mode if the window is in fullscreen mode, and close the window otherwise
+    LeaveFullscreenOrQuit,
+    /// Zoom in by a single step
+    ZoomIn,
+    /// Zoom out by a single step
+    ZoomOut,
+    /// Show the image at its actual size, or scaled down to fit within the window
+    ZoomToFit,
+    /// Show the image at its actual size
+    ZoomActualSize,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    /// Show the next image of the gallery
+    NextImage,
+    /// Show the previous image of the gallery
+    PreviousImage,
+    /// Composite transparent pixels over the next alternative backdrop
+    CycleTransparency,
+    /// Switch between showing the image and showing its red channel
+    ToggleRedChannel,
+    /// Switch between showing the image and showing its green channel
+    ToggleGreenChannel,
+    /// Switch between showing the image and showing its blue channel
+    ToggleBlueChannel,
+    /// Switch between showing the image and showing its alpha channel
+    ToggleAlphaChannel,
+    /// Switch between showing the image and showing its luminance
+    ToggleLuminance,
+    /// Switch between showing a single colour channel as greyscale or in its own colour
+    ToggleChannelTint,
+    /// Read the channels of the image in the order of the next alternative swizzle
+    CycleSwizzle,
+    /// Increase the exposure by half a stop
+    ExposureUp,
+    /// Decrease the exposure by half a stop
+    ExposureDown,
+    /// Increase the gamma
+    GammaUp,
+    /// Decrease the gamma
+    GammaDown,
+    /// Map the next range of values to the displayed range
+    CycleValueRange,
+    /// Compress values outside of the displayed range with the next tone mapping operator
+    CycleToneMapOperator,
+    /// Reset the exposure, gamma, range and operator to the configured tone mapping
+    ResetToneMapping,
+    /// Show single channels with the next colormap
+    CycleColormap,
+    /// Show, or hide, the colour bar
+    ToggleColourBar,
+    /// Show, or hide, the histogram
+    ToggleHistogram,
+    /// Switch the histogram between a linear and a logarithmic scale
+    ToggleHistogramScale,
+    /// Show, or hide, the info panel
+    ToggleInfo,
+    /// Switch between converting the colours of the image to the output profile, and showing the
+    /// raw values
+    ToggleColourManagement,
+    /// Rotate the shown image by 90 degrees clockwise
+    RotateClockwise,
+    /// Rotate the shown image by 90 degrees counter-clockwise
+    RotateCounterClockwise,
+    /// Mirror the shown image left to right
+    FlipHorizontal,
+    /// Mirror the shown image top to bottom
+    FlipVertical,
+    /// Sample the image with the next filter, when it is scaled
+    CycleFilter,
+    /// Show, or hide, the pixel grid when zoomed in
+    TogglePixelGrid,
+    /// Show the next grid of blocks of pixels, or no grid
+    CycleGrid,
+    /// Show, or hide, the rulers along the edges of the window
+    ToggleRuler,
+}
+
+/// The command bound to the given key by default, if any
+pub(crate) fn default_command(key: Key) -> Option<Command> {
+    match key {
+        Key::Escape => Some(Command::LeaveFullscreenOrQuit),
+        Key::F11 | Key::F => Some(Command::ToggleFullscreen),
+        Key::Q => Some(Command::Quit),
+        Key::Plus | Key::Equals => Some(Command::ZoomIn),
+        Key::Minus => Some(Command::ZoomOut),
+        Key::Key0 => Some(Command::ZoomToFit),
+        Key::Key1 => Some(Command::ZoomActualSize),
+        Key::Left => Some(Command::PanLeft),
+        Key::Right => Some(Command::PanRight),
+        Key::Up => Some(Command::PanUp),
+        Key::Down => Some(Command::PanDown),
+        Key::PageDown | Key::N | Key::Space => Some(Command::NextImage),
+        Key::PageUp | Key::P | Key::Backspace => Some(Command::PreviousImage),
+        Key::T => Some(Command::CycleTransparency),
+        Key::R => Some(Command::ToggleRedChannel),
+        Key::G => Some(Command::ToggleGreenChannel),
+        Key::B => Some(Command::ToggleBlueChannel),
+        Key::A => Some(Command::ToggleAlphaChannel),
+        Key::L => Some(Command::ToggleLuminance),
+        Key::C => Some(Command::ToggleChannelTint),
+        Key::S => Some(Command::CycleSwizzle),
+        Key::RightBracket => Some(Command::ExposureUp),
+        Key::LeftBracket => Some(Command::ExposureDown),
+        Key::Period => Some(Command::GammaUp),
+        Key::Comma => Some(Command::GammaDown),
+        Key::M => Some(Command::CycleValueRange),
+        Key::O => Some(Command::CycleToneMapOperator),
+        Key::X => Some(Command::ResetToneMapping),
+        Key::H => Some(Command::CycleColormap),
+        Key::K => Some(Command::ToggleColourBar),
+        Key::D => Some(Command::ToggleHistogram),
+        Key::E => Some(Command::ToggleHistogramScale),
+        Key::I => Some(Command::ToggleInfo),
+        Key::U => Some(Command::ToggleColourManagement),
+        Key::W => Some(Command::RotateClockwise),
+        Key::Z => Some(Command::RotateCounterClockwise),
+        Key::Y => Some(Command::FlipHorizontal),
+        Key::V => Some(Command::FlipVertical),
+        Key::J => Some(Command::CycleFilter),
+        Key::Slash => Some(Command::TogglePixelGrid),
+        Key::Tab => Some(Command::CycleGrid),
+        Key::Home => Some(Command::ToggleRuler),
+        _ => None,
+    }
+}
+
+/// The commands bound to keys, where the default bindings may be overridden
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyBindings {
+    // A key which maps to `None` has been unbound
+    overrides: HashMap<Key, Option<Command>>,
+}
+
+impl KeyBindings {
+    /// Bind the command to the key, or unbind the key if `command` is `None`
+    pub(crate) fn bind(&mut self, key: Key, command: Option<Command>) {
+        self.overrides.insert(key, command);
+    }
+
+    /// The keys of which the default binding has been overridden, and their commands
+    #[cfg(feature = "serde")]
+    pub(crate) fn overrides(&self) -> impl Iterator<Item = (Key, Option<Command>)> + '_ {
+        self.overrides.iter().map(|(key, command)| (*key, *command))
+    }
+
+    /// The command bound to the given key, if any
+    pub(crate) fn command(&self, key: Key) -> Option<Command> {
+        match self.overrides.get(&key) {
+            Some(command) => *command,
+            None => default_command(key),
+        }
+    }
+}
@@ -10,7 +10,8 @@
 //!
 //! For library usage you may want to start by looking at the [`MiniView.show`] method and
 //! [`ConfigBuilder`] struct, to respectively create a `MiniView` window controlling instance and
-//! conveniently create a configuration which is required for `MiniView.show`.
+//! conveniently create a configuration which is required for `MiniView.show`. If the window
+//! should be driven from the current thread instead, take a look at [`MiniView.run_with_handle`].
 //!
 //! Feel free to post questions, issues, suggestions and feedback at the [`issue tracker`].
 //!
@@ -87,7 +88,8 @@
 //! * OpenBSD
 //! * Windows
 //!
-//! Note: MacOS is not yet supported for this backend.
+//! Note: MacOS is not yet supported for this backend. Since the event loop must be driven from the
+//! main thread on MacOS, only [`MiniView.run`] could work there.
 //!
 //! ### Configuration examples
 //!
@@ -111,35 +113,101 @@
 //! miniview = { version = "*", default-features = false, features = ["backend_pixels"] }
 //! ```
 //!
+//! # Async
+//!
+//! When the `async` feature is enabled, futures are available to show a window
+//! ([`MiniView.show_async`]), to wait until a window has been closed ([`MiniView.closed`] and
+//! [`MiniView.close_async`]), and to receive the events of a window as a stream
+//! ([`MiniViewHandle.events`]). These futures are not tied to a specific async runtime.
+//!
+//! ```toml
+//! [dependencies]
+//! miniview = { version = "*", features = ["async"] }
+//! ```
+//!
+//! # Serde
+//!
+//! When the `serde` feature is enabled, a [`Config`] and its [`Source`] can be serialized and
+//! deserialized, e.g. to store the setup of a viewer in a test fixture, or to send it to another
+//! process. The serialized form of a configuration has a `version` field, and unknown fields are
+//! rejected.
+//!
+//! ```toml
+//! [dependencies]
+//! miniview = { version = "*", features = ["serde"] }
+//! ```
+//!
 //! [`issue tracker`]: https://github.com/foresterre/miniview/issues
 //! [`readme`]: https://github.com/foresterre/miniview/blob/main/README.md
 //! [`MiniView.show`]: struct.MiniView.html#method.show
+//! [`MiniView.run_with_handle`]: struct.MiniView.html#method.run_with_handle
+//! [`MiniView.run`]: struct.MiniView.html#method.run
 //! [`ConfigBuilder`]: config/struct.ConfigBuilder.html
+//! [`MiniView.show_async`]: struct.MiniView.html#method.show_async
+//! [`MiniView.closed`]: struct.MiniView.html#method.closed
+//! [`MiniView.close_async`]: struct.MiniView.html#method.close_async
+//! [`MiniViewHandle.events`]: handle/struct.MiniViewHandle.html#method.events
+//! [`Config`]: config/struct.Config.html
+//! [`Source`]: enum.Source.html
 
 #![allow(clippy::upper_case_acronyms)]
 
 extern crate image as imagecrate; // There is also an image module in piston_window
 
-use crate::config::Config;
+use crate::config::{Backend, Config, DropBehavior};
 use crate::errors::ImportError;
-use crate::io::import_image_from_stdin_bytes_block;
-use imagecrate::DynamicImage;
+use crate::handle::Shared;
+use crate::io::{decode_with_metadata, import_image_bytes_from_stdin_block, open_with_metadata};
+use crate::metadata::Metadata;
+use imagecrate::{DynamicImage, RgbaImage};
 use std::fmt::Debug;
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::thread;
+use std::time::Duration;
 
+pub use crate::channels::{Channel, Channels, Swizzle};
+pub use crate::colormap::Colormap;
 pub use crate::config::ConfigBuilder;
 pub use crate::errors::MiniViewError;
+pub use crate::filter::Filter;
+pub use crate::grid::Grid;
+pub use crate::handle::{MiniViewHandle, ViewEvent, WindowReady, WindowState};
+pub use crate::histogram::{Histogram, HistogramScale};
+pub use crate::profile::ColourProfile;
+pub use crate::tonemap::{ToneMapOperator, ToneMapping, ValueRange};
+pub use crate::transform::{Rotation, ViewTransform};
+pub use crate::view::{Transparency, Zoom};
 
+#[cfg(feature = "async")]
+mod asynchronous;
 #[cfg(feature = "backend_piston_window")]
 pub(crate) mod backend_piston_window;
 #[cfg(feature = "backend_pixels")]
 pub(crate) mod backend_pixels;
 
+pub(crate) mod channels;
+pub(crate) mod colormap;
 pub mod config;
 pub mod errors;
+pub(crate) mod filter;
+pub(crate) mod gallery;
+pub(crate) mod grid;
+pub mod handle;
+pub mod histogram;
 pub mod io;
+pub(crate) mod keys;
+pub mod metadata;
+pub(crate) mod overlay;
+pub mod profile;
+pub mod remote;
+#[cfg(unix)]
+pub mod serve;
+pub(crate) mod settings;
+pub(crate) mod tonemap;
+pub(crate) mod transform;
+pub(crate) mod view;
+pub(crate) mod window;
 
 /// A convenience type alias which represents a regular [`Result`] where the error type is
 /// represented by the [`MiniViewError`], which is the top-level error type for this crate.
@@ -159,7 +227,17 @@ trait FullscreenWhen {
 }
 
 /// The source of an image which will be shown by the view
+///
+/// With the `serde` feature, sources can be serialized and deserialized; the bytes of
+/// [`Source::Bytes`] are written as a base64 string.
+///
+/// [`Source::Bytes`]: enum.Source.html#variant.Bytes
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Source {
     /// A path which points at an image file, e.g. `/home/myuser/image.png` or
     /// `C:/Users/MyUser/image.png`.
@@ -168,32 +246,123 @@ pub enum Source {
     /// A raw (as in an image formatted using a supported encoding as byte stream) image piped or
     /// otherwise provided to the stdin
     StdinBytes,
+
+    /// An image formatted using a supported encoding, which has already been loaded to memory
+    #[cfg_attr(feature = "serde", serde(with = "base64_bytes"))]
+    Bytes(Vec<u8>),
+
+    /// A list of paths which point at image files, which are shown one at a time; see the
+    /// keyboard shortcuts to move between the images
+    ///
+    /// Paths which can't be opened are skipped.
+    ByPaths(Vec<PathBuf>),
 }
 
 impl Source {
-    /// Load the image to memory
-    fn open(&self) -> MVResult<DynamicImage> {
+    /// Load the image to memory, transformed so it is shown upright according to its EXIF
+    /// orientation
+    ///
+    /// For [`Source::StdinBytes`], this blocks until stdin has been closed.
+    ///
+    /// [`Source::StdinBytes`]: enum.Source.html#variant.StdinBytes
+    pub fn open(&self) -> MVResult<DynamicImage> {
+        self.load(true).map(|(image, _)| image)
+    }
+
+    /// Load the image to memory, with its pixels as they are stored, ignoring its EXIF
+    /// orientation
+    ///
+    /// See [`open`] for when this blocks.
+    ///
+    /// [`open`]: enum.Source.html#method.open
+    pub fn open_as_stored(&self) -> MVResult<DynamicImage> {
+        self.load(false).map(|(image, _)| image)
+    }
+
+    /// Load the image, and read its EXIF metadata and its embedded colour profile
+    ///
+    /// See [`open`] for when this blocks.
+    ///
+    /// [`open`]: enum.Source.html#method.open
+    pub fn metadata(&self) -> MVResult<Metadata> {
+        self.load(false).map(|(_, metadata)| metadata)
+    }
+
+    /// Load the image and read its metadata, decoding the image only once; the image is
+    /// transformed so it is shown upright according to its EXIF orientation if `orient` is set
+    ///
+    /// See [`open`] for when this blocks.
+    ///
+    /// [`open`]: enum.Source.html#method.open
+    pub fn open_with_metadata(&self, orient: bool) -> MVResult<(DynamicImage, Metadata)> {
+        self.load(orient)
+    }
+
+    /// Load the image and its metadata; the image is transformed according to its EXIF
+    /// orientation if `orient` is set
+    pub(crate) fn load(&self, orient: bool) -> MVResult<(DynamicImage, Metadata)> {
         match &self {
-            Source::ByPath(path) => imagecrate::open(path.as_path())
-                .map_err(|_| MiniViewError::FailedToImport(ImportError::OnPathNotFound)),
-            Source::StdinBytes => import_image_from_stdin_bytes_block(),
+            Source::ByPath(path) => open_with_metadata(path, orient)
+                .ok_or(MiniViewError::FailedToImport(ImportError::OnPathNotFound)),
+            Source::StdinBytes => {
+                let bytes = import_image_bytes_from_stdin_block()?;
+
+                decode_with_metadata(&bytes, None, orient).ok_or(MiniViewError::FailedToImport(
+                    ImportError::OnStdinBytesUnableToGuessOrLoadFormat,
+                ))
+            }
+            Source::Bytes(bytes) => decode_with_metadata(bytes, None, orient).ok_or(
+                MiniViewError::FailedToImport(ImportError::UnableToDecodeBytes),
+            ),
+            Source::ByPaths(paths) => paths
+                .iter()
+                .find_map(|path| open_with_metadata(path, orient))
+                .ok_or(MiniViewError::FailedToImport(ImportError::OnPathNotFound)),
         }
     }
+
+    /// Load the image to memory, and compute the histogram of its values
+    ///
+    /// See [`open`] for when this blocks.
+    ///
+    /// [`open`]: enum.Source.html#method.open
+    pub fn histogram(&self) -> MVResult<Histogram> {
+        self.open().map(|image| Histogram::new(&image))
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 enum Action {
     Close,
+    SetImage(DynamicImage, Box<Metadata>, Option<Source>),
+    SetTitle(String),
+    SetFullscreen(bool),
+    ToggleFullscreen,
+    Resize(u32, u32),
+    Move(i32, i32),
+    SetVisible(bool),
+    Focus,
+    SetZoom(Zoom),
+    PanTo(f32, f32),
+    Screenshot(mpsc::Sender<RgbaImage>),
+    Pixel(u32, u32, mpsc::Sender<Option<[f32; 4]>>),
+    Image(mpsc::Sender<DynamicImage>),
+    Source(mpsc::Sender<Option<Source>>),
+    Sync(mpsc::Sender<()>),
 }
 
 /// Provides the controls to show and consecutively close a `miniview` window
 ///
-/// For more, see [`show`].
+/// For more, see [`show`]. The window can be controlled from other threads by using the
+/// [`MiniViewHandle`] obtained with [`handle`].
 ///
 /// [`show`]: struct.MiniView.html#method.show
+/// [`MiniViewHandle`]: handle/struct.MiniViewHandle.html
+/// [`handle`]: struct.MiniView.html#method.handle
 pub struct MiniView {
-    sender: mpsc::Sender<Action>,
-    handle: thread::JoinHandle<Result<(), MiniViewError>>,
+    controls: MiniViewHandle,
+    handle: Option<thread::JoinHandle<Result<(), MiniViewError>>>,
+    drop_behavior: DropBehavior,
 }
 
 impl MiniView {
@@ -205,22 +374,90 @@ impl MiniView {
     /// The window can be closed explicitly by calling [`close`] or we can wait until the user will
     /// close the window manually by using [`wait_for_exit`] instead.
     ///
-    /// When a [`MiniView`] instance goes out of scope and is dropped, the thread managing the
-    /// graphical image view window will also die.
+    /// What happens with the window when a [`MiniView`] instance goes out of scope and is dropped,
+    /// is defined by the [`DropBehavior`] of the configuration. By default, the window is detached,
+    /// and will be shown until the user closes the window.
+    ///
+    /// Some platforms, like MacOS, require the window to be driven from the main thread. On these
+    /// platforms [`run`] or [`run_with_handle`] should be used instead.
     ///
     /// [`MiniView`]: struct.MiniView.html
     /// [`close`]: struct.MiniView.html#method.close
     /// [`wait_for_exit`]: struct.MiniView.html#method.wait_for_exit
+    /// [`DropBehavior`]: config/enum.DropBehavior.html
+    /// [`run`]: struct.MiniView.html#method.run
+    /// [`run_with_handle`]: struct.MiniView.html#method.run_with_handle
     pub fn show(config: Config) -> MVResult<Self> {
-        #[cfg(feature = "backend_piston_window")]
-        {
-            backend_piston_window::show(config)
-        }
+        check_backend(&config)?;
+        let (img, metadata) = config.source().load(config.apply_orientation())?;
+        let drop_behavior = config.drop_behavior();
 
-        #[cfg(feature = "backend_pixels")]
-        {
-            backend_pixels::show(config)
-        }
+        let (controls, receiver) = MiniViewHandle::new(&config);
+        let shared = controls.shared();
+
+        let handle = thread::spawn(move || {
+            let _exit = shared.exit_guard();
+            run_backend(config, img, metadata, receiver, shared)
+        });
+
+        Ok(MiniView {
+            controls,
+            handle: Some(handle),
+            drop_behavior,
+        })
+    }
+
+    /// Show a `miniview` window, and drive it from the current thread
+    ///
+    /// Blocks until the window has been closed. Use [`run_with_handle`] to control the window
+    /// from other threads.
+    ///
+    /// [`run_with_handle`]: struct.MiniView.html#method.run_with_handle
+    pub fn run(config: Config) -> MVResult<()> {
+        Self::run_with_handle(config, |_handle| {})
+    }
+
+    /// Show a `miniview` window, and drive it from the current thread
+    ///
+    /// Before the window is shown, `with_handle` is called with a [`MiniViewHandle`], which can
+    /// be moved to other threads to control the window. Actions which are send before the window
+    /// has been created, will be processed once the window has been created.
+    ///
+    /// Blocks until the window has been closed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use miniview::{ConfigBuilder, MiniView};
+    /// use std::time::Duration;
+    ///
+    /// let config = ConfigBuilder::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/plant.jpg"))
+    ///     .build();
+    ///
+    /// MiniView::run_with_handle(config, |handle| {
+    ///     std::thread::spawn(move || {
+    ///         std::thread::sleep(Duration::from_millis(1000));
+    ///         handle.request_close().expect("unable to close miniview");
+    ///     });
+    /// })
+    /// .expect("unable to run miniview");
+    /// ```
+    ///
+    /// [`MiniViewHandle`]: handle/struct.MiniViewHandle.html
+    pub fn run_with_handle<F>(config: Config, with_handle: F) -> MVResult<()>
+    where
+        F: FnOnce(MiniViewHandle),
+    {
+        check_backend(&config)?;
+        let (img, metadata) = config.source().load(config.apply_orientation())?;
+
+        let (controls, receiver) = MiniViewHandle::new(&config);
+        let shared = controls.shared();
+
+        with_handle(controls);
+
+        let _exit = shared.exit_guard();
+        run_backend(config, img, metadata, receiver, shared)
     }
 
     /// Sends a 'close window' event to the thread managing the graphical window and waits for the
@@ -244,25 +481,127 @@ impl MiniView {
     pub fn wait_for_exit(self) -> MVResult<()> {
         wait_for_exit(self)
     }
+
+    /// Checks whether the thread managing the graphical window has returned, without blocking
+    ///
+    /// Returns `Ok(true)` if the thread has returned, and `Ok(false)` if the window is still
+    /// being shown. If the thread returned an error, the error is returned instead.
+    pub fn try_wait(&mut self) -> MVResult<bool> {
+        match &self.handle {
+            Some(handle) if !handle.is_finished() => Ok(false),
+            _ => self.join().map(|_| true),
+        }
+    }
+
+    /// Waits until the thread managing the graphical window returns, or the timeout elapses
+    ///
+    /// Returns `Ok(true)` if the thread has returned, and `Ok(false)` if the timeout elapsed
+    /// while the window is still being shown. If the thread returned an error, the error is
+    /// returned instead.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> MVResult<bool> {
+        if self.controls.shared().wait_for_exit_timeout(timeout) {
+            self.join().map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// A handle which can be used to control the window from other threads
+    pub fn handle(&self) -> MiniViewHandle {
+        self.controls.clone()
+    }
+
+    fn join(&mut self) -> MVResult<()> {
+        match self.handle.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_err| MiniViewError::ViewThreadFailedToJoin)
+                .and_then(|inner| inner),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MiniView {
+    fn drop(&mut self) {
+        if self.handle.is_none() {
+            return;
+        }
+
+        match self.drop_behavior {
+            DropBehavior::CloseAndJoin => {
+                let _ = self.controls.request_close();
+                let _ = self.join();
+            }
+            DropBehavior::Detach => {}
+            DropBehavior::WaitForUser => {
+                let _ = self.join();
+            }
+        }
+    }
+}
+
+/// Backends are selected at compile time, so a configuration which requires another backend
+/// can't be shown
+fn check_backend(config: &Config) -> MVResult<()> {
+    match config.backend() {
+        Some(backend) if backend != Backend::compiled() => Err(MiniViewError::BackendNotAvailable(
+            backend.to_string(),
+            Backend::compiled().to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn run_backend(
+    config: Config,
+    img: DynamicImage,
+    metadata: Metadata,
+    receiver: mpsc::Receiver<Action>,
+    shared: Arc<Shared>,
+) -> MVResult<()> {
+    #[cfg(feature = "backend_piston_window")]
+    {
+        backend_piston_window::run(config, img, metadata, receiver, shared)
+    }
+
+    #[cfg(feature = "backend_pixels")]
+    {
+        backend_pixels::run(config, img, metadata, receiver, shared)
+    }
+}
+
+pub(crate) fn close(mut mini_view: MiniView) -> MVResult<()> {
+    if mini_view.handle.is_none() {
+        return Ok(());
+    }
+
+    mini_view.controls.request_close()?;
+    mini_view.join()
 }
 
-pub(crate) fn close(mini_view: MiniView) -> MVResult<()> {
-    mini_view
-        .sender
-        .send(Action::Close)
-        .map_err(|_err| MiniViewError::SendStopError)?;
-
-    mini_view
-        .handle
-        .join()
-        .map_err(|_err| MiniViewError::ViewThreadFailedToJoin)
-        .and_then(|inner| inner)
+pub(crate) fn wait_for_exit(mut mini_view: MiniView) -> MVResult<()> {
+    mini_view.join()
 }
 
-pub(crate) fn wait_for_exit(mini_view: MiniView) -> MVResult<()> {
-    mini_view
-        .handle
-        .join()
-        .map_err(|_err| MiniViewError::ViewThreadFailedToJoin)
-        .and_then(|inner| inner)
+/// Serializes bytes as a base64 string, rather than as a sequence of numbers
+#[cfg(feature = "serde")]
+mod base64_bytes {
+    use base64::Engine;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let data = String::deserialize(deserializer)?;
+
+        base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(D::Error::custom)
+    }
 }
@@ -1,21 +1,106 @@
 extern crate image as imagecrate; // There is also an image module in piston_window
 
+#[cfg(unix)]
+use base64::Engine;
 use clap::{
     crate_authors, crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches,
+    SubCommand,
 };
-use miniview::config::ConfigBuilder;
-use miniview::errors::MiniViewError;
-use miniview::io::read_path_from_stdin_block;
-use miniview::{MiniView, Source};
+use miniview::config::{Config, ConfigBuilder};
+use miniview::errors::{ImportError, MiniViewError};
+use miniview::histogram::{ChannelHistogram, BINS};
+use miniview::io::{
+    import_image_bytes_from_stdin_block, read_path_from_stdin_block, read_source_from_stdin_block,
+};
+use miniview::metadata::Metadata;
+#[cfg(unix)]
+use miniview::remote::Socket;
+use miniview::{
+    Channel, Channels, Colormap, ColourProfile, Filter, Grid, Histogram, HistogramScale, MiniView,
+    MiniViewHandle, Rotation, Source, Swizzle, ToneMapOperator, Transparency, ValueRange,
+    WindowReady,
+};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+/// Remote control is only supported on Unix platforms, where there is a socket to listen on
+#[cfg(not(unix))]
+enum Socket {}
+
+/// The positional path which reads the input from stdin, like --from-stdin
+const STDIN_PATH: &str = "-";
+
+/// How long --close-after waits for the window to present its first frame
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Set for the process started by --detach, which shows the window
+const DETACHED_ENV: &str = "MINIVIEW_DETACHED";
+
+/// How often --reuse hands the image over again when another instance claimed the socket first
+#[cfg(unix)]
+const REUSE_ATTEMPTS: usize = 3;
+
 const IMPORT_FROM_PATH_CLI: &str = "import_from_path";
 const IMPORT_FROM_STDIN_BYTES: &str = "import_from_stdin_bytes";
 const IMPORT_FROM_STDIN_PATH: &str = "import_from_stdin_path";
+const IMPORT_FROM_STDIN: &str = "import_from_stdin";
 const POSITIONAL_FROM_PATH: &str = "positional_from_path";
 const OPTION_FULLSCREEN: &str = "fullscreen";
+const OPTION_NO_FULLSCREEN: &str = "no_fullscreen";
 const OPTION_WINDOW_RESIZE: &str = "window_resize";
+const OPTION_NO_WINDOW_RESIZE: &str = "no_window_resize";
+const OPTION_TRANSPARENCY: &str = "transparency";
+const OPTION_CHANNELS: &str = "channels";
+const OPTION_TINT: &str = "tint";
+const OPTION_NO_TINT: &str = "no_tint";
+const OPTION_SWIZZLE: &str = "swizzle";
+const OPTION_RANGE: &str = "range";
+const OPTION_EXPOSURE: &str = "exposure";
+const OPTION_GAMMA: &str = "gamma";
+const OPTION_TONE_MAPPING: &str = "tone_mapping";
+const OPTION_COLORMAP: &str = "colormap";
+const OPTION_COLOUR_BAR: &str = "colour_bar";
+const OPTION_NO_COLOUR_BAR: &str = "no_colour_bar";
+const OPTION_HISTOGRAM: &str = "histogram";
+const OPTION_NO_HISTOGRAM: &str = "no_histogram";
+const OPTION_HISTOGRAM_SCALE: &str = "histogram_scale";
+const OPTION_INFO: &str = "info";
+const OPTION_INFO_PANEL: &str = "info_panel";
+const OPTION_NO_INFO_PANEL: &str = "no_info_panel";
+const OPTION_IGNORE_ORIENTATION: &str = "ignore_orientation";
+const OPTION_APPLY_ORIENTATION: &str = "apply_orientation";
+const OPTION_UNMANAGED: &str = "unmanaged";
+const OPTION_MANAGED: &str = "managed";
+const OPTION_OUTPUT_PROFILE: &str = "output_profile";
+const OPTION_ROTATE: &str = "rotate";
+const OPTION_FLIP_HORIZONTAL: &str = "flip_horizontal";
+const OPTION_NO_FLIP_HORIZONTAL: &str = "no_flip_horizontal";
+const OPTION_FLIP_VERTICAL: &str = "flip_vertical";
+const OPTION_NO_FLIP_VERTICAL: &str = "no_flip_vertical";
+const OPTION_SAVE_AS: &str = "save_as";
+const OPTION_FILTER: &str = "filter";
+const OPTION_NO_PIXEL_GRID: &str = "no_pixel_grid";
+const OPTION_PIXEL_GRID: &str = "pixel_grid";
+const OPTION_PIXEL_GRID_THRESHOLD: &str = "pixel_grid_threshold";
+const OPTION_GRID: &str = "grid";
+const OPTION_RULER: &str = "ruler";
+const OPTION_NO_RULER: &str = "no_ruler";
 const OPTION_CLOSE_AFTER: &str = "close_after_ms";
+const OPTION_LISTEN: &str = "listen";
+const OPTION_DETACH: &str = "detach";
+const OPTION_PRINT_PID: &str = "print_pid";
+const OPTION_READY: &str = "ready";
+const OPTION_READY_FD: &str = "ready_fd";
+const OPTION_COMMANDS_FROM_STDIN: &str = "commands_from_stdin";
+const OPTION_REUSE: &str = "reuse";
+const OPTION_REUSE_KEY: &str = "reuse_key";
+const REMOTE_SEND: &str = "send";
+const SERVE: &str = "serve";
+const SERVE_SOCKET: &str = "socket";
+const SERVE_TCP: &str = "tcp";
 
 // Perhaps it will be better to use the lower level gfx tools instead of piston_window.
 fn cli() -> App<'static, 'static> {
@@ -24,10 +109,16 @@ fn cli() -> App<'static, 'static> {
         .version(crate_version!())
         .about(crate_description!())
         .setting(AppSettings::NextLineHelp)
-        .usage("miniview (<PATH> OR --from-path <PATH> OR --from-stdin-bytes OR --from-stdin-path) \
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .usage("miniview (<PATH> OR --from-path <PATH> OR --from-stdin OR --from-stdin-bytes OR --from-stdin-path) \
             [--fullscreen] \
             [--allow-window-resizing] \
-            [--close-after <ms>]")
+            [--close-after <ms> OR --commands-from-stdin] \
+            [--ready] [--ready-fd <FD>] \
+            [--detach [--print-pid]] \
+            [--listen <SOCKET> OR --reuse [--reuse-key <KEY>]]
+    miniview --send <SOCKET> <COMMAND>...
+    miniview serve [--socket <SOCKET> OR --tcp <ADDRESS>]")
         .arg(
             Arg::with_name(IMPORT_FROM_PATH_CLI)
                 .long("from-path")
@@ -35,41 +126,337 @@ fn cli() -> App<'static, 'static> {
                 .takes_value(true)
                 .value_name("PATH")
                 .help("Load an image from the given path and display it.")
-                .conflicts_with_all(&[IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH])
-                .required_unless_one(&[IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH]),
+                .conflicts_with_all(&[IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH])
+                .required_unless_one(&[IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH, REMOTE_SEND]),
+        )
+        .arg(
+            Arg::with_name(IMPORT_FROM_STDIN)
+                .long("from-stdin")
+                .help("Load an image from stdin and display it, detecting whether stdin contains an image \
+                    (as bytes), a base64 encoded image, a data URI, a path or a list of paths (one per line). \
+                    A list of paths is shown as a gallery. Same as giving '-' as the path.")
+                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH])
+                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH, REMOTE_SEND]),
         )
         .arg(
-            Arg::with_name("import_from_stdin_path")
+            Arg::with_name(IMPORT_FROM_STDIN_PATH)
                 .long("from-stdin-path")
                 .short("s")
                 .help("Load an image from the path received by stdin and display it.")
-                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_BYTES, POSITIONAL_FROM_PATH])
-                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_BYTES, POSITIONAL_FROM_PATH]),
+                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_BYTES, POSITIONAL_FROM_PATH])
+                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_BYTES, POSITIONAL_FROM_PATH, REMOTE_SEND]),
         )
         .arg(
             Arg::with_name(IMPORT_FROM_STDIN_BYTES)
                 .long("from-stdin-bytes")
                 .short("b")
                 .help("Load an image received by stdin (image as bytes), guess its format and display it.")
-                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH])
-                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH]),
+                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH])
+                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_PATH, POSITIONAL_FROM_PATH, REMOTE_SEND]),
         )
         .arg(
             Arg::with_name(POSITIONAL_FROM_PATH)
-                .help("Load an image from the given path and display it.")
+                .help("Load an image from the given path and display it. If the path is '-', the image is \
+                    loaded from stdin, like --from-stdin.")
                 .index(1)
-                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_PATH, IMPORT_FROM_STDIN_BYTES])
-                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN_PATH, IMPORT_FROM_STDIN_BYTES]),
+                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_PATH, IMPORT_FROM_STDIN_BYTES])
+                .required_unless_one(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_PATH, IMPORT_FROM_STDIN_BYTES, REMOTE_SEND]),
         )
         .arg(
             Arg::with_name(OPTION_FULLSCREEN)
                 .help("Instruct the window to go into fullscreen mode")
                 .long("fullscreen")
+                .overrides_with(OPTION_NO_FULLSCREEN)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_FULLSCREEN)
+                .help("Don't go into fullscreen mode, e.g. when the settings enable it")
+                .long("no-fullscreen")
+                .overrides_with(OPTION_FULLSCREEN)
         )
         .arg(
             Arg::with_name(OPTION_WINDOW_RESIZE)
                 .help("Allow window resizing (doesn't resize the image)")
                 .long("allow-window-resizing")
+                .overrides_with(OPTION_NO_WINDOW_RESIZE)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_WINDOW_RESIZE)
+                .help("Don't allow window resizing, e.g. when the settings allow it")
+                .long("no-window-resizing")
+                .overrides_with(OPTION_WINDOW_RESIZE)
+        )
+        .arg(
+            Arg::with_name(OPTION_TRANSPARENCY)
+                .help("What transparent pixels are shown over: 'checkerboard' (default), 'black', 'white' \
+                    or a colour written as '#rrggbb'. Press 't' to cycle through the alternatives.")
+                .long("transparency")
+                .takes_value(true)
+                .value_name("BACKDROP")
+                .validator(|value| value.parse::<Transparency>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_CHANNELS)
+                .help("Which channels are shown: 'all' (default), 'red', 'green', 'blue', 'alpha' or 'luminance'. \
+                    A single channel is shown as greyscale, unless --tint is given.")
+                .long("channels")
+                .takes_value(true)
+                .value_name("CHANNELS")
+                .validator(|value| value.parse::<Channels>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_TINT)
+                .help("Show a single colour channel in its own colour, instead of as greyscale")
+                .long("tint")
+                .overrides_with(OPTION_NO_TINT)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_TINT)
+                .help("Show a single colour channel as greyscale, e.g. when the settings tint it")
+                .long("no-tint")
+                .overrides_with(OPTION_TINT)
+        )
+        .arg(
+            Arg::with_name(OPTION_SWIZZLE)
+                .help("The order in which the channels of the image are read, e.g. 'bgr' for an image which \
+                    was stored in BGR order")
+                .long("swizzle")
+                .takes_value(true)
+                .value_name("ORDER")
+                .validator(|value| value.parse::<Swizzle>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_RANGE)
+                .help("Which values are mapped to the displayed range: 'full' (default), 'auto' (the minimum \
+                    and maximum of the image), 'percentile:LOW,HIGH' or 'window:LEVEL,WIDTH'. Press 'm' to cycle \
+                    between the given range and the alternatives.")
+                .long("range")
+                .takes_value(true)
+                .value_name("RANGE")
+                .validator(|value| value.parse::<ValueRange>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_EXPOSURE)
+                .help("The exposure in stops, by which values are scaled before they are displayed; 0 by default")
+                .long("exposure")
+                .takes_value(true)
+                .value_name("STOPS")
+                .allow_hyphen_values(true)
+                .validator(|value| value.parse::<f32>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_GAMMA)
+                .help("The gamma with which values are displayed; 1 by default")
+                .long("gamma")
+                .takes_value(true)
+                .value_name("GAMMA")
+                .validator(|value| value.parse::<f32>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_TONE_MAPPING)
+                .help("How values outside of the displayed range are shown: 'clamp' (default), 'reinhard' or \
+                    'aces'. Press 'o' to cycle through the alternatives.")
+                .long("tone-mapping")
+                .takes_value(true)
+                .value_name("OPERATOR")
+                .validator(|value| value.parse::<ToneMapOperator>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_COLORMAP)
+                .help("The colormap with which greyscale images, single channels and the luminance are shown: \
+                    'greyscale' (default), 'viridis', 'magma', 'inferno', 'turbo', or the diverging 'coolwarm' \
+                    and 'rdbu'. The values within --range are mapped from the first to the last colour. Press \
+                    'h' to cycle through the colormaps.")
+                .long("colormap")
+                .takes_value(true)
+                .value_name("COLORMAP")
+                .validator(|value| value.parse::<Colormap>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_COLOUR_BAR)
+                .help("Show a colour bar with the values of the range and the colours they are shown as. \
+                    Press 'k' to show or hide it.")
+                .long("colour-bar")
+                .alias("color-bar")
+                .overrides_with(OPTION_NO_COLOUR_BAR)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_COLOUR_BAR)
+                .help("Don't show the colour bar, e.g. when the settings show it")
+                .long("no-colour-bar")
+                .alias("no-color-bar")
+                .overrides_with(OPTION_COLOUR_BAR)
+        )
+        .arg(
+            Arg::with_name(OPTION_HISTOGRAM)
+                .help("Show the histogram of the channels which are shown, with markers for clipped values. \
+                    Press 'd' to show or hide it.")
+                .long("histogram")
+                .overrides_with(OPTION_NO_HISTOGRAM)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_HISTOGRAM)
+                .help("Don't show the histogram, e.g. when the settings show it")
+                .long("no-histogram")
+                .overrides_with(OPTION_HISTOGRAM)
+        )
+        .arg(
+            Arg::with_name(OPTION_HISTOGRAM_SCALE)
+                .help("The scale of the bars of the histogram: 'linear' (default) or 'log'. Press 'e' to \
+                    switch between the scales.")
+                .long("histogram-scale")
+                .takes_value(true)
+                .value_name("SCALE")
+                .validator(|value| value.parse::<HistogramScale>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_INFO)
+                .help("Print the dimensions, the EXIF metadata and the histogram of the image, instead of \
+                    showing it")
+                .long("info")
+        )
+        .arg(
+            Arg::with_name(OPTION_INFO_PANEL)
+                .help("Show a panel with the dimensions and the EXIF metadata of the image, such as the camera, \
+                    the exposure and where and when it was taken. Press 'i' to show or hide it.")
+                .long("info-panel")
+                .overrides_with(OPTION_NO_INFO_PANEL)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_INFO_PANEL)
+                .help("Don't show the info panel, e.g. when the settings show it")
+                .long("no-info-panel")
+                .overrides_with(OPTION_INFO_PANEL)
+        )
+        .arg(
+            Arg::with_name(OPTION_IGNORE_ORIENTATION)
+                .help("Show the pixels of the image as they are stored, instead of rotating and mirroring them \
+                    upright according to the EXIF orientation of the image")
+                .long("ignore-orientation")
+                .overrides_with(OPTION_APPLY_ORIENTATION)
+        )
+        .arg(
+            Arg::with_name(OPTION_APPLY_ORIENTATION)
+                .help("Turn the image upright according to its EXIF orientation, e.g. when the settings ignore it")
+                .long("apply-orientation")
+                .overrides_with(OPTION_IGNORE_ORIENTATION)
+        )
+        .arg(
+            Arg::with_name(OPTION_UNMANAGED)
+                .help("Show the raw values of images with an embedded ICC colour profile, instead of converting \
+                    their colours to sRGB or the --output-profile. Press 'u' to switch between both.")
+                .long("unmanaged")
+                .overrides_with(OPTION_MANAGED)
+        )
+        .arg(
+            Arg::with_name(OPTION_MANAGED)
+                .help("Convert the colours of images with an embedded ICC colour profile, e.g. when the settings disable it")
+                .long("managed")
+                .overrides_with(OPTION_UNMANAGED)
+        )
+        .arg(
+            Arg::with_name(OPTION_OUTPUT_PROFILE)
+                .help("The ICC profile of the display, to which the colours of images with an embedded colour \
+                    profile are converted, instead of sRGB")
+                .long("output-profile")
+                .takes_value(true)
+                .value_name("PATH")
+        )
+        .arg(
+            Arg::with_name(OPTION_ROTATE)
+                .help("Rotate the shown image clockwise by 0, 90, 180 or 270 degrees; negative angles rotate \
+                    counter-clockwise. Press 'w' and 'z' to rotate it further.")
+                .long("rotate")
+                .takes_value(true)
+                .value_name("DEGREES")
+                .allow_hyphen_values(true)
+                .validator(|value| value.parse::<Rotation>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_FLIP_HORIZONTAL)
+                .help("Mirror the shown image left to right, before it is rotated. Press 'y' to flip it.")
+                .long("flip-horizontal")
+                .overrides_with(OPTION_NO_FLIP_HORIZONTAL)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_FLIP_HORIZONTAL)
+                .help("Don't mirror the shown image left to right, e.g. when the settings flip it")
+                .long("no-flip-horizontal")
+                .overrides_with(OPTION_FLIP_HORIZONTAL)
+        )
+        .arg(
+            Arg::with_name(OPTION_FLIP_VERTICAL)
+                .help("Mirror the shown image top to bottom, before it is rotated. Press 'v' to flip it.")
+                .long("flip-vertical")
+                .overrides_with(OPTION_NO_FLIP_VERTICAL)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_FLIP_VERTICAL)
+                .help("Don't mirror the shown image top to bottom, e.g. when the settings flip it")
+                .long("no-flip-vertical")
+                .overrides_with(OPTION_FLIP_VERTICAL)
+        )
+        .arg(
+            Arg::with_name(OPTION_FILTER)
+                .help("How the image is sampled when it is scaled: 'auto' (nearest above 100% zoom, area below it), \
+                    'nearest' for sharp pixels, 'bilinear', or 'area' to average the pixels using mipmaps. \
+                    Press 'j' to cycle through the filters.")
+                .long("filter")
+                .takes_value(true)
+                .value_name("FILTER")
+                .validator(|value| value.parse::<Filter>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_PIXEL_GRID)
+                .help("Don't show a grid around the pixels when zoomed in. Press '/' to show or hide it.")
+                .long("no-pixel-grid")
+                .overrides_with(OPTION_PIXEL_GRID)
+        )
+        .arg(
+            Arg::with_name(OPTION_PIXEL_GRID)
+                .help("Show a grid around the pixels when zoomed in, e.g. when the settings hide it")
+                .long("pixel-grid")
+                .overrides_with(OPTION_NO_PIXEL_GRID)
+        )
+        .arg(
+            Arg::with_name(OPTION_PIXEL_GRID_THRESHOLD)
+                .help("The zoom factor beyond which a grid around the pixels is shown; 8 (800%) by default")
+                .long("pixel-grid-threshold")
+                .takes_value(true)
+                .value_name("ZOOM")
+                .validator(|value| value.parse::<f32>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_GRID)
+                .help("Show a grid of blocks of pixels, e.g. '8' or '16' to check the alignment of JPEG blocks, \
+                    or '64x32' for tiles which are 64 pixels wide and 32 pixels high. Press Tab to cycle \
+                    through a few common grids.")
+                .long("grid")
+                .takes_value(true)
+                .value_name("SIZE")
+                .validator(|value| value.parse::<Grid>().map(|_| ()).map_err(|err| err.to_string()))
+        )
+        .arg(
+            Arg::with_name(OPTION_RULER)
+                .help("Show rulers with the coordinates of the image along the top and left edges of the window. \
+                    Press Home to show or hide them.")
+                .long("ruler")
+                .overrides_with(OPTION_NO_RULER)
+        )
+        .arg(
+            Arg::with_name(OPTION_NO_RULER)
+                .help("Don't show rulers, e.g. when the settings show them")
+                .long("no-ruler")
+                .overrides_with(OPTION_RULER)
+        )
+        .arg(
+            Arg::with_name(OPTION_SAVE_AS)
+                .help("Write the image, rotated and flipped by --rotate, --flip-horizontal and --flip-vertical, \
+                    to the given path instead of showing it; the format is chosen by the extension of the path")
+                .long("save-as")
+                .takes_value(true)
+                .value_name("PATH")
+                .conflicts_with_all(&[OPTION_INFO, OPTION_REUSE, OPTION_LISTEN, OPTION_DETACH, OPTION_COMMANDS_FROM_STDIN, OPTION_CLOSE_AFTER])
         )
         .arg(
             Arg::with_name(OPTION_CLOSE_AFTER)
@@ -79,9 +466,135 @@ fn cli() -> App<'static, 'static> {
                 .number_of_values(1)
                 .validator(|f| f.parse::<u64>().map(|_| ()).map_err(|_| String::from("value should be a natural number")))
         )
+        .arg(
+            Arg::with_name(OPTION_COMMANDS_FROM_STDIN)
+                .help("Run the commands received by stdin, one per line, against the window, e.g. \
+                    'load image.png', 'zoom 200', 'zoom fit', 'pan 100 50', 'title my window', \
+                    'wait 500' (milliseconds), 'screenshot out.png', 'save out.png' or 'close'. \
+                    Lines starting with '#' are ignored.")
+                .long("commands-from-stdin")
+                .conflicts_with_all(&[IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_BYTES, IMPORT_FROM_STDIN_PATH, OPTION_CLOSE_AFTER])
+        )
+        .arg(
+            Arg::with_name(OPTION_READY)
+                .help("Print a line to stdout once the first frame has been presented: \
+                    'ready window_id=<ID> size=<WIDTH>x<HEIGHT>', where ID is the X11 window ID \
+                    or Windows HWND in decimal, or 'none' if the platform does not expose one")
+                .long("ready")
+        )
+        .arg(
+            Arg::with_name(OPTION_READY_FD)
+                .help("Write the line printed by --ready to the given file descriptor instead, \
+                    and close it (Unix only)")
+                .long("ready-fd")
+                .takes_value(true)
+                .value_name("FD")
+                .validator(|f| f.parse::<i32>().map(|_| ()).map_err(|_| String::from("value should be a file descriptor")))
+        )
+        .arg(
+            Arg::with_name(OPTION_DETACH)
+                .help("Load the image, then show it from a background process and exit immediately; \
+                    errors while loading the image are still reported by the exit code. The background \
+                    process has no stdin or stdout, so --ready and --commands-from-stdin can't be used.")
+                .long("detach")
+                .conflicts_with_all(&[OPTION_READY, OPTION_COMMANDS_FROM_STDIN])
+        )
+        .arg(
+            Arg::with_name(OPTION_PRINT_PID)
+                .help("Print the process ID of the background process started by --detach")
+                .long("print-pid")
+                .requires(OPTION_DETACH)
+        )
+        .arg(
+            Arg::with_name(OPTION_LISTEN)
+                .help("Listen for remote control commands on a Unix domain socket at the given path")
+                .long("listen")
+                .takes_value(true)
+                .value_name("SOCKET")
+        )
+        .arg(
+            Arg::with_name(OPTION_REUSE)
+                .help("Show the image in an already running instance which was started with --reuse, \
+                    and exit; if no such instance is running, start normally (Unix only)")
+                .long("reuse")
+                .conflicts_with(OPTION_LISTEN)
+        )
+        .arg(
+            Arg::with_name(OPTION_REUSE_KEY)
+                .help("Only reuse instances which were started with the same key [default: default]")
+                .long("reuse-key")
+                .takes_value(true)
+                .value_name("KEY")
+                .requires(OPTION_REUSE)
+        )
+        .arg(
+            Arg::with_name(REMOTE_SEND)
+                .help("Send a command to the window listening at the given socket, e.g. \
+                    'load image.png', 'zoom 200', 'zoom fit', 'pan 100 50', 'title my window', \
+                    'screenshot out.png', 'save out.png', 'state' or 'close'. \
+                    'load_bytes' without data sends an image received by stdin.")
+                .long("send")
+                .value_name("SOCKET> <COMMAND")
+                .min_values(2)
+                .multiple(true)
+                .allow_hyphen_values(true)
+                .conflicts_with_all(&[IMPORT_FROM_PATH_CLI, IMPORT_FROM_STDIN, IMPORT_FROM_STDIN_PATH, IMPORT_FROM_STDIN_BYTES, POSITIONAL_FROM_PATH, OPTION_LISTEN])
+        )
+        .subcommand(
+            SubCommand::with_name(SERVE)
+                .about("Show images pushed by other processes in named windows (Unix only). \
+                    See the documentation of the miniview::serve module for the wire format.")
+                .setting(AppSettings::NextLineHelp)
+                .arg(
+                    Arg::with_name(SERVE_SOCKET)
+                        .help("Listen on a Unix domain socket at the given path \
+                            [default: $XDG_RUNTIME_DIR/miniview/serve.sock]")
+                        .long("socket")
+                        .takes_value(true)
+                        .value_name("SOCKET")
+                        .conflicts_with(SERVE_TCP)
+                )
+                .arg(
+                    Arg::with_name(SERVE_TCP)
+                        .help("Listen on a TCP socket at the given loopback address, e.g. 127.0.0.1:7878")
+                        .long("tcp")
+                        .takes_value(true)
+                        .value_name("ADDRESS")
+                        .validator(|address| address.parse::<std::net::SocketAddr>().map(|_| ()).map_err(|_| String::from("value should be a socket address")))
+                )
+        )
+}
+
+/// Whether a setting was switched on or off on the command line, or `None` if neither flag was
+/// given
+///
+/// The flags override each other, so only the last one given is present.
+fn switch(matches: &ArgMatches, on: &str, off: &str) -> Option<bool> {
+    if matches.is_present(on) {
+        Some(true)
+    } else if matches.is_present(off) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Whether the input should be read from stdin, and its content detected
+fn reads_from_stdin(matches: &ArgMatches) -> bool {
+    matches.is_present(IMPORT_FROM_STDIN)
+        || matches.value_of(POSITIONAL_FROM_PATH) == Some(STDIN_PATH)
 }
 
 fn determine_source(matches: &ArgMatches) -> Result<Source, MiniViewError> {
+    if reads_from_stdin(matches) {
+        // stdin can't provide both the image and the commands
+        if matches.is_present(OPTION_COMMANDS_FROM_STDIN) {
+            return Err(MiniViewError::CliUnableToDetermineInputMode);
+        }
+
+        return read_source_from_stdin_block();
+    }
+
     match (
         matches.is_present(IMPORT_FROM_PATH_CLI),
         matches.is_present(IMPORT_FROM_STDIN_PATH),
@@ -110,23 +623,696 @@ fn determine_source(matches: &ArgMatches) -> Result<Source, MiniViewError> {
     }
 }
 
+#[cfg(unix)]
+fn send(values: &[&str]) -> anyhow::Result<()> {
+    use miniview::remote::{self, Request};
+
+    let (socket, command) = values
+        .split_first()
+        .ok_or(MiniViewError::CliUnableToDetermineInputMode)?;
+
+    let request = match Request::from_line(&command.join(" ")) {
+        Ok(request) => request,
+        Err(_) if command == ["load_bytes"] => Request::LoadBytes {
+            data: base64::engine::general_purpose::STANDARD
+                .encode(import_image_bytes_from_stdin_block()?),
+        },
+        Err(err) => return Err(MiniViewError::from(err).into()),
+    };
+
+    let request = request.with_base_directory(std::env::current_dir()?);
+    let response = remote::send(socket, &request)?;
+
+    println!("{}", serde_json::to_string(&response)?);
+
+    match response.error {
+        Some(error) if !response.ok => Err(anyhow::anyhow!(error)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn send(_values: &[&str]) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "Remote control is only supported on Unix platforms"
+    ))
+}
+
+/// Prints the dimensions and the EXIF metadata of the image, a summary of the histogram of each
+/// channel, and the bins of the histograms as tab separated columns
+fn print_info(source: &Source, orient: bool) -> anyhow::Result<()> {
+    let (image, metadata) = source.open_with_metadata(orient)?;
+    let histogram = Histogram::new(&image);
+
+    let channels: [(&str, &ChannelHistogram); 5] = [
+        ("red", histogram.channel(Channel::Red)),
+        ("green", histogram.channel(Channel::Green)),
+        ("blue", histogram.channel(Channel::Blue)),
+        ("alpha", histogram.channel(Channel::Alpha)),
+        ("luminance", histogram.luminance()),
+    ];
+
+    let mut stdout = std::io::stdout().lock();
+
+    writeln!(stdout, "dimensions: {}x{}", image.width(), image.height())?;
+    writeln!(stdout, "colour type: {:?}", image.color())?;
+
+    print_metadata(&mut stdout, &metadata)?;
+
+    for (name, channel) in channels {
+        let percentage = |count: u64| 100.0 * count as f64 / channel.count().max(1) as f64;
+
+        writeln!(
+            stdout,
+            "{}: mean {:.4}, clipped low {} ({:.2}%), clipped high {} ({:.2}%)",
+            name,
+            channel.mean().unwrap_or(0.0),
+            channel.clipped_low(),
+            percentage(channel.clipped_low()),
+            channel.clipped_high(),
+            percentage(channel.clipped_high()),
+        )?;
+    }
+
+    writeln!(stdout)?;
+    writeln!(stdout, "bin\tred\tgreen\tblue\talpha\tluminance")?;
+
+    for bin in 0..BINS {
+        write!(stdout, "{}", bin)?;
+
+        for (_, channel) in channels {
+            write!(stdout, "\t{}", channel.bins()[bin])?;
+        }
+
+        writeln!(stdout)?;
+    }
+
+    Ok(())
+}
+
+/// Prints the colour profile of the image, and the fields of the EXIF metadata which are recorded
+/// by the image
+fn print_metadata(out: &mut impl Write, metadata: &Metadata) -> anyhow::Result<()> {
+    let profile = match &metadata.colour_profile {
+        Some(profile) => profile
+            .description()
+            .unwrap_or("embedded, without description"),
+        None => "none (assumed to be sRGB)",
+    };
+
+    writeln!(out, "colour profile: {}", profile)?;
+
+    let fields = [
+        (
+            "orientation",
+            metadata.orientation.map(|o| format!("{:?}", o)),
+        ),
+        ("camera", metadata.camera.clone()),
+        ("lens", metadata.lens.clone()),
+        ("exposure time", metadata.exposure_time.clone()),
+        ("f-number", metadata.f_number.clone()),
+        ("iso", metadata.iso.map(|iso| iso.to_string())),
+        ("focal length", metadata.focal_length.clone()),
+        ("taken", metadata.timestamp.clone()),
+        (
+            "gps",
+            metadata.gps.map(|gps| match gps.altitude {
+                Some(altitude) => format!(
+                    "{:.6}, {:.6}, {:.1} m",
+                    gps.latitude, gps.longitude, altitude
+                ),
+                None => format!("{:.6}, {:.6}", gps.latitude, gps.longitude),
+            }),
+        ),
+    ];
+
+    for (name, value) in fields {
+        if let Some(value) = value {
+            writeln!(out, "{}: {}", name, value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the image, rotated and flipped as configured, instead of showing it
+fn save_as(config: &Config, path: &Path) -> anyhow::Result<()> {
+    let source = config.source();
+    let image = if config.apply_orientation() {
+        source.open()?
+    } else {
+        source.open_as_stored()?
+    };
+
+    config
+        .transform()
+        .apply(image)
+        .save(path)
+        .map_err(|_| MiniViewError::UnableToSaveImage)?;
+
+    Ok(())
+}
+
+/// Reports readiness of the window on stdout, or on the given file descriptor
+fn notify_ready(config: ConfigBuilder, matches: &ArgMatches) -> anyhow::Result<ConfigBuilder> {
+    let message = |ready: &WindowReady| {
+        let window_id = ready
+            .window_id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "none".to_string());
+
+        format!(
+            "ready window_id={} size={}x{}\n",
+            window_id, ready.size.0, ready.size.1
+        )
+    };
+
+    if let Some(fd) = matches.value_of(OPTION_READY_FD) {
+        let file = ready_fd(fd.parse()?)?;
+
+        return Ok(config.on_ready(move |ready| {
+            if let Some(mut file) = file.lock().ok().and_then(|mut file| file.take()) {
+                let _ = file.write_all(message(ready).as_bytes());
+            }
+        }));
+    }
+
+    if matches.is_present(OPTION_READY) {
+        return Ok(config.on_ready(move |ready| {
+            let mut stdout = std::io::stdout().lock();
+            let _ = stdout.write_all(message(ready).as_bytes());
+            let _ = stdout.flush();
+        }));
+    }
+
+    Ok(config)
+}
+
+#[cfg(unix)]
+fn ready_fd(fd: i32) -> anyhow::Result<Mutex<Option<std::fs::File>>> {
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: fcntl only queries whether the file descriptor is open
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(anyhow::anyhow!("File descriptor {} is not open", fd));
+    }
+
+    // SAFETY: the file descriptor is open, and was handed to this process to be owned by it
+    Ok(Mutex::new(Some(unsafe { std::fs::File::from_raw_fd(fd) })))
+}
+
+#[cfg(not(unix))]
+fn ready_fd(_fd: i32) -> anyhow::Result<Mutex<Option<std::fs::File>>> {
+    Err(anyhow::anyhow!(
+        "--ready-fd is only supported on Unix platforms"
+    ))
+}
+
+/// Loads the image to validate it, and starts a background process which shows it
+///
+/// The background process runs this program again with the same arguments, and receives the same
+/// input on stdin.
+fn detach(matches: &ArgMatches, source: &Source) -> anyhow::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let input = match source {
+        Source::StdinBytes => {
+            let bytes = import_image_bytes_from_stdin_block()?;
+            imagecrate::load_from_memory(&bytes).map_err(|_| {
+                MiniViewError::FailedToImport(ImportError::OnStdinBytesUnableToGuessOrLoadFormat)
+            })?;
+
+            Some(bytes)
+        }
+        Source::Bytes(bytes) => {
+            source.open()?;
+
+            Some(bytes.clone())
+        }
+        Source::ByPath(path) => {
+            source.open()?;
+
+            (matches.is_present(IMPORT_FROM_STDIN_PATH) || reads_from_stdin(matches))
+                .then(|| path.to_string_lossy().into_owned().into_bytes())
+        }
+        Source::ByPaths(paths) => {
+            source.open()?;
+
+            let paths = paths
+                .iter()
+                .map(|path| path.to_string_lossy())
+                .collect::<Vec<_>>();
+
+            Some(paths.join("\n").into_bytes())
+        }
+    };
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(std::env::args_os().skip(1))
+        .env(DETACHED_ENV, "1")
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    detach_from_session(&mut command);
+
+    let mut child = command.spawn()?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(&input)?;
+    }
+
+    if matches.is_present(OPTION_PRINT_PID) {
+        println!("{}", child.id());
+    }
+
+    Ok(())
+}
+
+/// Runs the process in a new session, so it is not affected by the terminal or the shell
+#[cfg(unix)]
+fn detach_from_session(command: &mut std::process::Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: setsid is async-signal-safe
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+}
+
+/// Runs the process without a console
+#[cfg(windows)]
+fn detach_from_session(command: &mut std::process::Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    command.creation_flags(DETACHED_PROCESS);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach_from_session(_command: &mut std::process::Command) {}
+
+/// Records the termination signal received by the process, if any
+#[derive(Clone, Default)]
+struct Termination(Arc<AtomicI32>);
+
+impl Termination {
+    /// The conventional exit code of a process which was terminated by the recorded signal
+    fn exit_code(&self) -> Option<i32> {
+        match self.0.load(Ordering::SeqCst) {
+            0 => None,
+            signal => Some(128 + signal),
+        }
+    }
+}
+
+/// Closes the window on SIGINT and SIGTERM, and reloads the shown image on SIGHUP and SIGUSR1
+///
+/// A second SIGINT or SIGTERM exits immediately, in case the window does not respond.
+#[cfg(unix)]
+fn handle_signals(controls: &MiniView) -> anyhow::Result<Termination> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGUSR1};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP, SIGUSR1])?;
+    let termination = Termination::default();
+
+    let handle = controls.handle();
+    let received = termination.clone();
+
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            match signal {
+                SIGINT | SIGTERM => {
+                    if received.0.swap(signal, Ordering::SeqCst) != 0 {
+                        std::process::exit(128 + signal);
+                    }
+
+                    let _ = handle.request_close();
+                }
+                _ => {
+                    if let Err(err) = handle.reload() {
+                        eprintln!("Unable to reload the image: {}", err);
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(termination)
+}
+
+#[cfg(not(unix))]
+fn handle_signals(_controls: &MiniView) -> anyhow::Result<Termination> {
+    Ok(Termination::default())
+}
+
+/// Runs the commands received by stdin against the window, until stdin is closed or the window
+/// has been closed
+fn run_commands_from_stdin(handle: &MiniViewHandle) -> anyhow::Result<()> {
+    use miniview::remote::Request;
+    use std::io::BufRead;
+
+    let directory = std::env::current_dir()?;
+
+    for (index, line) in std::io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !handle.is_open() {
+            break;
+        }
+
+        let fail = |err: &dyn std::fmt::Display| anyhow::anyhow!("line {}: {}", index + 1, err);
+
+        match line.split_once(char::is_whitespace).unwrap_or((line, "")) {
+            ("wait", argument) => {
+                let time = argument
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| fail(&"'wait' requires a number of milliseconds"))?;
+                std::thread::sleep(Duration::from_millis(time));
+            }
+            _ => Request::from_line(line)
+                .map_err(MiniViewError::from)
+                .and_then(|request| request.with_base_directory(&directory).apply(handle))
+                .map_err(|err| fail(&err))?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn serve(matches: &ArgMatches) -> anyhow::Result<()> {
+    use miniview::serve::{self, Address};
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+    use std::sync::atomic::AtomicBool;
+
+    let address = match (matches.value_of(SERVE_SOCKET), matches.value_of(SERVE_TCP)) {
+        (_, Some(address)) => Address::Tcp(address.parse()?),
+        (Some(path), None) => Address::Unix(path.into()),
+        (None, None) => Address::Unix(miniview::remote::runtime_socket_path("serve")?),
+    };
+
+    // Stop the server on SIGINT, SIGTERM and SIGHUP, so the windows it started are closed
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+
+    {
+        let (address, stop) = (address.clone(), Arc::clone(&stop));
+        std::thread::spawn(move || {
+            if signals.forever().next().is_some() {
+                serve::stop(&address, &stop);
+            }
+        });
+    }
+
+    serve::serve_until(&address, std::env::current_exe()?, &stop)?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve(_matches: &ArgMatches) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "Serving is only supported on Unix platforms"
+    ))
+}
+
+/// Hands the source over to a running instance with the given key, if there is one
+///
+/// Returns the socket this instance should listen on if there is none. Another instance may start
+/// listening between handing over the source and binding the socket, in which case the source is
+/// handed over to that instance instead.
+#[cfg(unix)]
+fn reuse(key: &str, source: &Source) -> anyhow::Result<Option<Socket>> {
+    use miniview::errors::RemoteError;
+
+    let path = miniview::remote::runtime_socket_path(key)?;
+
+    for _ in 0..REUSE_ATTEMPTS {
+        if miniview::remote::hand_off(&path, source)? {
+            return Ok(None);
+        }
+
+        match miniview::remote::bind(&path) {
+            Err(MiniViewError::Remote(RemoteError::AddressInUse(_))) => continue,
+            result => return Ok(Some(result?)),
+        }
+    }
+
+    Ok(Some(miniview::remote::bind(&path)?))
+}
+
+#[cfg(not(unix))]
+fn reuse(_key: &str, _source: &Source) -> anyhow::Result<Option<Socket>> {
+    Err(anyhow::anyhow!(
+        "Reusing an instance is only supported on Unix platforms"
+    ))
+}
+
+/// Binds the socket to listen on, before the window is shown
+#[cfg(unix)]
+fn bind(path: &str) -> anyhow::Result<Socket> {
+    Ok(miniview::remote::bind(path)?)
+}
+
+#[cfg(not(unix))]
+fn bind(_path: &str) -> anyhow::Result<Socket> {
+    Err(anyhow::anyhow!(
+        "Remote control is only supported on Unix platforms"
+    ))
+}
+
+#[cfg(unix)]
+fn listen(socket: Option<Socket>, controls: &MiniView) -> Option<miniview::remote::Listener> {
+    socket.map(|socket| socket.listen(controls.handle()))
+}
+
+#[cfg(not(unix))]
+fn listen(socket: Option<Socket>, _controls: &MiniView) -> Option<()> {
+    socket.map(|socket| match socket {})
+}
+
 fn main() -> anyhow::Result<()> {
     let matches = cli().get_matches();
+
+    if let Some(matches) = matches.subcommand_matches(SERVE) {
+        return serve(matches);
+    }
+
+    if let Some(values) = matches.values_of(REMOTE_SEND) {
+        return send(&values.collect::<Vec<_>>());
+    }
+
     let source = determine_source(&matches)?;
 
-    let config = ConfigBuilder::new(source)
-        .set_fullscreen(matches.is_present(OPTION_FULLSCREEN))
-        .allow_resizable_window(matches.is_present(OPTION_WINDOW_RESIZE));
+    if matches.is_present(OPTION_INFO) {
+        return print_info(&source, !matches.is_present(OPTION_IGNORE_ORIENTATION));
+    }
+
+    let socket = if matches.is_present(OPTION_REUSE) {
+        let key = matches.value_of(OPTION_REUSE_KEY).unwrap_or("default");
+
+        match reuse(key, &source)? {
+            Some(socket) => Some(socket),
+            None => return Ok(()),
+        }
+    } else {
+        matches.value_of(OPTION_LISTEN).map(bind).transpose()?
+    };
+
+    // Flags given on the command line take precedence over the defaults preferred by the user
+    let mut config = ConfigBuilder::from_env_and_file(source.clone())?;
+
+    if let Some(value) = switch(&matches, OPTION_FULLSCREEN, OPTION_NO_FULLSCREEN) {
+        config = config.set_fullscreen(value);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_WINDOW_RESIZE, OPTION_NO_WINDOW_RESIZE) {
+        config = config.allow_resizable_window(value);
+    }
+
+    if let Some(transparency) = matches.value_of(OPTION_TRANSPARENCY) {
+        config = config.transparency(transparency.parse()?);
+    }
+
+    if let Some(channels) = matches.value_of(OPTION_CHANNELS) {
+        config = config.channels(channels.parse()?);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_TINT, OPTION_NO_TINT) {
+        config = config.tinted(value);
+    }
+
+    if let Some(swizzle) = matches.value_of(OPTION_SWIZZLE) {
+        config = config.swizzle(swizzle.parse()?);
+    }
+
+    if let Some(range) = matches.value_of(OPTION_RANGE) {
+        config = config.value_range(range.parse()?);
+    }
+
+    if let Some(exposure) = matches.value_of(OPTION_EXPOSURE) {
+        config = config.exposure(exposure.parse()?);
+    }
+
+    if let Some(gamma) = matches.value_of(OPTION_GAMMA) {
+        config = config.gamma(gamma.parse()?);
+    }
+
+    if let Some(operator) = matches.value_of(OPTION_TONE_MAPPING) {
+        config = config.tone_map_operator(operator.parse()?);
+    }
+
+    if let Some(colormap) = matches.value_of(OPTION_COLORMAP) {
+        config = config.colormap(colormap.parse()?);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_COLOUR_BAR, OPTION_NO_COLOUR_BAR) {
+        config = config.show_colour_bar(value);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_HISTOGRAM, OPTION_NO_HISTOGRAM) {
+        config = config.show_histogram(value);
+    }
+
+    if let Some(scale) = matches.value_of(OPTION_HISTOGRAM_SCALE) {
+        config = config.histogram_scale(scale.parse()?);
+    }
 
-    if let Some(close_after) = matches.value_of(OPTION_CLOSE_AFTER) {
+    if let Some(value) = switch(&matches, OPTION_INFO_PANEL, OPTION_NO_INFO_PANEL) {
+        config = config.show_info(value);
+    }
+
+    if let Some(value) = switch(
+        &matches,
+        OPTION_APPLY_ORIENTATION,
+        OPTION_IGNORE_ORIENTATION,
+    ) {
+        config = config.apply_orientation(value);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_MANAGED, OPTION_UNMANAGED) {
+        config = config.colour_management(value);
+    }
+
+    if let Some(path) = matches.value_of(OPTION_OUTPUT_PROFILE) {
+        config = config.output_profile(ColourProfile::open(path)?);
+    }
+
+    if let Some(rotation) = matches.value_of(OPTION_ROTATE) {
+        config = config.rotation(rotation.parse()?);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_FLIP_HORIZONTAL, OPTION_NO_FLIP_HORIZONTAL) {
+        config = config.flip_horizontal(value);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_FLIP_VERTICAL, OPTION_NO_FLIP_VERTICAL) {
+        config = config.flip_vertical(value);
+    }
+
+    if let Some(filter) = matches.value_of(OPTION_FILTER) {
+        config = config.filter(filter.parse()?);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_PIXEL_GRID, OPTION_NO_PIXEL_GRID) {
+        config = config.show_pixel_grid(value);
+    }
+
+    if let Some(threshold) = matches.value_of(OPTION_PIXEL_GRID_THRESHOLD) {
+        config = config.pixel_grid_threshold(threshold.parse()?);
+    }
+
+    if let Some(grid) = matches.value_of(OPTION_GRID) {
+        config = config.grid(grid.parse()?);
+    }
+
+    if let Some(value) = switch(&matches, OPTION_RULER, OPTION_NO_RULER) {
+        config = config.show_ruler(value);
+    }
+
+    if let Some(path) = matches.value_of(OPTION_SAVE_AS) {
+        return save_as(&config.build(), Path::new(path));
+    }
+
+    if matches.is_present(OPTION_DETACH) && std::env::var_os(DETACHED_ENV).is_none() {
+        // The background process binds the socket again, once this one has been removed
+        drop(socket);
+
+        return detach(&matches, &source);
+    }
+
+    let config = notify_ready(config, &matches)?;
+
+    let termination = if let Some(close_after) = matches.value_of(OPTION_CLOSE_AFTER) {
         let time = close_after.parse::<u64>()?;
-        let controls = MiniView::show(config.build())?;
-        std::thread::sleep(Duration::from_millis(time));
-        controls.close()?;
+        let mut controls = MiniView::show(config.build())?;
+        let _listener = listen(socket, &controls);
+        let termination = handle_signals(&controls)?;
+
+        // Count from the moment the window is shown, rather than from the moment it is created
+        let _ = controls.handle().wait_ready(READY_TIMEOUT);
+
+        // The window may also be closed earlier, by the user or by a signal
+        if controls.wait_timeout(Duration::from_millis(time))? {
+            controls.wait_for_exit()?;
+        } else {
+            controls.close()?;
+        }
+
+        termination
     } else {
         let config = config.set_lazy_window(true).build();
         let controls = MiniView::show(config)?;
+        let _listener = listen(socket, &controls);
+        let termination = handle_signals(&controls)?;
+
+        // Commands are run on a separate thread, so the process does not keep waiting for stdin
+        // after the window has been closed
+        let failure = Arc::new(Mutex::new(None));
+
+        if matches.is_present(OPTION_COMMANDS_FROM_STDIN) {
+            let handle = controls.handle();
+            let failure = Arc::clone(&failure);
+
+            std::thread::spawn(move || {
+                if let Err(err) = run_commands_from_stdin(&handle) {
+                    if let Ok(mut failure) = failure.lock() {
+                        *failure = Some(err);
+                    }
+
+                    let _ = handle.request_close();
+                }
+            });
+        }
+
         controls.wait_for_exit()?;
+
+        if let Some(err) = failure.lock().ok().and_then(|mut failure| failure.take()) {
+            return Err(err);
+        }
+
+        termination
+    };
+
+    if let Some(code) = termination.exit_code() {
+        std::process::exit(code);
     }
 
     Ok(())
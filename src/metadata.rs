@@ -0,0 +1,239 @@
+//! Metadata of an image which is stored alongside its pixels, such as the EXIF orientation, the
+//! camera which took a photo, where and when it was taken, and the colour profile of its values.
+
+use crate::profile::ColourProfile;
+use exif::{Exif, In, Tag, Value};
+use imagecrate::DynamicImage;
+use std::io::Cursor;
+
+/// How the stored pixels of an image must be transformed to show the image upright, as recorded
+/// by the EXIF Orientation tag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Orientation {
+    /// The image is stored upright
+    #[default]
+    Normal,
+    /// The image is stored mirrored horizontally
+    FlipHorizontal,
+    /// The image is stored upside down
+    Rotate180,
+    /// The image is stored mirrored vertically
+    FlipVertical,
+    /// The image is stored mirrored along its top-left to bottom-right diagonal
+    Transpose,
+    /// The image must be rotated by 90 degrees clockwise
+    Rotate90,
+    /// The image is stored mirrored along its top-right to bottom-left diagonal
+    Transverse,
+    /// The image must be rotated by 270 degrees clockwise
+    Rotate270,
+}
+
+impl Orientation {
+    /// The orientation of the value of an EXIF Orientation tag, from `1` to `8`
+    pub fn from_exif(value: u32) -> Option<Orientation> {
+        match value {
+            1 => Some(Orientation::Normal),
+            2 => Some(Orientation::FlipHorizontal),
+            3 => Some(Orientation::Rotate180),
+            4 => Some(Orientation::FlipVertical),
+            5 => Some(Orientation::Transpose),
+            6 => Some(Orientation::Rotate90),
+            7 => Some(Orientation::Transverse),
+            8 => Some(Orientation::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// Transform the stored image, so it is shown upright
+    pub fn apply(self, image: DynamicImage) -> DynamicImage {
+        match self {
+            Orientation::Normal => image,
+            Orientation::FlipHorizontal => image.fliph(),
+            Orientation::Rotate180 => image.rotate180(),
+            Orientation::FlipVertical => image.flipv(),
+            Orientation::Transpose => image.rotate90().fliph(),
+            Orientation::Rotate90 => image.rotate90(),
+            Orientation::Transverse => image.rotate270().fliph(),
+            Orientation::Rotate270 => image.rotate270(),
+        }
+    }
+}
+
+/// The position at which a photo was taken
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    /// Degrees north of the equator; negative south of it
+    pub latitude: f64,
+    /// Degrees east of the prime meridian; negative west of it
+    pub longitude: f64,
+    /// Metres above sea level; negative below it
+    pub altitude: Option<f64>,
+}
+
+/// The EXIF metadata of an image, and its embedded ICC colour profile
+///
+/// Fields which are not recorded by the image are `None`; all EXIF fields are `None` for images
+/// without EXIF metadata. Values which carry a unit are formatted with their unit, e.g. `1/250 s`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// How the stored pixels must be transformed to show the image upright
+    pub orientation: Option<Orientation>,
+    /// The make and model of the camera
+    pub camera: Option<String>,
+    /// The model of the lens
+    pub lens: Option<String>,
+    /// The exposure time, e.g. `1/250 s`
+    pub exposure_time: Option<String>,
+    /// The aperture, e.g. `f/1.8`
+    pub f_number: Option<String>,
+    /// The ISO sensitivity
+    pub iso: Option<u32>,
+    /// The focal length, e.g. `4.2 mm`
+    pub focal_length: Option<String>,
+    /// When the photo was taken, as `YYYY-MM-DD HH:MM:SS` in the local time of the camera
+    pub timestamp: Option<String>,
+    /// Where the photo was taken
+    pub gps: Option<GpsPosition>,
+    /// The ICC profile which describes the colours of the values of the image; images without a
+    /// profile are assumed to be sRGB
+    pub colour_profile: Option<ColourProfile>,
+}
+
+impl Metadata {
+    /// Read the EXIF metadata of an encoded image; images in a format which can't hold EXIF
+    /// metadata, or without EXIF metadata, have no metadata
+    ///
+    /// The colour profile is not read, as it is stored differently by each format; see
+    /// [`Source::metadata`].
+    ///
+    /// [`Source::metadata`]: ../enum.Source.html#method.metadata
+    pub fn from_bytes(bytes: &[u8]) -> Metadata {
+        exif::Reader::new()
+            .read_from_container(&mut Cursor::new(bytes))
+            .map(|exif| Metadata::from_exif(&exif))
+            .unwrap_or_default()
+    }
+
+    fn from_exif(exif: &Exif) -> Metadata {
+        let field = |tag: Tag| exif.get_field(tag, In::PRIMARY);
+        let text = |tag: Tag| match &field(tag)?.value {
+            Value::Ascii(values) => {
+                let value = String::from_utf8_lossy(values.first()?);
+                let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+
+                (!value.is_empty()).then(|| value.to_string())
+            }
+            _ => None,
+        };
+        let with_unit =
+            |tag: Tag| field(tag).map(|field| field.display_value().with_unit(exif).to_string());
+
+        let camera = match (text(Tag::Make), text(Tag::Model)) {
+            // Models are often named with the make included
+            (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.or(model),
+        };
+
+        let timestamp = [Tag::DateTimeOriginal, Tag::DateTime]
+            .into_iter()
+            .find_map(|tag| match &field(tag)?.value {
+                Value::Ascii(values) => exif::DateTime::from_ascii(values.first()?).ok(),
+                _ => None,
+            })
+            .map(|timestamp| timestamp.to_string());
+
+        Metadata {
+            orientation: field(Tag::Orientation)
+                .and_then(|field| field.value.get_uint(0))
+                .and_then(Orientation::from_exif),
+            camera,
+            lens: text(Tag::LensModel),
+            exposure_time: with_unit(Tag::ExposureTime),
+            f_number: field(Tag::FNumber).map(|field| format!("f/{}", field.display_value())),
+            iso: field(Tag::PhotographicSensitivity).and_then(|field| field.value.get_uint(0)),
+            focal_length: with_unit(Tag::FocalLength),
+            timestamp,
+            gps: gps_position(exif),
+            colour_profile: None,
+        }
+    }
+
+    /// Whether none of the fields are recorded
+    pub fn is_empty(&self) -> bool {
+        *self == Metadata::default()
+    }
+
+    /// Whether any of the EXIF fields are recorded
+    pub fn has_exif(&self) -> bool {
+        let exif = Metadata {
+            colour_profile: None,
+            ..self.clone()
+        };
+
+        !exif.is_empty()
+    }
+}
+
+fn gps_position(exif: &Exif) -> Option<GpsPosition> {
+    let degrees = |tag: Tag, negative: &[u8]| -> Option<f64> {
+        let Value::Rational(values) = &exif.get_field(tag, In::PRIMARY)?.value else {
+            return None;
+        };
+
+        // degrees, minutes and seconds
+        let value = values
+            .iter()
+            .zip([1.0, 60.0, 3600.0])
+            .map(|(value, divisor)| value.to_f64() / divisor)
+            .sum::<f64>();
+
+        Some(if reference(exif, tag)? == negative {
+            -value
+        } else {
+            value
+        })
+    };
+
+    let latitude = degrees(Tag::GPSLatitude, b"S")?;
+    let longitude = degrees(Tag::GPSLongitude, b"W")?;
+
+    let altitude = match exif
+        .get_field(Tag::GPSAltitude, In::PRIMARY)
+        .map(|field| &field.value)
+    {
+        Some(Value::Rational(values)) => values.first().map(|value| {
+            let below_sea_level = exif
+                .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+                == Some(1);
+
+            if below_sea_level {
+                -value.to_f64()
+            } else {
+                value.to_f64()
+            }
+        }),
+        _ => None,
+    };
+
+    Some(GpsPosition {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
+/// The reference of a GPS coordinate, i.e. whether it is north or south, or east or west
+fn reference(exif: &Exif, coordinate: Tag) -> Option<&[u8]> {
+    let tag = match coordinate {
+        Tag::GPSLatitude => Tag::GPSLatitudeRef,
+        _ => Tag::GPSLongitudeRef,
+    };
+
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().map(|value| value.as_slice()),
+        _ => None,
+    }
+}
@@ -0,0 +1,599 @@
+//! Overlays which are drawn on top of the image, such as the colour bar.
+
+use crate::colormap::Colormap;
+use crate::histogram::{ChannelHistogram, HistogramScale, BINS};
+use crate::tonemap::ToneMapping;
+use imagecrate::{Rgba, RgbaImage};
+
+/// Distance between an overlay and the edges of the window, in window pixels
+const MARGIN: u32 = 16;
+
+/// Factor by which the glyphs of the font are scaled
+const TEXT_SCALE: u32 = 2;
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+
+const PANEL: Rgba<u8> = Rgba([32, 32, 32, 255]);
+const TEXT: Rgba<u8> = Rgba([230, 230, 230, 255]);
+
+const WARNING: Rgba<u8> = Rgba([255, 96, 96, 255]);
+
+const HISTOGRAM_HEIGHT: u32 = 96;
+// The width of the markers which show that values are clipped
+const CLIPPING_MARKER_WIDTH: u32 = 3;
+
+const COLOUR_BAR_WIDTH: u32 = 16;
+const COLOUR_BAR_MAX_HEIGHT: u32 = 256;
+const COLOUR_BAR_TICKS: u32 = 5;
+
+/// Opacity of the lines of the pixel grid, which are drawn in black or white, whichever contrasts
+/// most with the pixel beneath them
+const PIXEL_GRID_OPACITY: u8 = 96;
+
+// The length of the ticks of the rulers at labelled coordinates, and of the ticks in between
+const RULER_TICK: u32 = 6;
+const RULER_MINOR_TICK: u32 = 3;
+// The smallest distance between ticks of the rulers, in window pixels
+const RULER_MINOR_SPACING: f32 = 4.0;
+
+/// Draw the colour bar of the colormap along the right edge of the frame, with ticks at evenly
+/// spaced values within the given bounds of the range of the tone mapping
+///
+/// Nothing is drawn if the frame is too small to fit the colour bar.
+pub(crate) fn draw_colour_bar(
+    frame: &mut RgbaImage,
+    colormap: Colormap,
+    tone_mapping: &ToneMapping,
+    bounds: (f32, f32),
+) {
+    let (width, height) = frame.dimensions();
+    let bar_height = COLOUR_BAR_MAX_HEIGHT.min(height.saturating_sub(4 * MARGIN));
+
+    let labels = colour_bar_labels(bounds);
+    let label_width = labels
+        .iter()
+        .map(|(_, label)| text_width(label))
+        .max()
+        .unwrap_or(0);
+
+    // The labels are drawn left of the ticks, on a panel around the labels and the bar
+    let panel_width = MARGIN / 2 + label_width + 8 + COLOUR_BAR_WIDTH + MARGIN / 2;
+
+    if bar_height < COLOUR_BAR_TICKS * text_height() || width < panel_width + 2 * MARGIN {
+        return;
+    }
+
+    let bar_x = width - MARGIN - COLOUR_BAR_WIDTH;
+    let bar_y = (height - bar_height) / 2;
+
+    fill_rect(
+        frame,
+        bar_x + COLOUR_BAR_WIDTH + MARGIN / 2 - panel_width,
+        bar_y - MARGIN / 2,
+        panel_width,
+        bar_height + MARGIN,
+        PANEL,
+    );
+
+    // The largest value is shown at the top
+    for row in 0..bar_height {
+        let level = 1.0 - row as f32 / (bar_height - 1) as f32;
+        fill_rect(
+            frame,
+            bar_x,
+            bar_y + row,
+            COLOUR_BAR_WIDTH,
+            1,
+            colormap.colour(level),
+        );
+    }
+
+    // The exposure and the tone mapping operator may move ticks together; a label which would
+    // overlap the label below it is left out
+    let mut free_above = u32::MAX;
+
+    for (value, label) in labels {
+        let level = tone_mapping.level(value, bounds);
+        let y = bar_y + ((1.0 - level) * (bar_height - 1) as f32).round() as u32;
+
+        let text_y = y.saturating_sub(text_height() / 2).clamp(
+            bar_y - MARGIN / 2,
+            bar_y + bar_height + MARGIN / 2 - text_height(),
+        );
+
+        if text_y + text_height() + 2 > free_above {
+            continue;
+        }
+
+        fill_rect(frame, bar_x - 4, y, 4, 1, TEXT);
+        draw_text(frame, bar_x - 8 - text_width(&label), text_y, &label, TEXT);
+        free_above = text_y;
+    }
+}
+
+/// Draw the histograms in the bottom left corner of the frame, each in the given colour, with
+/// markers for the values which are clipped
+///
+/// Where bars overlap, their colours are added. Nothing is drawn if the frame is too small to fit
+/// the histogram.
+pub(crate) fn draw_histogram(
+    frame: &mut RgbaImage,
+    histograms: &[(&ChannelHistogram, Rgba<u8>)],
+    scale: HistogramScale,
+) {
+    let (width, height) = frame.dimensions();
+    let plot_width = BINS as u32;
+    let panel_width = plot_width + MARGIN;
+    let panel_height = HISTOGRAM_HEIGHT + MARGIN / 2 + text_height() + MARGIN;
+
+    if width < panel_width + 2 * MARGIN || height < panel_height + 2 * MARGIN {
+        return;
+    }
+
+    let panel_x = MARGIN;
+    let panel_y = height - MARGIN - panel_height;
+    let plot_x = panel_x + MARGIN / 2;
+    let plot_y = panel_y + MARGIN / 2;
+
+    fill_rect(frame, panel_x, panel_y, panel_width, panel_height, PANEL);
+
+    let largest = histograms
+        .iter()
+        .flat_map(|(histogram, _)| histogram.bins().iter().copied())
+        .max()
+        .unwrap_or(0);
+
+    if largest == 0 {
+        return;
+    }
+
+    let bar_height = |count: u64| {
+        let fraction = match scale {
+            HistogramScale::Linear => count as f32 / largest as f32,
+            HistogramScale::Log => (count as f32).ln_1p() / (largest as f32).ln_1p(),
+        };
+
+        (fraction * HISTOGRAM_HEIGHT as f32).round() as u32
+    };
+
+    for bin in 0..BINS {
+        let heights = histograms
+            .iter()
+            .map(|(histogram, colour)| (bar_height(histogram.bins()[bin]), *colour))
+            .collect::<Vec<_>>();
+
+        for row in 0..HISTOGRAM_HEIGHT {
+            let mut pixel = PANEL;
+
+            for (_, colour) in heights.iter().filter(|(height, _)| row < *height) {
+                for channel in 0..3 {
+                    pixel[channel] = pixel[channel].saturating_add(colour[channel]);
+                }
+            }
+
+            frame.put_pixel(
+                plot_x + bin as u32,
+                plot_y + HISTOGRAM_HEIGHT - 1 - row,
+                pixel,
+            );
+        }
+    }
+
+    // The largest fraction of values of any of the histograms which is clipped, at each end
+    let clipped = |clipped: fn(&ChannelHistogram) -> u64| {
+        histograms
+            .iter()
+            .filter(|(histogram, _)| histogram.count() > 0)
+            .map(|(histogram, _)| clipped(histogram) as f32 / histogram.count() as f32)
+            .fold(0.0, f32::max)
+    };
+
+    let text_y = plot_y + HISTOGRAM_HEIGHT + MARGIN / 2;
+
+    for (fraction, left) in [
+        (clipped(ChannelHistogram::clipped_low), true),
+        (clipped(ChannelHistogram::clipped_high), false),
+    ] {
+        if fraction == 0.0 {
+            continue;
+        }
+
+        let label = percentage(fraction);
+        let (marker_x, text_x) = if left {
+            (plot_x, plot_x)
+        } else {
+            (
+                plot_x + plot_width - CLIPPING_MARKER_WIDTH,
+                plot_x + plot_width - text_width(&label),
+            )
+        };
+
+        fill_rect(
+            frame,
+            marker_x,
+            plot_y,
+            CLIPPING_MARKER_WIDTH,
+            HISTOGRAM_HEIGHT,
+            WARNING,
+        );
+        draw_text(frame, text_x, text_y, &label, WARNING);
+    }
+}
+
+/// Draw the lines of a grid at the given columns and rows of the frame, within the area of the
+/// frame, given as its left, top, right and bottom edges, which shows the image
+///
+/// Lines are blended over the frame in the given colour, of which the alpha is the opacity; lines
+/// without a colour are drawn in black or white, whichever contrasts most with the pixel beneath
+/// them.
+pub(crate) fn draw_grid(
+    frame: &mut RgbaImage,
+    columns: &[f32],
+    rows: &[f32],
+    area: [f32; 4],
+    colour: Option<Rgba<u8>>,
+) {
+    let (width, height) = frame.dimensions();
+
+    // The range of the window pixels along an axis of which the lines are drawn; the lines at the
+    // right and bottom edges of the image are drawn just outside of it
+    let span = |start: f32, end: f32, extent: u32| {
+        let start = start.floor().max(0.0);
+        let end = end.floor().min(extent as f32 - 1.0);
+
+        (start <= end).then_some((start as u32, end as u32))
+    };
+
+    let (Some((left, right)), Some((top, bottom))) = (
+        span(area[0], area[2], width),
+        span(area[1], area[3], height),
+    ) else {
+        return;
+    };
+
+    let mut blend = |x: u32, y: u32| {
+        let pixel = frame.get_pixel_mut(x, y);
+        let colour = colour.unwrap_or_else(|| {
+            let luminance = 0.2126 * f32::from(pixel[0])
+                + 0.7152 * f32::from(pixel[1])
+                + 0.0722 * f32::from(pixel[2]);
+            let value = if luminance > 127.5 { 0 } else { 255 };
+
+            Rgba([value, value, value, PIXEL_GRID_OPACITY])
+        });
+        let alpha = u32::from(colour[3]);
+
+        for channel in 0..3 {
+            let value =
+                u32::from(colour[channel]) * alpha + u32::from(pixel[channel]) * (255 - alpha);
+            pixel[channel] = ((value + 127) / 255) as u8;
+        }
+    };
+
+    let within = |line: f32, (start, end): (u32, u32)| {
+        (line >= start as f32 && line <= end as f32).then_some(line as u32)
+    };
+
+    // Pixels at which lines cross are only blended once
+    let mut drawn = vec![false; width as usize];
+
+    for x in columns
+        .iter()
+        .filter_map(|column| within(*column, (left, right)))
+    {
+        for y in top..=bottom {
+            blend(x, y);
+        }
+
+        drawn[x as usize] = true;
+    }
+
+    for y in rows.iter().filter_map(|row| within(*row, (top, bottom))) {
+        for x in (left..=right).filter(|x| !drawn[*x as usize]) {
+            blend(x, y);
+        }
+    }
+}
+
+/// Draw rulers along the top and left edges of the frame, with the coordinates of the shown image
+/// of the given dimensions, of which the top left corner is shown at the given origin and which
+/// is scaled by the given factor
+///
+/// Nothing is drawn if the frame is too small to fit the rulers.
+pub(crate) fn draw_ruler(
+    frame: &mut RgbaImage,
+    origin: (f32, f32),
+    scale: f32,
+    dimensions: (u32, u32),
+) {
+    let (width, height) = frame.dimensions();
+    let largest = dimensions.0.max(dimensions.1).to_string();
+
+    // The top ruler fits a line of labels, and the left ruler the widest label
+    let top = text_height() + 2 * RULER_TICK;
+    let left = text_width(&largest) + 2 * RULER_TICK;
+
+    if width < 2 * left || height < 2 * top {
+        return;
+    }
+
+    // The distance between labelled ticks, in image pixels: the smallest of 1, 2 and 5 times a
+    // power of ten, at which the labels don't overlap
+    let spacing = (text_width(&largest) + MARGIN) as f32 / scale;
+    let step = (0..9)
+        .flat_map(|power| [1, 2, 5].map(|factor| factor * 10u32.pow(power)))
+        .find(|step| *step as f32 >= spacing)
+        .unwrap_or(u32::MAX);
+    let minor = match step {
+        step if step % 5 == 0 => step / 5,
+        step if step % 2 == 0 => step / 2,
+        step => step,
+    };
+    let minor = if minor as f32 * scale >= RULER_MINOR_SPACING {
+        minor
+    } else {
+        step
+    };
+
+    fill_rect(frame, 0, 0, width, top, PANEL);
+    fill_rect(frame, 0, top, left, height - top, PANEL);
+
+    // The ticks along an axis, as the position within the window and the coordinate of the
+    // image, if it is labelled
+    let ticks = |origin: f32, extent: u32, start: u32, end: u32| {
+        (0..=extent / minor)
+            .map(|index| index * minor)
+            .filter_map(move |coordinate| {
+                let position = (origin + coordinate as f32 * scale).floor();
+
+                (position >= start as f32 && position < end as f32).then(|| {
+                    let label = (coordinate % step == 0).then(|| coordinate.to_string());
+                    (position as u32, label)
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (x, label) in ticks(origin.0, dimensions.0, left, width) {
+        let length = if label.is_some() {
+            RULER_TICK
+        } else {
+            RULER_MINOR_TICK
+        };
+
+        fill_rect(frame, x, top - length, 1, length, TEXT);
+
+        if let Some(label) = label {
+            draw_text(frame, x + TEXT_SCALE, RULER_TICK / 2, &label, TEXT);
+        }
+    }
+
+    for (y, label) in ticks(origin.1, dimensions.1, top, height) {
+        let length = if label.is_some() {
+            RULER_TICK
+        } else {
+            RULER_MINOR_TICK
+        };
+
+        fill_rect(frame, left - length, y, length, 1, TEXT);
+
+        if let Some(label) = label {
+            draw_text(frame, RULER_TICK / 2, y + TEXT_SCALE, &label, TEXT);
+        }
+    }
+}
+
+/// Draw the lines of text on a panel in the top left corner of the frame
+pub(crate) fn draw_info(frame: &mut RgbaImage, lines: &[String]) {
+    let line_height = text_height() + TEXT_SCALE * 2;
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+    let height = (lines.len() as u32 * line_height).saturating_sub(TEXT_SCALE * 2);
+
+    fill_rect(
+        frame,
+        MARGIN,
+        MARGIN,
+        width + MARGIN,
+        height + MARGIN,
+        PANEL,
+    );
+
+    for (index, line) in lines.iter().enumerate() {
+        let y = MARGIN + MARGIN / 2 + index as u32 * line_height;
+        draw_text(frame, MARGIN + MARGIN / 2, y, line, TEXT);
+    }
+}
+
+/// The fraction as a short percentage
+fn percentage(fraction: f32) -> String {
+    if fraction < 0.001 {
+        "<0.1%".to_string()
+    } else {
+        format!("{:.1}%", fraction * 100.0)
+    }
+}
+
+/// The values of the ticks of the colour bar, evenly spaced within the given bounds, and their
+/// labels
+fn colour_bar_labels(bounds: (f32, f32)) -> Vec<(f32, String)> {
+    (0..COLOUR_BAR_TICKS)
+        .map(|tick| {
+            let value =
+                bounds.0 + (bounds.1 - bounds.0) * tick as f32 / (COLOUR_BAR_TICKS - 1) as f32;
+            (value, label(value))
+        })
+        .collect()
+}
+
+/// The value as a short label
+fn label(value: f32) -> String {
+    if value == 0.0 {
+        "0".to_string()
+    } else if value.abs() >= 1000.0 || value.abs() < 0.01 {
+        format!("{:.1e}", value)
+    } else {
+        let label = format!("{:.2}", value);
+        label
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+/// Fill the rectangle with the colour, clipped to the frame
+pub(crate) fn fill_rect(
+    frame: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    colour: Rgba<u8>,
+) {
+    let right = (x + width).min(frame.width());
+    let bottom = (y + height).min(frame.height());
+
+    for y in y..bottom {
+        for x in x..right {
+            frame.put_pixel(x, y, colour);
+        }
+    }
+}
+
+/// The width of the text, in window pixels
+pub(crate) fn text_width(text: &str) -> u32 {
+    let count = text.chars().count() as u32;
+
+    (count * (GLYPH_WIDTH + 1)).saturating_sub(1) * TEXT_SCALE
+}
+
+/// The height of a line of text, in window pixels
+pub(crate) fn text_height() -> u32 {
+    GLYPH_HEIGHT * TEXT_SCALE
+}
+
+/// Draw the text with its top left corner at the given position, clipped to the frame
+///
+/// Letters are drawn as capitals. Only letters, digits and common punctuation can be drawn;
+/// other characters are drawn as blank space.
+pub(crate) fn draw_text(frame: &mut RgbaImage, x: u32, y: u32, text: &str, colour: Rgba<u8>) {
+    for (index, character) in text.chars().enumerate() {
+        let left = x + index as u32 * (GLYPH_WIDTH + 1) * TEXT_SCALE;
+
+        for (row, bits) in glyph(character).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    fill_rect(
+                        frame,
+                        left + column * TEXT_SCALE,
+                        y + row as u32 * TEXT_SCALE,
+                        TEXT_SCALE,
+                        TEXT_SCALE,
+                        colour,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The rows of the glyph of the character, in a font of 3 by 5 pixels
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match character.to_ascii_lowercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        'a' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'b' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'c' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'd' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'e' => [0b111, 0b100, 0b111, 0b100, 0b111],
+        'f' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'g' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'h' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'i' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'j' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'k' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'l' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'm' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'n' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'o' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'p' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'r' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        't' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'u' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'v' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'w' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'x' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        _ => [0; GLYPH_HEIGHT as usize],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{colour_bar_labels, label};
+
+    fn labels(bounds: (f32, f32)) -> Vec<String> {
+        colour_bar_labels(bounds)
+            .into_iter()
+            .map(|(_, label)| label)
+            .collect()
+    }
+
+    #[test]
+    fn short_labels() {
+        assert_eq!(label(0.0), "0");
+        assert_eq!(label(1.0), "1");
+        assert_eq!(label(0.5), "0.5");
+        assert_eq!(label(-0.25), "-0.25");
+        assert_eq!(label(123.456), "123.46");
+        assert_eq!(label(999.0), "999");
+    }
+
+    #[test]
+    fn large_and_small_labels_in_scientific_notation() {
+        assert_eq!(label(1000.0), "1.0e3");
+        assert_eq!(label(65535.0), "6.6e4");
+        assert_eq!(label(0.001), "1.0e-3");
+        assert_eq!(label(-0.005), "-5.0e-3");
+    }
+
+    #[test]
+    fn colour_bar_ticks() {
+        assert_eq!(labels((0.0, 1.0)), ["0", "0.25", "0.5", "0.75", "1"]);
+        assert_eq!(
+            labels((0.0, 255.0)),
+            ["0", "63.75", "127.5", "191.25", "255"]
+        );
+        assert_eq!(labels((-1.0, 1.0)), ["-1", "-0.5", "0", "0.5", "1"]);
+        assert_eq!(
+            labels((0.0, 65535.0)),
+            ["0", "1.6e4", "3.3e4", "4.9e4", "6.6e4"]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! ICC colour profiles, with which the colours of an image are converted to the colours of the
+//! display.
+
+use crate::errors::{MiniViewError, SettingsError};
+use crate::MVResult;
+use imagecrate::codecs::jpeg::JpegDecoder;
+use imagecrate::codecs::png::PngDecoder;
+use imagecrate::codecs::tiff::TiffDecoder;
+use imagecrate::codecs::webp::WebPDecoder;
+use imagecrate::{ImageDecoder, ImageFormat, RgbaImage};
+use qcms::{DataType, Intent, Profile, Transform};
+use std::fmt::{Debug, Formatter};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Signature of the tag which holds the description of a profile
+const DESCRIPTION_TAG: &[u8; 4] = b"desc";
+
+/// An ICC colour profile, which describes the colours of the values of an image or a display
+///
+/// Only RGB and greyscale profiles are supported.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ColourProfile {
+    data: Vec<u8>,
+    description: Option<String>,
+}
+
+impl ColourProfile {
+    /// Read an ICC profile; returns `None` if the data is not a supported ICC profile
+    pub fn from_bytes(data: Vec<u8>) -> Option<ColourProfile> {
+        Profile::new_from_slice(&data, false)?;
+
+        let description = description(&data);
+
+        Some(ColourProfile { data, description })
+    }
+
+    /// Read the ICC profile stored in the given file, e.g. the profile of a display
+    pub fn open<P: AsRef<Path>>(path: P) -> MVResult<ColourProfile> {
+        let path = path.as_ref();
+        let invalid =
+            || MiniViewError::Settings(SettingsError::InvalidColourProfile(path.to_path_buf()));
+
+        let data = std::fs::read(path).map_err(|_| invalid())?;
+
+        ColourProfile::from_bytes(data).ok_or_else(invalid)
+    }
+
+    /// The name of the profile as it is described by the profile, e.g. `Display P3`
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// The ICC profile as it is stored
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Convert the colours of an image with this profile to the colours of the given output
+    /// profile, or to sRGB if no output profile is given
+    ///
+    /// This is how the window shows images with a colour profile, unless colour management is
+    /// turned off. Returns `None` if the profiles can't be converted between, e.g. for a
+    /// greyscale profile.
+    pub fn convert(&self, image: &RgbaImage, output: Option<&ColourProfile>) -> Option<RgbaImage> {
+        let input = Profile::new_from_slice(&self.data, false)?;
+        let mut output = match output {
+            Some(output) => Profile::new_from_slice(&output.data, false)?,
+            None => Profile::new_sRGB(),
+        };
+        output.precache_output_transform();
+
+        let transform = Transform::new(&input, &output, DataType::RGBA8, Intent::Perceptual)?;
+        let mut image = image.clone();
+        transform.apply(&mut image);
+
+        Some(image)
+    }
+}
+
+impl Debug for ColourProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ColourProfile")
+            .field("description", &self.description)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// The ICC profile embedded in an encoded image of the given format, if any
+pub(crate) fn embedded(bytes: &[u8], format: ImageFormat) -> Option<ColourProfile> {
+    let reader = Cursor::new(bytes);
+
+    let data = match format {
+        ImageFormat::Png => PngDecoder::new(reader).ok()?.icc_profile(),
+        ImageFormat::Jpeg => JpegDecoder::new(reader).ok()?.icc_profile(),
+        ImageFormat::WebP => WebPDecoder::new(reader).ok()?.icc_profile(),
+        ImageFormat::Tiff => TiffDecoder::new(reader).ok()?.icc_profile(),
+        _ => None,
+    }?;
+
+    ColourProfile::from_bytes(data)
+}
+
+/// The description of the profile: the text of a version 2 `desc` tag, or the first text of a
+/// version 4 `mluc` tag
+fn description(data: &[u8]) -> Option<String> {
+    let u32_at = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().ok()?) as usize)
+    };
+
+    // The tag table follows the header of 128 bytes; each entry holds a signature, an offset
+    // and a size
+    let tags = u32_at(128)?;
+    let (offset, size) = (0..tags.min(1024))
+        .map(|index| 132 + index * 12)
+        .find(|entry| data.get(*entry..entry + 4) == Some(DESCRIPTION_TAG))
+        .and_then(|entry| Some((u32_at(entry + 4)?, u32_at(entry + 8)?)))?;
+    let tag = data.get(offset..offset.checked_add(size)?)?;
+
+    let text = match tag.get(0..4)? {
+        b"desc" => {
+            let length = u32_at(offset + 8)?;
+            let text = tag.get(12..12 + length)?;
+
+            String::from_utf8_lossy(text).into_owned()
+        }
+        b"mluc" => {
+            let records = u32_at(offset + 8)?;
+
+            if records == 0 {
+                return None;
+            }
+
+            let length = u32_at(offset + 20)?;
+            let start = u32_at(offset + 24)?;
+            let text = tag.get(start..start.checked_add(length)?)?;
+            let units = text
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .collect::<Vec<_>>();
+
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Profiles are serialized as the base64 string of the profile as it is stored
+#[cfg(feature = "serde")]
+mod serialization {
+    use super::ColourProfile;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    impl Serialize for ColourProfile {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            crate::base64_bytes::serialize(&self.data, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for ColourProfile {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = crate::base64_bytes::deserialize(deserializer)?;
+
+            ColourProfile::from_bytes(data)
+                .ok_or_else(|| D::Error::custom("not an RGB or greyscale ICC profile"))
+        }
+    }
+}
@@ -0,0 +1,601 @@
+//! A protocol to control a running `miniview` window, e.g. over a Unix domain socket.
+//!
+//! On Unix platforms, a window is made controllable by [`listen`]ing on a socket path. Clients connect to this path,
+//! and write [`Request`]s as line delimited JSON objects. Each request is answered by a single
+//! [`Response`] line, which includes the state of the window after the request was applied.
+//!
+//! The supported requests are:
+//!
+//! ```json
+//! {"command": "load", "path": "/home/myuser/image.png"}
+//! {"command": "load_bytes", "data": "<base64 encoded image>"}
+//! {"command": "zoom", "percent": 200}
+//! {"command": "zoom"}
+//! {"command": "pan", "x": 100, "y": 50}
+//! {"command": "title", "title": "my window"}
+//! {"command": "screenshot", "path": "/home/myuser/screenshot.png"}
+//! {"command": "save", "path": "/home/myuser/rotated.png"}
+//! {"command": "focus"}
+//! {"command": "close"}
+//! {"command": "state"}
+//! ```
+//!
+//! A zoom request without a percentage zooms the image to fit the window. A pan request centers
+//! the view on the given position of the image, in image pixels. A save request writes the image
+//! as it is shown, rotated and flipped, at its original precision.
+//!
+//! Requests can also be written in a shorter line form, e.g. `zoom 200`, `zoom fit`, `pan 100 50`
+//! or `title my window`; see [`Request::from_line`].
+//!
+//! [`listen`]: fn.listen.html
+//! [`Request`]: enum.Request.html
+//! [`Response`]: struct.Response.html
+//! [`Request::from_line`]: enum.Request.html#method.from_line
+
+use crate::errors::RemoteError;
+#[cfg(unix)]
+use crate::io::import_image_bytes_from_stdin_block;
+use crate::{MVResult, MiniViewError, MiniViewHandle, Source, WindowState, Zoom};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::fs::{DirBuilderExt, FileTypeExt},
+    os::unix::net::{UnixListener, UnixStream},
+    thread,
+};
+
+/// A request to control a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Request {
+    /// Load an image from a path, and show it
+    Load { path: PathBuf },
+    /// Load a base64 encoded image, and show it
+    LoadBytes { data: String },
+    /// Set the zoom level in percent, or zoom to fit the window if absent
+    Zoom {
+        #[serde(default)]
+        percent: Option<f32>,
+    },
+    /// Center the view on the given position of the image, in image pixels
+    Pan { x: f32, y: f32 },
+    /// Set the title of the window
+    Title { title: String },
+    /// Save the contents of the window to a path
+    Screenshot { path: PathBuf },
+    /// Save the image as it is shown, rotated and flipped, to a path
+    Save { path: PathBuf },
+    /// Raise the window above other windows, and focus it
+    Focus,
+    /// Close the window
+    Close,
+    /// Only reply with the state of the window
+    State,
+}
+
+impl Request {
+    /// Parse a request from its line form
+    ///
+    /// The first word of the line names the command, and the remainder of the line holds its
+    /// arguments, for example: `load image.png`, `load_bytes <base64>`, `zoom 200`, `zoom fit`,
+    /// `pan 100 50`, `title my window`, `screenshot out.png`, `save out.png`, `focus`, `close` or
+    /// `state`.
+    /// A line which starts with `{` is parsed as a JSON request instead.
+    pub fn from_line(line: &str) -> Result<Request, RemoteError> {
+        let line = line.trim();
+
+        if line.starts_with('{') {
+            return serde_json::from_str(line)
+                .map_err(|err| RemoteError::InvalidRequest(err.to_string()));
+        }
+
+        let (command, argument) = line
+            .split_once(char::is_whitespace)
+            .map(|(command, argument)| (command, argument.trim()))
+            .unwrap_or((line, ""));
+
+        let required = |name: &str| {
+            if argument.is_empty() {
+                Err(RemoteError::InvalidRequest(format!(
+                    "'{}' requires {}",
+                    command, name
+                )))
+            } else {
+                Ok(argument.to_string())
+            }
+        };
+
+        let number = |value: &str| {
+            value
+                .parse::<f32>()
+                .map_err(|_| RemoteError::InvalidRequest(format!("'{}' is not a number", value)))
+        };
+
+        let request = match command {
+            "load" => Request::Load {
+                path: required("a path")?.into(),
+            },
+            "load_bytes" => Request::LoadBytes {
+                data: required("base64 encoded data")?,
+            },
+            "zoom" => match argument.trim_end_matches('%') {
+                "" | "fit" => Request::Zoom { percent: None },
+                percent => Request::Zoom {
+                    percent: Some(number(percent)?),
+                },
+            },
+            "pan" => match argument.split_whitespace().collect::<Vec<_>>()[..] {
+                [x, y] => Request::Pan {
+                    x: number(x)?,
+                    y: number(y)?,
+                },
+                _ => {
+                    return Err(RemoteError::InvalidRequest(
+                        "'pan' requires an x and y coordinate".to_string(),
+                    ))
+                }
+            },
+            "title" => Request::Title {
+                title: argument.to_string(),
+            },
+            "screenshot" => Request::Screenshot {
+                path: required("a path")?.into(),
+            },
+            "save" => Request::Save {
+                path: required("a path")?.into(),
+            },
+            "focus" => Request::Focus,
+            "close" => Request::Close,
+            "state" => Request::State,
+            other => {
+                return Err(RemoteError::InvalidRequest(format!(
+                    "unknown command '{}'",
+                    other
+                )))
+            }
+        };
+
+        Ok(request)
+    }
+
+    /// Resolve relative paths of the request against the given directory
+    ///
+    /// Useful for clients, since the window may have been started from a different working
+    /// directory.
+    pub fn with_base_directory<P: AsRef<Path>>(self, directory: P) -> Request {
+        match self {
+            Request::Load { path } => Request::Load {
+                path: directory.as_ref().join(path),
+            },
+            Request::Screenshot { path } => Request::Screenshot {
+                path: directory.as_ref().join(path),
+            },
+            Request::Save { path } => Request::Save {
+                path: directory.as_ref().join(path),
+            },
+            other => other,
+        }
+    }
+
+    /// Apply the request to the window controlled by the given handle
+    ///
+    /// Images are loaded with their metadata and colour profile, and are transformed according to
+    /// their EXIF orientation if the window was configured to apply it. Blocks until the window
+    /// has processed the request.
+    pub fn apply(self, handle: &MiniViewHandle) -> MVResult<()> {
+        match self {
+            Request::Load { path } => handle.load(Source::ByPath(path))?,
+            Request::LoadBytes { data } => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(data.trim())
+                    .map_err(|_| RemoteError::InvalidRequest("invalid base64 data".to_string()))?;
+
+                handle.load(Source::Bytes(bytes))?
+            }
+            Request::Zoom { percent } => handle.set_zoom(match percent {
+                Some(percent) => Zoom::Factor(percent / 100.0),
+                None => Zoom::Fit,
+            })?,
+            Request::Pan { x, y } => handle.pan_to(x, y)?,
+            Request::Title { title } => handle.set_title(title)?,
+            Request::Screenshot { path } => handle
+                .screenshot()?
+                .save(path)
+                .map_err(|_| MiniViewError::UnableToSaveImage)?,
+            Request::Save { path } => handle
+                .image()?
+                .save(path)
+                .map_err(|_| MiniViewError::UnableToSaveImage)?,
+            Request::Focus => handle.focus()?,
+            Request::Close => return handle.request_close(),
+            Request::State => {}
+        }
+
+        handle.sync()
+    }
+}
+
+/// The response to a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Response {
+    /// Whether the request was applied successfully
+    pub ok: bool,
+    /// Describes why the request failed, if it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The state of the window after the request was applied, or `None` if the window is closed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+}
+
+impl Response {
+    #[cfg(unix)]
+    fn new(result: MVResult<()>, handle: &MiniViewHandle) -> Self {
+        Response {
+            ok: result.is_ok(),
+            error: result.err().map(|err| err.to_string()),
+            state: handle.state().map(State::from),
+        }
+    }
+}
+
+/// The state of a window, as included in a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct State {
+    pub title: String,
+    pub window_size: (u32, u32),
+    pub image_size: (u32, u32),
+    pub zoom_percent: f32,
+    pub center: (f32, f32),
+    pub fullscreen: bool,
+    pub focused: bool,
+}
+
+impl From<WindowState> for State {
+    fn from(state: WindowState) -> Self {
+        State {
+            title: state.title,
+            window_size: state.size,
+            image_size: state.image_size,
+            zoom_percent: state.zoom * 100.0,
+            center: state.center,
+            fullscreen: state.fullscreen,
+            focused: state.focused,
+        }
+    }
+}
+
+/// Listens for requests on a Unix domain socket
+///
+/// The socket file is removed when the listener is dropped.
+#[derive(Debug)]
+#[cfg(unix)]
+pub struct Listener {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Remove the socket file at the given path if no process is listening on it, so a new socket can
+/// be bound there
+///
+/// Fails if a process is still listening on the socket, or if the path is not a socket at all, so
+/// files which happen to be at the path are never removed.
+#[cfg(unix)]
+pub(crate) fn remove_stale_socket(path: &Path) -> Result<(), RemoteError> {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(RemoteError::NotASocket(path.to_path_buf()));
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        return Err(RemoteError::AddressInUse(path.to_path_buf()));
+    }
+
+    std::fs::remove_file(path).map_err(|_| RemoteError::UnableToBind(path.to_path_buf()))
+}
+
+/// Listen for requests on a Unix domain socket at the given path, and apply them to the window
+/// controlled by the given handle
+///
+/// Requests are handled on background threads. A stale socket file at the path, which no
+/// process is listening on, is replaced; any other file at the path is left alone, and listening
+/// fails.
+#[cfg(unix)]
+pub fn listen<P: AsRef<Path>>(path: P, handle: MiniViewHandle) -> MVResult<Listener> {
+    Ok(bind(path)?.listen(handle))
+}
+
+/// Bind a Unix domain socket at the given path, before there is a window to apply its requests to
+///
+/// Binding the socket first claims the path, e.g. before a window is shown which should be the
+/// only one at the path. Clients can connect right away; their requests are applied once
+/// [`Socket::listen`] has been called. Fails like [`listen`]; if another process is listening at
+/// the path, with [`RemoteError::AddressInUse`].
+///
+/// [`Socket::listen`]: struct.Socket.html#method.listen
+/// [`listen`]: fn.listen.html
+/// [`RemoteError::AddressInUse`]: ../errors/enum.RemoteError.html#variant.AddressInUse
+#[cfg(unix)]
+pub fn bind<P: AsRef<Path>>(path: P) -> MVResult<Socket> {
+    let path = path.as_ref().to_path_buf();
+
+    remove_stale_socket(&path)?;
+
+    // Another process may have bound the path since the stale socket was removed
+    let listener = UnixListener::bind(&path).map_err(|err| match err.kind() {
+        io::ErrorKind::AddrInUse => RemoteError::AddressInUse(path.clone()),
+        _ => RemoteError::UnableToBind(path.clone()),
+    })?;
+
+    Ok(Socket {
+        listener,
+        file: Listener { path },
+    })
+}
+
+/// A bound Unix domain socket, of which the requests are not applied yet; see [`bind`]
+///
+/// The socket file is removed when the socket is dropped.
+///
+/// [`bind`]: fn.bind.html
+#[derive(Debug)]
+#[cfg(unix)]
+pub struct Socket {
+    listener: UnixListener,
+    file: Listener,
+}
+
+#[cfg(unix)]
+impl Socket {
+    /// Apply the requests received by the socket to the window controlled by the given handle
+    ///
+    /// Requests are handled on background threads. The socket file is removed when the returned
+    /// listener is dropped.
+    pub fn listen(self, handle: MiniViewHandle) -> Listener {
+        let listener = self.listener;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handle = handle.clone();
+                thread::spawn(move || serve_connection(stream, &handle));
+            }
+        });
+
+        self.file
+    }
+}
+
+#[cfg(unix)]
+fn serve_connection(stream: UnixStream, handle: &MiniViewHandle) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let result = Request::from_line(&line)
+            .map_err(MiniViewError::from)
+            .and_then(|request| request.apply(handle));
+
+        let response = Response::new(result, handle);
+
+        let written = serde_json::to_string(&response)
+            .map_err(|_| ())
+            .and_then(|json| writeln!(writer, "{}", json).map_err(|_| ()));
+
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+/// Send a request to the window listening at the given socket path, and wait for the response
+#[cfg(unix)]
+pub fn send<P: AsRef<Path>>(path: P, request: &Request) -> MVResult<Response> {
+    let path = path.as_ref();
+
+    let mut stream =
+        UnixStream::connect(path).map_err(|_| RemoteError::UnableToConnect(path.to_path_buf()))?;
+
+    let json = serde_json::to_string(request)
+        .map_err(|err| RemoteError::InvalidRequest(err.to_string()))?;
+    writeln!(stream, "{}", json).map_err(|_| RemoteError::ConnectionLost)?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|_| RemoteError::ConnectionLost)?;
+
+    serde_json::from_str(&line).map_err(|_| MiniViewError::from(RemoteError::MalformedResponse))
+}
+
+/// The path of the per-user socket of the instance with the given key
+///
+/// The socket is located in a `miniview` directory within `$XDG_RUNTIME_DIR`, or within a
+/// directory in the temporary directory which is private to the current user, if
+/// `$XDG_RUNTIME_DIR` is not set. The directory is created if it does not exist yet.
+#[cfg(unix)]
+pub fn runtime_socket_path(key: &str) -> MVResult<PathBuf> {
+    let directory = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) if !runtime_dir.is_empty() => PathBuf::from(runtime_dir).join("miniview"),
+        _ => {
+            // SAFETY: getuid is always successful, and has no side effects
+            let uid = unsafe { libc::getuid() };
+            std::env::temp_dir().join(format!("miniview-{}", uid))
+        }
+    };
+
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&directory)
+        .map_err(|_| RemoteError::UnableToCreateRuntimeDirectory(directory.clone()))?;
+
+    let key = key
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+
+    Ok(directory.join(format!("{}.sock", key)))
+}
+
+/// Hand the given source over to the instance listening at the given socket path, if any
+///
+/// Returns `false` when no instance is listening, in which case the source has not been
+/// consumed; for [`Source::StdinBytes`], stdin is only read when an instance is listening.
+/// The window of the instance is focused after the image has been loaded. A [`Source::ByPaths`]
+/// of more than one path, which would be shown as a gallery, can't be handed over, and fails with
+/// [`RemoteError::UnableToHandOffGallery`] if an instance is listening.
+///
+/// [`Source::StdinBytes`]: ../enum.Source.html#variant.StdinBytes
+/// [`Source::ByPaths`]: ../enum.Source.html#variant.ByPaths
+/// [`RemoteError::UnableToHandOffGallery`]: ../errors/enum.RemoteError.html#variant.UnableToHandOffGallery
+#[cfg(unix)]
+pub fn hand_off<P: AsRef<Path>>(path: P, source: &Source) -> MVResult<bool> {
+    let path = path.as_ref();
+
+    if UnixStream::connect(path).is_err() {
+        return Ok(false);
+    }
+
+    let load = |image: &PathBuf| Request::Load {
+        path: std::env::current_dir()
+            .map(|directory| directory.join(image))
+            .unwrap_or_else(|_| image.clone()),
+    };
+
+    let load_bytes = |bytes: &[u8]| Request::LoadBytes {
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+    };
+
+    let request = match source {
+        Source::ByPath(image) => load(image),
+        Source::ByPaths(images) => match images.as_slice() {
+            [] => return Err(MiniViewError::EmptyInputPath),
+            [image] => load(image),
+            _ => return Err(RemoteError::UnableToHandOffGallery.into()),
+        },
+        Source::StdinBytes => load_bytes(&import_image_bytes_from_stdin_block()?),
+        Source::Bytes(bytes) => load_bytes(bytes),
+    };
+
+    for request in [request, Request::Focus] {
+        let response = send(path, &request)?;
+
+        if let (false, Some(error)) = (response.ok, response.error) {
+            return Err(RemoteError::Rejected(error).into());
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Request;
+    use crate::config::ConfigBuilder;
+    use crate::metadata::{Metadata, Orientation};
+    use crate::{Action, MiniViewHandle};
+    use base64::Engine;
+    use imagecrate::{DynamicImage, GenericImageView, ImageOutputFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+    use std::thread;
+
+    /// A JPEG of a 2x1 image, with an EXIF segment which records a rotation of 90 degrees
+    fn rotated_jpeg() -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 1, Rgb([255, 0, 0])))
+            .write_to(&mut Cursor::new(&mut jpeg), ImageOutputFormat::Jpeg(100))
+            .unwrap();
+
+        // a big endian TIFF header, followed by an IFD with a single SHORT entry
+        let exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0";
+
+        let mut segment = vec![0xff, 0xe1];
+        segment.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(exif);
+
+        // directly after the start of image marker
+        jpeg.splice(2..2, segment);
+        jpeg
+    }
+
+    /// Applies the request to a window which is configured to apply the EXIF orientation or not,
+    /// and returns the image which is sent to the window
+    fn applied(request: Request, apply_orientation: bool) -> (DynamicImage, Metadata) {
+        let config = ConfigBuilder::from_path("")
+            .apply_orientation(apply_orientation)
+            .build();
+        let (handle, receiver) = MiniViewHandle::new(&config);
+
+        let request = thread::spawn(move || request.apply(&handle));
+        let mut image = None;
+
+        // ends once the handle has been dropped
+        for action in receiver {
+            match action {
+                Action::SetImage(shown, metadata, _) => image = Some((shown, *metadata)),
+                Action::Sync(done) => {
+                    let _ = done.send(());
+                }
+                _ => {}
+            }
+        }
+
+        request.join().unwrap().unwrap();
+        image.expect("no image was sent to the window")
+    }
+
+    #[test]
+    fn load_applies_the_configured_orientation() {
+        let path = std::env::temp_dir().join(format!("miniview-load-{}.jpg", std::process::id()));
+        std::fs::write(&path, rotated_jpeg()).unwrap();
+
+        let load = || Request::Load { path: path.clone() };
+        let (oriented, metadata) = applied(load(), true);
+        let (as_stored, _) = applied(load(), false);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(oriented.dimensions(), (1, 2));
+        assert_eq!(as_stored.dimensions(), (2, 1));
+        assert_eq!(metadata.orientation, Some(Orientation::Rotate90));
+    }
+
+    #[test]
+    fn load_bytes_keeps_the_metadata() {
+        let data = base64::engine::general_purpose::STANDARD.encode(rotated_jpeg());
+
+        let load_bytes = || Request::LoadBytes { data: data.clone() };
+        let (oriented, metadata) = applied(load_bytes(), true);
+        let (as_stored, _) = applied(load_bytes(), false);
+
+        assert_eq!(oriented.dimensions(), (1, 2));
+        assert_eq!(as_stored.dimensions(), (2, 1));
+        assert_eq!(metadata.orientation, Some(Orientation::Rotate90));
+        assert!(metadata.has_exif());
+    }
+}
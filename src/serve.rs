@@ -0,0 +1,704 @@
+//! A server which shows images pushed by other processes, in named windows.
+//!
+//! The server accepts connections on a Unix domain socket or a local TCP socket. Clients write
+//! [`Frame`]s to a connection; a frame carries the name of a window and an image. If no window with
+//! the given name is open, a new window is created; otherwise the image of the existing window is
+//! replaced. Each frame is answered by a reply.
+//!
+//! # Wire format
+//!
+//! All integers are unsigned, and encoded in big endian byte order.
+//!
+//! A frame:
+//!
+//! | Field       | Size      | Description                                              |
+//! | ---         | ---       | ---                                                      |
+//! | magic       | 4 bytes   | the ASCII characters `MVF1`                              |
+//! | name length | u16       | length of the window name in bytes                       |
+//! | name        | variable  | the name of the window, as UTF-8                         |
+//! | kind        | u8        | `0` for an encoded image, `1` for raw pixels             |
+//! | width       | u32       | raw pixels only: the width of the image                  |
+//! | height      | u32       | raw pixels only: the height of the image                 |
+//! | format      | u8        | raw pixels only: a [`PixelFormat`]                       |
+//! | data length | u32       | length of the image data in bytes                        |
+//! | data        | variable  | an encoded image (e.g. PNG), or the rows of raw pixels   |
+//!
+//! Raw pixels are 8 bits per channel, without padding between rows. The supported pixel formats
+//! are `1` (luma), `2` (luma with alpha), `3` (RGB), `4` (RGBA), `5` (BGR) and `6` (BGRA).
+//!
+//! Frames of which the width or height exceeds [`MAX_DIMENSION`], or of which the data exceeds
+//! [`MAX_DATA_LENGTH`] bytes, are rejected before their data is read.
+//!
+//! A reply:
+//!
+//! | Field          | Size     | Description                                     |
+//! | ---            | ---      | ---                                             |
+//! | status         | u8       | `0` if the frame was shown, `1` otherwise       |
+//! | message length | u32      | length of the message in bytes                  |
+//! | message        | variable | describes why the frame was not shown, as UTF-8 |
+//!
+//! An example client written in Python can be found in the `examples/serve_client.py` file of
+//! the repository.
+//!
+//! [`Frame`]: struct.Frame.html
+//! [`PixelFormat`]: enum.PixelFormat.html
+//! [`MAX_DIMENSION`]: constant.MAX_DIMENSION.html
+//! [`MAX_DATA_LENGTH`]: constant.MAX_DATA_LENGTH.html
+
+use crate::errors::RemoteError;
+use crate::remote::{self, Request};
+use crate::{MVResult, MiniViewError};
+use base64::Engine;
+use imagecrate::{DynamicImage, ImageBuffer, ImageOutputFormat};
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The first bytes of each frame
+pub const MAGIC: [u8; 4] = *b"MVF1";
+
+/// The largest width or height of the raw pixels of a frame
+pub const MAX_DIMENSION: u32 = 1 << 16;
+
+/// The largest length of the image data of a frame, in bytes
+pub const MAX_DATA_LENGTH: u32 = 1 << 30;
+
+/// How long to wait for a newly started window to accept requests
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The layout of raw pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// A single grey channel
+    Luma8 = 1,
+    /// A grey channel, followed by an alpha channel
+    LumaA8 = 2,
+    /// Red, green and blue channels
+    Rgb8 = 3,
+    /// Red, green, blue and alpha channels
+    Rgba8 = 4,
+    /// Blue, green and red channels
+    Bgr8 = 5,
+    /// Blue, green, red and alpha channels
+    Bgra8 = 6,
+}
+
+impl PixelFormat {
+    fn from_byte(byte: u8) -> Option<PixelFormat> {
+        Some(match byte {
+            1 => PixelFormat::Luma8,
+            2 => PixelFormat::LumaA8,
+            3 => PixelFormat::Rgb8,
+            4 => PixelFormat::Rgba8,
+            5 => PixelFormat::Bgr8,
+            6 => PixelFormat::Bgra8,
+            _ => return None,
+        })
+    }
+
+    /// The number of bytes of a single pixel
+    pub fn channels(self) -> usize {
+        match self {
+            PixelFormat::Luma8 => 1,
+            PixelFormat::LumaA8 => 2,
+            PixelFormat::Rgb8 | PixelFormat::Bgr8 => 3,
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+        }
+    }
+}
+
+/// The image carried by a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameImage {
+    /// An image encoded in any of the supported formats, e.g. PNG
+    Encoded(Vec<u8>),
+    /// Raw pixels, 8 bits per channel, without padding between rows
+    Raw {
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: Vec<u8>,
+    },
+}
+
+/// A message which shows an image in a named window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The name of the window, which is also used as its title
+    pub window: String,
+    /// The image to show
+    pub image: FrameImage,
+}
+
+impl Frame {
+    /// Read a frame; returns `None` if the stream ended before a new frame started
+    pub fn read_from<R: Read>(reader: &mut R) -> MVResult<Option<Frame>> {
+        let mut magic = [0; 4];
+
+        match reader.read_exact(&mut magic) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(_) => return Err(RemoteError::ConnectionLost.into()),
+        }
+
+        if magic != MAGIC {
+            return Err(malformed("unexpected magic bytes"));
+        }
+
+        let name_length = read_u16(reader)?;
+        let window = String::from_utf8(read_bytes(reader, name_length as usize)?)
+            .map_err(|_| malformed("the window name is not valid UTF-8"))?;
+
+        let image = match read_u8(reader)? {
+            0 => {
+                let length = read_length(reader)?;
+                FrameImage::Encoded(read_bytes(reader, length)?)
+            }
+            1 => {
+                let width = read_u32(reader)?;
+                let height = read_u32(reader)?;
+
+                if width > MAX_DIMENSION || height > MAX_DIMENSION {
+                    return Err(malformed(&format!(
+                        "the dimensions {}x{} exceed the maximum of {} pixels",
+                        width, height, MAX_DIMENSION
+                    )));
+                }
+
+                let format = PixelFormat::from_byte(read_u8(reader)?)
+                    .ok_or_else(|| malformed("unknown pixel format"))?;
+                let length = read_length(reader)?;
+
+                FrameImage::Raw {
+                    width,
+                    height,
+                    format,
+                    data: read_bytes(reader, length)?,
+                }
+            }
+            _ => return Err(malformed("unknown image kind")),
+        };
+
+        Ok(Some(Frame { window, image }))
+    }
+
+    /// Write the frame
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> MVResult<()> {
+        let name_length = u16::try_from(self.window.len())
+            .map_err(|_| malformed("the window name is too long"))?;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&name_length.to_be_bytes());
+        bytes.extend_from_slice(self.window.as_bytes());
+
+        let data = match &self.image {
+            FrameImage::Encoded(data) => {
+                bytes.push(0);
+                data
+            }
+            FrameImage::Raw {
+                width,
+                height,
+                format,
+                data,
+            } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&width.to_be_bytes());
+                bytes.extend_from_slice(&height.to_be_bytes());
+                bytes.push(*format as u8);
+                data
+            }
+        };
+
+        let data_length =
+            u32::try_from(data.len()).map_err(|_| malformed("the image data is too large"))?;
+        bytes.extend_from_slice(&data_length.to_be_bytes());
+
+        writer
+            .write_all(&bytes)
+            .and_then(|_| writer.write_all(data))
+            .and_then(|_| writer.flush())
+            .map_err(|_| RemoteError::ConnectionLost.into())
+    }
+
+    /// Decode the image carried by the frame
+    pub fn decode(&self) -> MVResult<DynamicImage> {
+        match &self.image {
+            FrameImage::Encoded(data) => imagecrate::load_from_memory(data)
+                .map_err(|_| malformed("the image could not be decoded")),
+            FrameImage::Raw {
+                width,
+                height,
+                format,
+                data,
+            } => {
+                let (width, height) = (*width, *height);
+                let expected = (width as usize)
+                    .checked_mul(height as usize)
+                    .and_then(|pixels| pixels.checked_mul(format.channels()))
+                    .ok_or_else(|| malformed("the dimensions are too large"))?;
+
+                if data.len() != expected {
+                    return Err(malformed(&format!(
+                        "expected {} bytes of pixel data, but received {}",
+                        expected,
+                        data.len()
+                    )));
+                }
+
+                let data = data.clone();
+                let swap_red_blue = |mut data: Vec<u8>, channels: usize| {
+                    data.chunks_exact_mut(channels)
+                        .for_each(|pixel| pixel.swap(0, 2));
+                    data
+                };
+
+                let image = match format {
+                    PixelFormat::Luma8 => {
+                        ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma8)
+                    }
+                    PixelFormat::LumaA8 => {
+                        ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLumaA8)
+                    }
+                    PixelFormat::Rgb8 => {
+                        ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb8)
+                    }
+                    PixelFormat::Rgba8 => {
+                        ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+                    }
+                    PixelFormat::Bgr8 => {
+                        ImageBuffer::from_raw(width, height, swap_red_blue(data, 3))
+                            .map(DynamicImage::ImageRgb8)
+                    }
+                    PixelFormat::Bgra8 => {
+                        ImageBuffer::from_raw(width, height, swap_red_blue(data, 4))
+                            .map(DynamicImage::ImageRgba8)
+                    }
+                };
+
+                image.ok_or_else(|| malformed("the pixel data does not match the dimensions"))
+            }
+        }
+    }
+}
+
+/// Write a reply to a frame; a reply with an error message tells the client that the frame was
+/// not shown
+pub fn write_reply<W: Write>(writer: &mut W, error: Option<&str>) -> MVResult<()> {
+    let message = error.unwrap_or_default().as_bytes();
+
+    let mut bytes = vec![u8::from(error.is_some())];
+    bytes.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(message);
+
+    writer
+        .write_all(&bytes)
+        .and_then(|_| writer.flush())
+        .map_err(|_| RemoteError::ConnectionLost.into())
+}
+
+/// Read a reply to a frame; a rejected frame results in an error
+pub fn read_reply<R: Read>(reader: &mut R) -> MVResult<()> {
+    let status = read_u8(reader)?;
+    let length = read_u32(reader)?;
+    let message = String::from_utf8_lossy(&read_bytes(reader, length as usize)?).into_owned();
+
+    match status {
+        0 => Ok(()),
+        _ => Err(RemoteError::Rejected(message).into()),
+    }
+}
+
+/// The address at which a server listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    /// A Unix domain socket at the given path
+    Unix(PathBuf),
+    /// A TCP socket
+    Tcp(SocketAddr),
+}
+
+/// Send a frame to the server at the given address, and wait for its reply
+pub fn send(address: &Address, frame: &Frame) -> MVResult<()> {
+    fn exchange<S: Read + Write>(mut stream: S, frame: &Frame) -> MVResult<()> {
+        frame.write_to(&mut stream)?;
+        read_reply(&mut stream)
+    }
+
+    match address {
+        Address::Unix(path) => exchange(
+            UnixStream::connect(path).map_err(|_| RemoteError::UnableToConnect(path.clone()))?,
+            frame,
+        ),
+        Address::Tcp(address) => exchange(
+            TcpStream::connect(address)
+                .map_err(|_| RemoteError::UnableToConnect(address.to_string().into()))?,
+            frame,
+        ),
+    }
+}
+
+/// Serve frames received at the given address, and show them in windows which are started by
+/// running the given `miniview` executable
+///
+/// Each window runs in its own process, and is controlled by the server over a remote control
+/// socket (see the [`remote`] module). A window which was closed by the user is started again
+/// when a new frame for it arrives. Windows are shared by all clients, and stay open when the
+/// client which opened them disconnects. Blocks for as long as the server is listening; use
+/// [`serve_until`] to be able to stop the server.
+///
+/// TCP addresses must be loopback addresses, since anyone who can connect can open windows. Any
+/// file at the path of a Unix domain socket, other than a stale socket, is left alone, and serving
+/// fails.
+///
+/// [`remote`]: ../remote/index.html
+/// [`serve_until`]: fn.serve_until.html
+pub fn serve<P: AsRef<Path>>(address: &Address, program: P) -> MVResult<()> {
+    serve_until(address, program, &AtomicBool::new(false))
+}
+
+/// Like [`serve`], but stops listening once `stop` has been set by [`stop`]
+///
+/// When the server stops, the windows it started are closed, and their sockets are removed.
+///
+/// [`serve`]: fn.serve.html
+/// [`stop`]: fn.stop.html
+pub fn serve_until<P: AsRef<Path>>(
+    address: &Address,
+    program: P,
+    stop: &AtomicBool,
+) -> MVResult<()> {
+    let windows = Arc::new(Windows {
+        program: program.as_ref().to_path_buf(),
+        open: Mutex::new(HashMap::new()),
+        started: AtomicUsize::new(0),
+    });
+
+    match address {
+        Address::Unix(path) => {
+            remote::remove_stale_socket(path)?;
+
+            let listener =
+                UnixListener::bind(path).map_err(|_| RemoteError::UnableToBind(path.clone()))?;
+            let _socket = SocketFile(path.clone());
+
+            for stream in listener.incoming().flatten() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let windows = Arc::clone(&windows);
+                thread::spawn(move || windows.serve_connection(stream));
+            }
+        }
+        Address::Tcp(address) => {
+            if !address.ip().is_loopback() {
+                return Err(RemoteError::NonLocalAddress(*address).into());
+            }
+
+            let listener = TcpListener::bind(address)
+                .map_err(|_| RemoteError::UnableToBind(address.to_string().into()))?;
+
+            for stream in listener.incoming().flatten() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let windows = Arc::clone(&windows);
+                thread::spawn(move || windows.serve_connection(stream));
+            }
+        }
+    }
+
+    // Connections which are still being served hold on to the windows, so close them here
+    windows.close();
+
+    Ok(())
+}
+
+/// Stop a server started by [`serve_until`] with the same address and `stop` flag
+///
+/// The server only notices the flag when a connection arrives, so this also connects to it.
+///
+/// [`serve_until`]: fn.serve_until.html
+pub fn stop(address: &Address, stop: &AtomicBool) {
+    stop.store(true, Ordering::SeqCst);
+
+    let _ = match address {
+        Address::Unix(path) => UnixStream::connect(path).map(drop),
+        Address::Tcp(address) => TcpStream::connect(address).map(drop),
+    };
+}
+
+/// Removes the socket file of the server when it stops listening
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// The windows started by a server, by name
+struct Windows {
+    program: PathBuf,
+    // Each name has its own slot, so a window which is starting only holds up frames for the same
+    // window
+    open: Mutex<HashMap<String, Arc<Mutex<Option<Window>>>>>,
+    started: AtomicUsize,
+}
+
+/// A window process, and the socket at which it is controlled
+///
+/// Dropping a window closes it: the process is killed and reaped, and its socket is removed.
+struct Window {
+    process: Child,
+    socket: PathBuf,
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+impl Windows {
+    /// Close all windows; frames which arrive afterwards open new windows
+    fn close(&self) {
+        let slots = match self.open.lock() {
+            Ok(mut open) => open.drain().map(|(_, slot)| slot).collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+
+        for slot in slots {
+            if let Ok(mut window) = slot.lock() {
+                window.take();
+            }
+        }
+    }
+
+    fn serve_connection<S: Read + Write>(&self, stream: S) {
+        let mut stream = stream;
+
+        loop {
+            let frame = match Frame::read_from(&mut stream) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return,
+                Err(err) => {
+                    // The stream can't be resynchronised after a malformed frame
+                    let _ = write_reply(&mut stream, Some(&err.to_string()));
+                    return;
+                }
+            };
+
+            let error = self.show(frame).err().map(|err| err.to_string());
+
+            if write_reply(&mut stream, error.as_deref()).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn show(&self, frame: Frame) -> MVResult<()> {
+        let image = frame.decode()?;
+
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .map_err(|_| malformed("the image could not be encoded"))?;
+
+        let slot = {
+            let mut open = self.open.lock().map_err(|_| MiniViewError::NoResponse)?;
+            Arc::clone(open.entry(frame.window.clone()).or_default())
+        };
+
+        let mut slot = slot.lock().map_err(|_| MiniViewError::NoResponse)?;
+
+        let alive = slot
+            .as_mut()
+            .and_then(|window| matches!(window.process.try_wait(), Ok(None)).then_some(window));
+
+        if let Some(window) = alive {
+            let request = Request::LoadBytes {
+                data: base64::engine::general_purpose::STANDARD.encode(&png),
+            };
+
+            return reply_to_result(remote::send(&window.socket, &request)?);
+        }
+
+        let socket = remote::runtime_socket_path(&format!(
+            "serve-{}-{}",
+            std::process::id(),
+            self.started.fetch_add(1, Ordering::Relaxed)
+        ))?;
+
+        let mut process = Command::new(&self.program)
+            .arg("--from-stdin-bytes")
+            .arg("--listen")
+            .arg(&socket)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|_| RemoteError::UnableToStartWindow)?;
+
+        process
+            .stdin
+            .take()
+            .ok_or(RemoteError::UnableToStartWindow)?
+            .write_all(&png)
+            .map_err(|_| RemoteError::UnableToStartWindow)?;
+
+        let mut window = Window { process, socket };
+        let started = Instant::now();
+        let title = Request::Title {
+            title: frame.window.clone(),
+        };
+
+        // The window accepts requests once it has been created
+        let response = loop {
+            match remote::send(&window.socket, &title) {
+                Ok(response) => break response,
+                Err(_) if !matches!(window.process.try_wait(), Ok(None)) => {
+                    return Err(RemoteError::UnableToStartWindow.into())
+                }
+                Err(_) if started.elapsed() < START_TIMEOUT => {
+                    thread::sleep(Duration::from_millis(50))
+                }
+                // Dropping the window kills it
+                Err(err) => return Err(err),
+            }
+        };
+
+        *slot = Some(window);
+
+        reply_to_result(response)
+    }
+}
+
+fn reply_to_result(response: remote::Response) -> MVResult<()> {
+    match (response.ok, response.error) {
+        (false, Some(error)) => Err(RemoteError::Rejected(error).into()),
+        _ => Ok(()),
+    }
+}
+
+fn malformed(reason: &str) -> MiniViewError {
+    RemoteError::MalformedFrame(reason.to_string()).into()
+}
+
+fn read_bytes<R: Read>(reader: &mut R, length: usize) -> MVResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    reader
+        .take(length as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|_| MiniViewError::from(RemoteError::ConnectionLost))?;
+
+    if bytes.len() == length {
+        Ok(bytes)
+    } else {
+        Err(RemoteError::ConnectionLost.into())
+    }
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> MVResult<u8> {
+    let bytes = read_bytes(reader, 1)?;
+    Ok(bytes[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> MVResult<u16> {
+    let bytes = read_bytes(reader, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> MVResult<u32> {
+    let bytes = read_bytes(reader, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Read the length of the image data of a frame, which may not exceed the maximum length
+fn read_length<R: Read>(reader: &mut R) -> MVResult<usize> {
+    match read_u32(reader)? {
+        length if length > MAX_DATA_LENGTH => Err(malformed(&format!(
+            "the image data of {} bytes exceeds the maximum of {} bytes",
+            length, MAX_DATA_LENGTH
+        ))),
+        length => Ok(length as usize),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Window, Windows};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::process::Command;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::{Arc, Mutex};
+
+    fn window(name: &str) -> (Window, u32, PathBuf) {
+        let socket = std::env::temp_dir().join(format!(
+            "miniview-serve-window-{}-{}.sock",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&socket, "").unwrap();
+
+        let process = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = process.id();
+
+        (
+            Window {
+                process,
+                socket: socket.clone(),
+            },
+            pid,
+            socket,
+        )
+    }
+
+    fn reaped(pid: u32) -> bool {
+        // Signal 0 only checks whether the process still exists
+        unsafe { libc::kill(pid as libc::pid_t, 0) == -1 }
+    }
+
+    #[test]
+    fn dropping_a_window_closes_it() {
+        let (window, pid, socket) = window("drop");
+
+        drop(window);
+
+        assert!(reaped(pid));
+        assert!(!socket.exists());
+    }
+
+    #[test]
+    fn closing_closes_all_windows() {
+        let (first, first_pid, first_socket) = window("first");
+        let (second, second_pid, second_socket) = window("second");
+
+        let windows = Windows {
+            program: PathBuf::from("miniview"),
+            open: Mutex::new(HashMap::from([
+                ("first".to_string(), Arc::new(Mutex::new(Some(first)))),
+                ("second".to_string(), Arc::new(Mutex::new(Some(second)))),
+            ])),
+            started: AtomicUsize::new(0),
+        };
+
+        windows.close();
+
+        assert!(reaped(first_pid) && reaped(second_pid));
+        assert!(!first_socket.exists() && !second_socket.exists());
+        assert!(windows.open.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,552 @@
+//! The defaults preferred by the user, which are loaded from a configuration file and from the
+//! environment.
+
+use crate::config::{Backend, ConfigBuilder, DropBehavior};
+use crate::errors::SettingsError;
+use crate::keys::{Command, Key};
+use crate::{
+    Channels, Colormap, ColourProfile, Filter, Grid, HistogramScale, MVResult, Rotation, Swizzle,
+    ToneMapOperator, Transparency, ValueRange, Zoom,
+};
+use imagecrate::Rgba;
+use serde::de::value::StrDeserializer;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable which overrides the path of the configuration file
+const CONFIG_PATH_ENV: &str = "MINIVIEW_CONFIG";
+
+/// The command which unbinds a key
+const UNBOUND: &str = "none";
+
+/// The settings which may be set by the configuration file or the environment; settings which
+/// are not set keep the defaults of the [`ConfigBuilder`]
+///
+/// [`ConfigBuilder`]: ../config/struct.ConfigBuilder.html
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Settings {
+    fullscreen: Option<bool>,
+    allow_window_resizing: Option<bool>,
+    lazy_window: Option<bool>,
+    window_name: Option<String>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    drop_behavior: Option<DropBehavior>,
+    background: Option<Colour>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    transparency: Option<Transparency>,
+    checkerboard_size: Option<u32>,
+    checkerboard_colours: Option<[Colour; 2]>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    channels: Option<Channels>,
+    tint: Option<bool>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    swizzle: Option<Swizzle>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    range: Option<ValueRange>,
+    exposure: Option<f32>,
+    gamma: Option<f32>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    tone_mapping: Option<ToneMapOperator>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    colormap: Option<Colormap>,
+    colour_bar: Option<bool>,
+    histogram: Option<bool>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    histogram_scale: Option<HistogramScale>,
+    info: Option<bool>,
+    apply_orientation: Option<bool>,
+    colour_management: Option<bool>,
+    #[serde(deserialize_with = "deserialize_profile")]
+    output_profile: Option<ColourProfile>,
+    #[serde(deserialize_with = "deserialize_rotation")]
+    rotation: Option<Rotation>,
+    flip_horizontal: Option<bool>,
+    flip_vertical: Option<bool>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    filter: Option<Filter>,
+    pixel_grid: Option<bool>,
+    pixel_grid_threshold: Option<f32>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    grid: Option<Grid>,
+    ruler: Option<bool>,
+    fit: Option<Fit>,
+    #[serde(deserialize_with = "deserialize_parsed")]
+    backend: Option<Backend>,
+    keys: HashMap<Key, Binding>,
+}
+
+impl Settings {
+    /// Load the settings from the configuration file, and then from the environment, which takes
+    /// precedence over the file
+    pub(crate) fn from_env_and_file() -> MVResult<Settings> {
+        let mut settings = match std::env::var_os(CONFIG_PATH_ENV) {
+            Some(path) if !path.is_empty() => Settings::from_file(Path::new(&path))?,
+            _ => match default_config_file() {
+                Some(path) if path.is_file() => Settings::from_file(&path)?,
+                _ => Settings::default(),
+            },
+        };
+
+        settings.update_from_env()?;
+
+        Ok(settings)
+    }
+
+    fn from_file(path: &Path) -> Result<Settings, SettingsError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|_| SettingsError::UnableToReadFile(path.to_path_buf()))?;
+
+        toml::from_str(&contents).map_err(|err| {
+            SettingsError::InvalidFile(path.to_path_buf(), err.message().to_string())
+        })
+    }
+
+    fn update_from_env(&mut self) -> Result<(), SettingsError> {
+        update_from_env(&mut self.fullscreen, "MINIVIEW_FULLSCREEN", parse_bool)?;
+        update_from_env(
+            &mut self.allow_window_resizing,
+            "MINIVIEW_ALLOW_WINDOW_RESIZING",
+            parse_bool,
+        )?;
+        update_from_env(&mut self.lazy_window, "MINIVIEW_LAZY_WINDOW", parse_bool)?;
+        update_from_env(&mut self.window_name, "MINIVIEW_WINDOW_NAME", |value| {
+            Some(value.to_string())
+        })?;
+        update_from_env(&mut self.drop_behavior, "MINIVIEW_DROP_BEHAVIOR", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.background, "MINIVIEW_BACKGROUND", |value| {
+            Colour::try_from(value.to_string()).ok()
+        })?;
+        update_from_env(&mut self.transparency, "MINIVIEW_TRANSPARENCY", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(
+            &mut self.checkerboard_size,
+            "MINIVIEW_CHECKERBOARD_SIZE",
+            |value| value.parse().ok(),
+        )?;
+        update_from_env(
+            &mut self.checkerboard_colours,
+            "MINIVIEW_CHECKERBOARD_COLOURS",
+            |value| {
+                let (first, second) = value.split_once(',')?;
+                let colour = |value: &str| Colour::try_from(value.trim().to_string()).ok();
+
+                Some([colour(first)?, colour(second)?])
+            },
+        )?;
+        update_from_env(&mut self.channels, "MINIVIEW_CHANNELS", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.tint, "MINIVIEW_TINT", parse_bool)?;
+        update_from_env(&mut self.swizzle, "MINIVIEW_SWIZZLE", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.range, "MINIVIEW_RANGE", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.exposure, "MINIVIEW_EXPOSURE", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.gamma, "MINIVIEW_GAMMA", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.tone_mapping, "MINIVIEW_TONE_MAPPING", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.colormap, "MINIVIEW_COLORMAP", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.colour_bar, "MINIVIEW_COLOUR_BAR", parse_bool)?;
+        update_from_env(&mut self.histogram, "MINIVIEW_HISTOGRAM", parse_bool)?;
+        update_from_env(
+            &mut self.histogram_scale,
+            "MINIVIEW_HISTOGRAM_SCALE",
+            |value| value.parse().ok(),
+        )?;
+        update_from_env(&mut self.info, "MINIVIEW_INFO", parse_bool)?;
+        update_from_env(
+            &mut self.apply_orientation,
+            "MINIVIEW_APPLY_ORIENTATION",
+            parse_bool,
+        )?;
+        update_from_env(
+            &mut self.colour_management,
+            "MINIVIEW_COLOUR_MANAGEMENT",
+            parse_bool,
+        )?;
+        update_from_env(
+            &mut self.output_profile,
+            "MINIVIEW_OUTPUT_PROFILE",
+            |value| ColourProfile::open(value).ok(),
+        )?;
+        update_from_env(&mut self.rotation, "MINIVIEW_ROTATION", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(
+            &mut self.flip_horizontal,
+            "MINIVIEW_FLIP_HORIZONTAL",
+            parse_bool,
+        )?;
+        update_from_env(
+            &mut self.flip_vertical,
+            "MINIVIEW_FLIP_VERTICAL",
+            parse_bool,
+        )?;
+        update_from_env(&mut self.filter, "MINIVIEW_FILTER", |value| {
+            value.parse().ok()
+        })?;
+        update_from_env(&mut self.pixel_grid, "MINIVIEW_PIXEL_GRID", parse_bool)?;
+        update_from_env(
+            &mut self.pixel_grid_threshold,
+            "MINIVIEW_PIXEL_GRID_THRESHOLD",
+            |value| value.parse().ok(),
+        )?;
+        update_from_env(&mut self.grid, "MINIVIEW_GRID", |value| value.parse().ok())?;
+        update_from_env(&mut self.ruler, "MINIVIEW_RULER", parse_bool)?;
+        update_from_env(&mut self.fit, "MINIVIEW_FIT", parse_enum)?;
+        update_from_env(&mut self.backend, "MINIVIEW_BACKEND", |value| {
+            value.parse().ok()
+        })?;
+
+        Ok(())
+    }
+
+    /// Set the settings which have been set on the builder
+    pub(crate) fn apply(self, mut builder: ConfigBuilder) -> ConfigBuilder {
+        for (key, Binding(command)) in self.keys {
+            builder.key_bindings_mut().bind(key, command);
+        }
+
+        if let Some(value) = self.fullscreen {
+            builder = builder.set_fullscreen(value);
+        }
+
+        if let Some(value) = self.allow_window_resizing {
+            builder = builder.allow_resizable_window(value);
+        }
+
+        if let Some(value) = self.lazy_window {
+            builder = builder.set_lazy_window(value);
+        }
+
+        if let Some(value) = self.window_name {
+            builder = builder.window_name(value);
+        }
+
+        if let Some(value) = self.drop_behavior {
+            builder = builder.drop_behavior(value);
+        }
+
+        if let Some(Colour(value)) = self.background {
+            builder = builder.background(value);
+        }
+
+        if self.transparency.is_some()
+            || self.checkerboard_size.is_some()
+            || self.checkerboard_colours.is_some()
+        {
+            let mut transparency = self.transparency.unwrap_or_default();
+
+            if let Transparency::Checkerboard { size, colours } = &mut transparency {
+                if let Some(value) = self.checkerboard_size {
+                    *size = value;
+                }
+
+                if let Some([first, second]) = self.checkerboard_colours {
+                    *colours = [first.0, second.0];
+                }
+            }
+
+            builder = builder.transparency(transparency);
+        }
+
+        if let Some(value) = self.channels {
+            builder = builder.channels(value);
+        }
+
+        if let Some(value) = self.tint {
+            builder = builder.tinted(value);
+        }
+
+        if let Some(value) = self.swizzle {
+            builder = builder.swizzle(value);
+        }
+
+        if let Some(value) = self.range {
+            builder = builder.value_range(value);
+        }
+
+        if let Some(value) = self.exposure {
+            builder = builder.exposure(value);
+        }
+
+        if let Some(value) = self.gamma {
+            builder = builder.gamma(value);
+        }
+
+        if let Some(value) = self.tone_mapping {
+            builder = builder.tone_map_operator(value);
+        }
+
+        if let Some(value) = self.colormap {
+            builder = builder.colormap(value);
+        }
+
+        if let Some(value) = self.colour_bar {
+            builder = builder.show_colour_bar(value);
+        }
+
+        if let Some(value) = self.histogram {
+            builder = builder.show_histogram(value);
+        }
+
+        if let Some(value) = self.histogram_scale {
+            builder = builder.histogram_scale(value);
+        }
+
+        if let Some(value) = self.info {
+            builder = builder.show_info(value);
+        }
+
+        if let Some(value) = self.apply_orientation {
+            builder = builder.apply_orientation(value);
+        }
+
+        if let Some(value) = self.colour_management {
+            builder = builder.colour_management(value);
+        }
+
+        if let Some(value) = self.output_profile {
+            builder = builder.output_profile(value);
+        }
+
+        if let Some(value) = self.rotation {
+            builder = builder.rotation(value);
+        }
+
+        if let Some(value) = self.flip_horizontal {
+            builder = builder.flip_horizontal(value);
+        }
+
+        if let Some(value) = self.flip_vertical {
+            builder = builder.flip_vertical(value);
+        }
+
+        if let Some(value) = self.filter {
+            builder = builder.filter(value);
+        }
+
+        if let Some(value) = self.pixel_grid {
+            builder = builder.show_pixel_grid(value);
+        }
+
+        if let Some(value) = self.pixel_grid_threshold {
+            builder = builder.pixel_grid_threshold(value);
+        }
+
+        if let Some(value) = self.grid {
+            builder = builder.grid(value);
+        }
+
+        if let Some(value) = self.ruler {
+            builder = builder.show_ruler(value);
+        }
+
+        if let Some(value) = self.fit {
+            builder = builder.initial_zoom(value.into());
+        }
+
+        if let Some(value) = self.backend {
+            builder = builder.backend(value);
+        }
+
+        builder
+    }
+}
+
+/// The default path of the configuration file, within the configuration directory of the user
+fn default_config_file() -> Option<PathBuf> {
+    config_directory().map(|directory| directory.join("miniview").join("config.toml"))
+}
+
+#[cfg(not(windows))]
+fn config_directory() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(directory) if !directory.is_empty() => Some(PathBuf::from(directory)),
+        _ => std::env::var_os("HOME")
+            .filter(|home| !home.is_empty())
+            .map(|home| PathBuf::from(home).join(".config")),
+    }
+}
+
+#[cfg(windows)]
+fn config_directory() -> Option<PathBuf> {
+    std::env::var_os("APPDATA")
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Replace the setting by the value of the environment variable, if it is set
+fn update_from_env<T, F>(setting: &mut Option<T>, name: &str, parse: F) -> Result<(), SettingsError>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    let Some(value) = std::env::var_os(name) else {
+        return Ok(());
+    };
+
+    let invalid = || {
+        SettingsError::InvalidEnvironmentVariable(
+            name.to_string(),
+            value.to_string_lossy().into_owned(),
+        )
+    };
+
+    let value = value.to_str().ok_or_else(invalid)?;
+    *setting = Some(parse(value.trim()).ok_or_else(invalid)?);
+
+    Ok(())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a value by its name, as it would be written in the configuration file
+fn parse_enum<T: DeserializeOwned>(value: &str) -> Option<T> {
+    let deserializer: StrDeserializer<'_, serde::de::value::Error> = value.into_deserializer();
+
+    T::deserialize(deserializer).ok()
+}
+
+/// Deserialize a setting which is written as a string, and parsed by its `FromStr` implementation
+fn deserialize_parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+
+    value.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a rotation, which is written as a number of degrees
+fn deserialize_rotation<'de, D>(deserializer: D) -> Result<Option<Rotation>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let degrees = i32::deserialize(deserializer)?;
+
+    Rotation::try_from(degrees)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a colour profile, which is written as the path of the ICC profile
+fn deserialize_profile<'de, D>(deserializer: D) -> Result<Option<ColourProfile>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path = PathBuf::deserialize(deserializer)?;
+
+    ColourProfile::open(path)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// A colour written as `#rrggbb` or `#rrggbbaa`
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct Colour(pub(crate) Rgba<u8>);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Colour {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let [red, green, blue, alpha] = self.0 .0;
+
+        serializer.serialize_str(&format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            red, green, blue, alpha
+        ))
+    }
+}
+
+impl TryFrom<String> for Colour {
+    type Error = SettingsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || SettingsError::InvalidColour(value.clone());
+
+        let digits = value.strip_prefix('#').ok_or_else(invalid)?;
+
+        if !(digits.len() == 6 || digits.len() == 8) || !digits.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut channels = [255; 4];
+
+        for (channel, index) in channels.iter_mut().zip((0..digits.len()).step_by(2)) {
+            *channel = u8::from_str_radix(&digits[index..index + 2], 16).map_err(|_| invalid())?;
+        }
+
+        Ok(Colour(Rgba(channels)))
+    }
+}
+
+/// How an image is first shown
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Fit {
+    /// Scale the image down to fit within the window
+    Fit,
+    /// Show the image at its actual size
+    ActualSize,
+}
+
+impl From<Fit> for Zoom {
+    fn from(fit: Fit) -> Self {
+        match fit {
+            Fit::Fit => Zoom::Fit,
+            Fit::ActualSize => Zoom::Factor(1.0),
+        }
+    }
+}
+
+/// The command bound to a key, or `None` if the key has been unbound
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct Binding(pub(crate) Option<Command>);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Binding {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0 {
+            Some(command) => command.serialize(serializer),
+            None => serializer.serialize_str(UNBOUND),
+        }
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = SettingsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == UNBOUND {
+            return Ok(Binding(None));
+        }
+
+        parse_enum(&value)
+            .map(|command| Binding(Some(command)))
+            .ok_or(SettingsError::UnknownCommand(value))
+    }
+}
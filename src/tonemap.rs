@@ -0,0 +1,405 @@
+//! Mapping of the values of an image to the range which can be displayed, for images with a high
+//! bit depth, or with floating point (HDR) values.
+
+use crate::errors::SettingsError;
+use imagecrate::{ColorType, DynamicImage, Rgba, Rgba32FImage, RgbaImage};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Largest number of values which is sampled to compute the percentiles of an image
+const PERCENTILE_SAMPLES: usize = 1 << 20;
+
+/// The range of values of the image which is mapped to the displayed range, from black to white
+///
+/// Values are normalized: for images with integer values, `0.0` is the smallest and `1.0` the
+/// largest value which can be stored; floating point values are used as is.
+///
+/// Written as `full`, `auto`, `percentile:<LOW>,<HIGH>` (e.g. `percentile:1,99`) or
+/// `window:<LEVEL>,<WIDTH>` (e.g. `window:0.5,0.25`).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub enum ValueRange {
+    /// The range from `0.0` to `1.0`
+    #[default]
+    Full,
+    /// Stretch the range from the smallest to the largest value of the image
+    Auto,
+    /// Stretch the range between the given percentiles of the values of the image, so outliers
+    /// are clipped
+    Percentile(f32, f32),
+    /// The range of the given width, centered around the given level
+    Window {
+        /// The value which is shown as mid-grey
+        level: f32,
+        /// The width of the range
+        width: f32,
+    },
+}
+
+impl ValueRange {
+    /// The smallest and largest value of the range, for the given values of an image
+    pub(crate) fn bounds<I: Iterator<Item = f32> + Clone>(&self, values: I) -> (f32, f32) {
+        let values = values.filter(|value| value.is_finite());
+
+        match *self {
+            ValueRange::Full => (0.0, 1.0),
+            ValueRange::Auto => values
+                .fold(None, |bounds, value| match bounds {
+                    None => Some((value, value)),
+                    Some((low, high)) => Some((f32::min(low, value), f32::max(high, value))),
+                })
+                .unwrap_or((0.0, 1.0)),
+            ValueRange::Percentile(low, high) => {
+                let count = values.clone().count();
+                let step = (count / PERCENTILE_SAMPLES).max(1);
+                let mut samples = values.step_by(step).collect::<Vec<_>>();
+
+                if samples.is_empty() {
+                    return (0.0, 1.0);
+                }
+
+                samples.sort_unstable_by(f32::total_cmp);
+
+                let at = |percentile: f32| {
+                    let index = (percentile.clamp(0.0, 100.0) / 100.0 * (samples.len() - 1) as f32)
+                        .round() as usize;
+                    samples[index]
+                };
+
+                (at(low), at(high))
+            }
+            ValueRange::Window { level, width } => (level - width / 2.0, level + width / 2.0),
+        }
+    }
+}
+
+impl Display for ValueRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueRange::Full => f.write_str("full"),
+            ValueRange::Auto => f.write_str("auto"),
+            ValueRange::Percentile(low, high) => write!(f, "percentile:{},{}", low, high),
+            ValueRange::Window { level, width } => write!(f, "window:{},{}", level, width),
+        }
+    }
+}
+
+impl FromStr for ValueRange {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || SettingsError::InvalidValueRange(value.to_string());
+
+        let pair = |arguments: &str| -> Result<(f32, f32), SettingsError> {
+            let (first, second) = arguments.split_once(',').ok_or_else(invalid)?;
+            let parse = |number: &str| number.trim().parse::<f32>().map_err(|_| invalid());
+
+            Ok((parse(first)?, parse(second)?))
+        };
+
+        match value.split_once(':') {
+            None if value == "full" => Ok(ValueRange::Full),
+            None if value == "auto" => Ok(ValueRange::Auto),
+            Some(("percentile", arguments)) => {
+                let (low, high) = pair(arguments)?;
+
+                if !(0.0..=100.0).contains(&low) || !(0.0..=100.0).contains(&high) || low >= high {
+                    return Err(invalid());
+                }
+
+                Ok(ValueRange::Percentile(low, high))
+            }
+            Some(("window", arguments)) => {
+                let (level, width) = pair(arguments)?;
+
+                if width <= 0.0 {
+                    return Err(invalid());
+                }
+
+                Ok(ValueRange::Window { level, width })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<ValueRange> for String {
+    fn from(range: ValueRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl TryFrom<String> for ValueRange {
+    type Error = SettingsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// How values outside of the displayed range are compressed into it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ToneMapOperator {
+    /// Clip values to the displayed range
+    #[default]
+    Clamp,
+    /// The Reinhard operator, `v / (1 + v)`
+    Reinhard,
+    /// An approximation of the ACES filmic curve
+    Aces,
+}
+
+impl ToneMapOperator {
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            ToneMapOperator::Clamp => value,
+            ToneMapOperator::Reinhard => value.max(0.0) / (1.0 + value.max(0.0)),
+            ToneMapOperator::Aces => {
+                // Krzysztof Narkowicz's fit of the ACES curve
+                let value = value.max(0.0);
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+        }
+    }
+
+    /// The next operator, to cycle through the operators
+    pub(crate) fn next(self) -> Self {
+        match self {
+            ToneMapOperator::Clamp => ToneMapOperator::Reinhard,
+            ToneMapOperator::Reinhard => ToneMapOperator::Aces,
+            ToneMapOperator::Aces => ToneMapOperator::Clamp,
+        }
+    }
+}
+
+impl FromStr for ToneMapOperator {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "clamp" => Ok(ToneMapOperator::Clamp),
+            "reinhard" => Ok(ToneMapOperator::Reinhard),
+            "aces" => Ok(ToneMapOperator::Aces),
+            _ => Err(SettingsError::InvalidToneMapOperator(value.to_string())),
+        }
+    }
+}
+
+/// How the values of the image are mapped to the values which are displayed
+///
+/// The values within the [`range`] are stretched to the range from `0.0` to `1.0`, then scaled by
+/// the [`exposure`], compressed by the [`operator`], and finally raised to the power of
+/// `1 / gamma`. The alpha channel is not mapped.
+///
+/// By default, images are shown as they are.
+///
+/// [`range`]: struct.ToneMapping.html#structfield.range
+/// [`exposure`]: struct.ToneMapping.html#structfield.exposure
+/// [`operator`]: struct.ToneMapping.html#structfield.operator
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct ToneMapping {
+    /// The range of values which is shown from black to white
+    pub range: ValueRange,
+    /// Exposure in stops, where each stop doubles the brightness
+    pub exposure: f32,
+    /// Gamma of the display encoding; `2.2` approximates sRGB for images with linear values
+    pub gamma: f32,
+    /// How values outside of the displayed range are compressed into it
+    pub operator: ToneMapOperator,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping {
+            range: ValueRange::Full,
+            exposure: 0.0,
+            gamma: 1.0,
+            operator: ToneMapOperator::Clamp,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// Whether the values of the image are shown as they are
+    pub(crate) fn is_identity(&self) -> bool {
+        *self == ToneMapping::default()
+    }
+
+    /// Map a value within the given bounds of the range, to a displayed level between `0.0` and
+    /// `1.0`
+    pub(crate) fn level(&self, value: f32, (low, high): (f32, f32)) -> f32 {
+        let value = (value - low) / (high - low).max(f32::EPSILON);
+        let value = value * self.exposure.exp2();
+        let value = self.operator.apply(value).clamp(0.0, 1.0);
+
+        value.powf(1.0 / self.gamma.max(f32::EPSILON))
+    }
+
+    /// Map a value within the given bounds of the range, to a displayed value
+    pub(crate) fn map(&self, value: f32, bounds: (f32, f32)) -> u8 {
+        (self.level(value, bounds) * 255.0).round() as u8
+    }
+}
+
+/// The pixels of an image, at their original precision
+#[derive(Debug)]
+pub(crate) struct Original {
+    pixels: Pixels,
+    // The colour type of the image as it was given
+    colour_type: ColorType,
+}
+
+#[derive(Debug)]
+enum Pixels {
+    /// Images with 8 bits per channel
+    Standard(RgbaImage),
+    /// Images with more than 8 bits per channel, or with floating point values
+    High(Rgba32FImage),
+}
+
+impl Original {
+    pub(crate) fn new(image: DynamicImage) -> Self {
+        let colour_type = image.color();
+
+        let pixels = match image {
+            DynamicImage::ImageLuma8(_)
+            | DynamicImage::ImageLumaA8(_)
+            | DynamicImage::ImageRgb8(_)
+            | DynamicImage::ImageRgba8(_) => Pixels::Standard(image.to_rgba8()),
+            _ => Pixels::High(image.to_rgba32f()),
+        };
+
+        Original {
+            pixels,
+            colour_type,
+        }
+    }
+
+    pub(crate) fn dimensions(&self) -> (u32, u32) {
+        match &self.pixels {
+            Pixels::Standard(image) => image.dimensions(),
+            Pixels::High(image) => image.dimensions(),
+        }
+    }
+
+    /// Whether the image has a single colour channel
+    pub(crate) fn is_greyscale(&self) -> bool {
+        !self.colour_type.has_color()
+    }
+
+    /// The image at its original precision, converted back to the colour type it was given in
+    pub(crate) fn image(&self) -> DynamicImage {
+        let image = match &self.pixels {
+            Pixels::Standard(image) => DynamicImage::ImageRgba8(image.clone()),
+            Pixels::High(image) => DynamicImage::ImageRgba32F(image.clone()),
+        };
+
+        match self.colour_type {
+            ColorType::L8 => image.into_luma8().into(),
+            ColorType::La8 => image.into_luma_alpha8().into(),
+            ColorType::Rgb8 => image.into_rgb8().into(),
+            ColorType::Rgba8 => image.into_rgba8().into(),
+            ColorType::L16 => image.into_luma16().into(),
+            ColorType::La16 => image.into_luma_alpha16().into(),
+            ColorType::Rgb16 => image.into_rgb16().into(),
+            ColorType::Rgba16 => image.into_rgba16().into(),
+            ColorType::Rgb32F => image.into_rgb32f().into(),
+            _ => image,
+        }
+    }
+
+    /// The smallest and largest value of the range of the tone mapping, for this image
+    pub(crate) fn bounds(&self, tone_mapping: &ToneMapping) -> (f32, f32) {
+        match &self.pixels {
+            Pixels::Standard(image) => tone_mapping.range.bounds(
+                image
+                    .pixels()
+                    .flat_map(|pixel| pixel.0[..3].iter().map(|value| f32::from(*value) / 255.0)),
+            ),
+            Pixels::High(image) => tone_mapping.range.bounds(
+                image
+                    .pixels()
+                    .flat_map(|pixel| pixel.0[..3].iter().copied()),
+            ),
+        }
+    }
+
+    /// The normalized values of the pixels, row by row
+    pub(crate) fn pixels(&self) -> Box<dyn Iterator<Item = [f32; 4]> + '_> {
+        match &self.pixels {
+            Pixels::Standard(image) => Box::new(
+                image
+                    .pixels()
+                    .map(|pixel| pixel.0.map(|value| f32::from(value) / 255.0)),
+            ),
+            Pixels::High(image) => Box::new(image.pixels().map(|pixel| pixel.0)),
+        }
+    }
+
+    /// The normalized value of the pixel at the given position, if any
+    pub(crate) fn pixel(&self, x: u32, y: u32) -> Option<[f32; 4]> {
+        let (width, height) = self.dimensions();
+
+        if x >= width || y >= height {
+            return None;
+        }
+
+        match &self.pixels {
+            Pixels::Standard(image) => Some(
+                image
+                    .get_pixel(x, y)
+                    .0
+                    .map(|value| f32::from(value) / 255.0),
+            ),
+            Pixels::High(image) => Some(image.get_pixel(x, y).0),
+        }
+    }
+
+    /// The image as it is displayed, with its values within the given bounds mapped by the tone
+    /// mapping
+    pub(crate) fn display(&self, tone_mapping: &ToneMapping, bounds: (f32, f32)) -> RgbaImage {
+        match &self.pixels {
+            Pixels::Standard(image) if tone_mapping.is_identity() => image.clone(),
+            Pixels::Standard(image) => {
+                // 8 bit values can be mapped by a lookup table
+                let table = (0..=255u8)
+                    .map(|value| tone_mapping.map(f32::from(value) / 255.0, bounds))
+                    .collect::<Vec<_>>();
+
+                let mut display = image.clone();
+
+                for pixel in display.pixels_mut() {
+                    for value in &mut pixel.0[..3] {
+                        *value = table[usize::from(*value)];
+                    }
+                }
+
+                display
+            }
+            Pixels::High(image) => RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+                let [red, green, blue, alpha] = image.get_pixel(x, y).0;
+
+                Rgba([
+                    tone_mapping.map(red, bounds),
+                    tone_mapping.map(green, bounds),
+                    tone_mapping.map(blue, bounds),
+                    (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+                ])
+            }),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Rotating and flipping the view of an image, without altering the image itself.
+
+use crate::errors::SettingsError;
+use imagecrate::DynamicImage;
+use std::str::FromStr;
+
+/// A rotation of the view, clockwise, in steps of 90 degrees
+///
+/// Written as the number of degrees, e.g. `90`; negative angles rotate counter-clockwise, so
+/// `-90` is the same rotation as `270`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "u32", try_from = "i32")
+)]
+pub enum Rotation {
+    /// The image is not rotated
+    #[default]
+    Normal,
+    /// Rotated by 90 degrees clockwise
+    Rotate90,
+    /// Rotated by 180 degrees, upside down
+    Rotate180,
+    /// Rotated by 270 degrees clockwise, or 90 degrees counter-clockwise
+    Rotate270,
+}
+
+impl Rotation {
+    /// The rotation by the given number of degrees, if it is a multiple of 90
+    pub fn from_degrees(degrees: i32) -> Option<Rotation> {
+        if degrees % 90 != 0 {
+            return None;
+        }
+
+        Some(Rotation::from_quarter_turns(degrees.rem_euclid(360) / 90))
+    }
+
+    /// The rotation in degrees clockwise: `0`, `90`, `180` or `270`
+    pub fn degrees(self) -> u32 {
+        self.quarter_turns() as u32 * 90
+    }
+
+    fn quarter_turns(self) -> i32 {
+        match self {
+            Rotation::Normal => 0,
+            Rotation::Rotate90 => 1,
+            Rotation::Rotate180 => 2,
+            Rotation::Rotate270 => 3,
+        }
+    }
+
+    fn from_quarter_turns(turns: i32) -> Rotation {
+        match turns.rem_euclid(4) {
+            0 => Rotation::Normal,
+            1 => Rotation::Rotate90,
+            2 => Rotation::Rotate180,
+            _ => Rotation::Rotate270,
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = SettingsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .trim_end_matches('°')
+            .parse()
+            .ok()
+            .and_then(Rotation::from_degrees)
+            .ok_or_else(|| SettingsError::InvalidRotation(value.to_string()))
+    }
+}
+
+impl From<Rotation> for u32 {
+    fn from(rotation: Rotation) -> Self {
+        rotation.degrees()
+    }
+}
+
+impl TryFrom<i32> for Rotation {
+    type Error = SettingsError;
+
+    fn try_from(degrees: i32) -> Result<Self, Self::Error> {
+        Rotation::from_degrees(degrees)
+            .ok_or_else(|| SettingsError::InvalidRotation(degrees.to_string()))
+    }
+}
+
+/// How the image is rotated and flipped when it is shown
+///
+/// The image is first flipped, and then rotated. The transform only changes how the image is
+/// shown: the pixels of the image are not altered, and positions of the image, such as those of
+/// [`MiniViewHandle::pixel`], are positions of the shown image, which map back to the pixels of
+/// the image through the transform.
+///
+/// [`MiniViewHandle::pixel`]: handle/struct.MiniViewHandle.html#method.pixel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct ViewTransform {
+    /// The rotation, clockwise
+    pub rotation: Rotation,
+    /// Mirror the image left to right
+    pub flip_horizontal: bool,
+    /// Mirror the image top to bottom
+    pub flip_vertical: bool,
+}
+
+impl ViewTransform {
+    /// Whether the image is shown as it is
+    pub fn is_identity(&self) -> bool {
+        self.parts() == (0, false)
+    }
+
+    /// This transform, followed by a rotation of the shown image by 90 degrees clockwise
+    pub fn rotated_clockwise(self) -> ViewTransform {
+        let (turns, mirrored) = self.parts();
+        ViewTransform::from_parts(turns + 1, mirrored)
+    }
+
+    /// This transform, followed by a rotation of the shown image by 90 degrees counter-clockwise
+    pub fn rotated_counter_clockwise(self) -> ViewTransform {
+        let (turns, mirrored) = self.parts();
+        ViewTransform::from_parts(turns + 3, mirrored)
+    }
+
+    /// This transform, followed by mirroring the shown image left to right
+    pub fn flipped_horizontally(self) -> ViewTransform {
+        let (turns, mirrored) = self.parts();
+        ViewTransform::from_parts(-turns, !mirrored)
+    }
+
+    /// This transform, followed by mirroring the shown image top to bottom
+    pub fn flipped_vertically(self) -> ViewTransform {
+        let (turns, mirrored) = self.parts();
+        ViewTransform::from_parts(2 - turns, !mirrored)
+    }
+
+    /// The image as it is shown: flipped, and then rotated
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        let (turns, mirrored) = self.parts();
+        let image = if mirrored { image.fliph() } else { image };
+
+        match turns {
+            1 => image.rotate90(),
+            2 => image.rotate180(),
+            3 => image.rotate270(),
+            _ => image,
+        }
+    }
+
+    /// The dimensions of the shown image, for an image of the given dimensions
+    pub(crate) fn dimensions(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        if self.parts().0 % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Map a position of the shown image to the position of the image, for an image of the given
+    /// dimensions
+    pub(crate) fn image_position(&self, (x, y): (f32, f32), dimensions: (u32, u32)) -> (f32, f32) {
+        let (turns, mirrored) = self.parts();
+        let (mut x, mut y) = (x, y);
+        let (mut width, mut height) = self.dimensions(dimensions);
+
+        // Undo the rotations, one quarter turn counter-clockwise at a time
+        for _ in 0..turns {
+            (x, y) = (y, width as f32 - x);
+            (width, height) = (height, width);
+        }
+
+        if mirrored {
+            x = width as f32 - x;
+        }
+
+        (x, y)
+    }
+
+    /// Map a position of the image to the position of the shown image, for an image of the given
+    /// dimensions
+    pub(crate) fn view_position(&self, (x, y): (f32, f32), dimensions: (u32, u32)) -> (f32, f32) {
+        let (turns, mirrored) = self.parts();
+        let (mut x, mut y) = (x, y);
+        let (mut width, mut height) = dimensions;
+
+        if mirrored {
+            x = width as f32 - x;
+        }
+
+        for _ in 0..turns {
+            (x, y) = (height as f32 - y, x);
+            (width, height) = (height, width);
+        }
+
+        (x, y)
+    }
+
+    /// The transform as a number of quarter turns clockwise, preceded by a horizontal mirror if
+    /// set; a vertical flip is a horizontal flip followed by a half turn
+    fn parts(&self) -> (i32, bool) {
+        let turns = self.rotation.quarter_turns();
+
+        match (self.flip_horizontal, self.flip_vertical) {
+            (false, false) => (turns, false),
+            (true, false) => (turns, true),
+            (false, true) => ((turns + 2) % 4, true),
+            (true, true) => ((turns + 2) % 4, false),
+        }
+    }
+
+    fn from_parts(turns: i32, mirrored: bool) -> ViewTransform {
+        ViewTransform {
+            rotation: Rotation::from_quarter_turns(turns),
+            flip_horizontal: mirrored,
+            flip_vertical: false,
+        }
+    }
+}
@@ -0,0 +1,1071 @@
+//! The view of an image within a window, which is rendered on the CPU, so it can be shared by all
+//! backends.
+
+use crate::channels::{Channel, Channels, Swizzle};
+use crate::colormap::Colormap;
+use crate::config::Config;
+use crate::filter::{Filter, Mipmaps, Sampler};
+use crate::grid::Grid;
+use crate::histogram::{Histogram, HistogramScale};
+use crate::metadata::Metadata;
+use crate::overlay;
+use crate::profile::ColourProfile;
+use crate::tonemap::{Original, ToneMapping, ValueRange};
+use crate::transform::ViewTransform;
+use crate::Source;
+use imagecrate::{DynamicImage, Rgba, RgbaImage};
+
+/// Factor by which a single zoom in or zoom out step scales the image
+const ZOOM_STEP: f32 = 1.25;
+
+/// Stops by which a single exposure step changes the exposure
+const EXPOSURE_STEP: f32 = 0.5;
+
+/// Amount by which a single gamma step changes the gamma, and the smallest and largest gamma
+const GAMMA_STEP: f32 = 0.1;
+const GAMMA_RANGE: (f32, f32) = (0.1, 10.0);
+
+/// Smallest and largest zoom factors which can be set
+const ZOOM_RANGE: (f32, f32) = (0.01, 256.0);
+
+/// Colour of the area of the window which is not covered by the image, unless configured otherwise
+pub(crate) const DEFAULT_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Zoom factor beyond which the pixel grid is shown, unless configured otherwise
+pub(crate) const DEFAULT_PIXEL_GRID_THRESHOLD: f32 = 8.0;
+
+/// Sizes of the blocks of the grids which can be cycled through, besides the configured grid
+const GRID_SIZES: [u32; 3] = [8, 16, 64];
+
+/// Size, in window pixels, of the squares of the default checkerboard
+const CHECKERBOARD_SIZE: u32 = 8;
+
+/// Colours of the squares of the default checkerboard
+const CHECKERBOARD_COLOURS: [Rgba<u8>; 2] =
+    [Rgba([204, 204, 204, 255]), Rgba([153, 153, 153, 255])];
+
+/// The colours in which the histograms of the colour channels are drawn; where the bars of the
+/// histograms overlap, their colours are added
+const HISTOGRAM_COLOURS: [Rgba<u8>; 3] = [
+    Rgba([190, 40, 40, 255]),
+    Rgba([40, 190, 40, 255]),
+    Rgba([40, 60, 200, 255]),
+];
+const HISTOGRAM_GREY: Rgba<u8> = Rgba([200, 200, 200, 255]);
+
+/// Colour of the lines of a grid of blocks of pixels, of which the alpha is the opacity
+const GRID_COLOUR: Rgba<u8> = Rgba([255, 64, 192, 192]);
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// What transparent pixels of the image are composited over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transparency {
+    /// A checkerboard of squares with the given size, in window pixels, alternating between the
+    /// two colours
+    Checkerboard {
+        /// Size of the squares, in window pixels
+        size: u32,
+        /// Colours of the squares
+        colours: [Rgba<u8>; 2],
+    },
+    /// A single colour
+    Solid(Rgba<u8>),
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Transparency::Checkerboard {
+            size: CHECKERBOARD_SIZE,
+            colours: CHECKERBOARD_COLOURS,
+        }
+    }
+}
+
+impl Transparency {
+    /// The colour over which the pixel at the given window position is composited
+    fn colour_at(&self, x: u32, y: u32) -> Rgba<u8> {
+        match *self {
+            Transparency::Checkerboard { size, colours } => {
+                let size = size.max(1);
+                colours[((x / size + y / size) % 2) as usize]
+            }
+            Transparency::Solid(colour) => colour,
+        }
+    }
+}
+
+impl std::str::FromStr for Transparency {
+    type Err = crate::errors::SettingsError;
+
+    /// Parses `checkerboard` (the default checkerboard), `black`, `white`, or a colour written as
+    /// `#rrggbb` or `#rrggbbaa`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "checkerboard" => Ok(Transparency::default()),
+            "black" => Ok(Transparency::Solid(BLACK)),
+            "white" => Ok(Transparency::Solid(WHITE)),
+            colour => crate::settings::Colour::try_from(colour.to_string())
+                .map(|colour| Transparency::Solid(colour.0)),
+        }
+    }
+}
+
+/// The zoom level of the view
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Zoom {
+    /// Show the image at its actual size, unless the image does not fit within the window, in
+    /// which case the image is scaled down to fit within the window
+    #[default]
+    Fit,
+    /// Scale the image by the given factor, where a factor of `1.0` shows the image at its actual
+    /// size
+    Factor(f32),
+}
+
+/// The image which is shown by a window, and the camera through which it is viewed
+#[derive(Debug)]
+pub(crate) struct View {
+    // The pixels of the image at their original precision, and the pixels as they are displayed
+    original: Original,
+    image: RgbaImage,
+    // The displayed pixels converted from the colour profile of the image to the output profile,
+    // if the image has a colour profile which can be converted
+    managed: Option<RgbaImage>,
+    colour_management: bool,
+    output_profile: Option<ColourProfile>,
+    // How the image is sampled when it is scaled, and the mipmaps of the displayed pixels which
+    // are shown, once they are needed; the flag tells whether they are of the converted pixels
+    filter: Filter,
+    mipmaps: Option<(bool, Mipmaps)>,
+    pixel_grid: bool,
+    pixel_grid_threshold: f32,
+    // The configured grid, followed by the alternatives which can be cycled through
+    grids: Vec<Option<Grid>>,
+    grid: usize,
+    ruler: bool,
+    tone_mapping: ToneMapping,
+    // The smallest and largest value of the range of the tone mapping, for the current image
+    bounds: (f32, f32),
+    // The configured tone mapping, which the tone mapping can be reset to
+    initial_tone_mapping: ToneMapping,
+    zoom: Zoom,
+    // The zoom level of a newly shown image
+    initial_zoom: Zoom,
+    background: Rgba<u8>,
+    // The configured transparency, followed by the alternatives which can be cycled through
+    transparencies: Vec<Transparency>,
+    transparency: usize,
+    channels: Channels,
+    // Show a single colour channel in its own colour, instead of as greyscale
+    tinted: bool,
+    // The configured swizzle, followed by the alternatives which can be cycled through
+    swizzles: Vec<Swizzle>,
+    swizzle: usize,
+    colormap: Colormap,
+    colour_bar: bool,
+    // The histogram of the current image, which is computed once it is shown
+    histogram: Option<Histogram>,
+    show_histogram: bool,
+    histogram_scale: HistogramScale,
+    // The metadata of the current image, which is shown by the info panel
+    metadata: Metadata,
+    info: bool,
+    // Where the current image was loaded from, if it can be loaded again
+    source: Option<Source>,
+    // How the image is rotated and flipped; the dimensions, positions and center of the view are
+    // those of the shown image
+    transform: ViewTransform,
+    // The position of the shown image, in image pixels, which is shown at the center of the
+    // window
+    center: (f32, f32),
+    frame: RgbaImage,
+    dirty: bool,
+}
+
+impl View {
+    pub(crate) fn new(image: DynamicImage, metadata: Metadata, config: &Config) -> Self {
+        let original = Original::new(image);
+        let bounds = original.bounds(&config.tone_mapping());
+        let image = original.display(&config.tone_mapping(), bounds);
+        let center = image_center(config.transform().dimensions(image.dimensions()));
+
+        let mut transparencies = vec![config.transparency()];
+
+        for alternative in [
+            Transparency::default(),
+            Transparency::Solid(BLACK),
+            Transparency::Solid(WHITE),
+        ] {
+            if !transparencies.contains(&alternative) {
+                transparencies.push(alternative);
+            }
+        }
+
+        let mut swizzles = vec![config.swizzle()];
+
+        for alternative in [Swizzle::RGBA, Swizzle::BGRA] {
+            if !swizzles.contains(&alternative) {
+                swizzles.push(alternative);
+            }
+        }
+
+        let mut grids = vec![config.grid()];
+
+        for alternative in
+            std::iter::once(None).chain(GRID_SIZES.map(|size| Some(Grid::square(size))))
+        {
+            if !grids.contains(&alternative) {
+                grids.push(alternative);
+            }
+        }
+
+        let mut view = View {
+            original,
+            image,
+            managed: None,
+            colour_management: config.colour_management(),
+            output_profile: config.output_profile().cloned(),
+            filter: config.filter(),
+            mipmaps: None,
+            pixel_grid: config.pixel_grid(),
+            pixel_grid_threshold: config.pixel_grid_threshold(),
+            grids,
+            grid: 0,
+            ruler: config.ruler(),
+            tone_mapping: config.tone_mapping(),
+            bounds,
+            initial_tone_mapping: config.tone_mapping(),
+            zoom: config.initial_zoom(),
+            initial_zoom: config.initial_zoom(),
+            background: config.background(),
+            transparencies,
+            transparency: 0,
+            channels: config.channels(),
+            tinted: config.tinted(),
+            swizzles,
+            swizzle: 0,
+            colormap: config.colormap(),
+            colour_bar: config.colour_bar(),
+            histogram: None,
+            show_histogram: config.histogram(),
+            histogram_scale: config.histogram_scale(),
+            metadata,
+            info: config.info(),
+            source: match config.source() {
+                // Stdin has been consumed by the time the window is created
+                Source::StdinBytes => None,
+                source => Some(source.clone()),
+            },
+            transform: config.transform(),
+            center,
+            frame: RgbaImage::new(0, 0),
+            dirty: true,
+        };
+
+        view.update_managed();
+        view
+    }
+
+    /// The dimensions of the image as it is shown, i.e. rotated
+    pub(crate) fn image_size(&self) -> (u32, u32) {
+        self.transform.dimensions(self.image.dimensions())
+    }
+
+    /// Replace the image; the camera is reset if the dimensions of the image changed
+    ///
+    /// The metadata of the previous image is cleared; see [`set_metadata`].
+    ///
+    /// [`set_metadata`]: struct.View.html#method.set_metadata
+    pub(crate) fn set_image(&mut self, image: DynamicImage) {
+        let original = Original::new(image);
+        let bounds = original.bounds(&self.tone_mapping);
+        let image = original.display(&self.tone_mapping, bounds);
+
+        if image.dimensions() != self.image.dimensions() {
+            self.center = image_center(self.transform.dimensions(image.dimensions()));
+            self.zoom = self.initial_zoom;
+        }
+
+        self.original = original;
+        self.histogram = None;
+        self.metadata = Metadata::default();
+        self.bounds = bounds;
+        self.image = image;
+        self.managed = None;
+        self.mipmaps = None;
+        self.dirty = true;
+    }
+
+    /// Set the metadata of the current image, of which the colour profile describes the colours
+    /// of the image
+    pub(crate) fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = metadata;
+        self.update_managed();
+        self.dirty = true;
+    }
+
+    /// Where the current image was loaded from, if it can be loaded again
+    pub(crate) fn source(&self) -> Option<&Source> {
+        self.source.as_ref()
+    }
+
+    /// Record where the current image was loaded from; `None` for images which were set from
+    /// memory
+    pub(crate) fn set_source(&mut self, source: Option<Source>) {
+        self.source = source;
+    }
+
+    /// The value of the pixel at the given position of the shown image, at its original precision
+    ///
+    /// The position is mapped through the transform, to the pixel of the image which is shown
+    /// there. Values of images with integer values are normalized to the range from `0.0` to
+    /// `1.0`.
+    pub(crate) fn pixel(&self, x: u32, y: u32) -> Option<[f32; 4]> {
+        let (width, height) = self.image_size();
+
+        if x >= width || y >= height {
+            return None;
+        }
+
+        let (x, y) = self.source_pixel(x, y);
+        self.original.pixel(x, y)
+    }
+
+    /// The image as it is shown: rotated and flipped, at its original precision and in its
+    /// original colour type
+    pub(crate) fn transformed_image(&self) -> DynamicImage {
+        self.transform.apply(self.original.image())
+    }
+
+    /// The pixel of the image which is shown at the given position of the shown image
+    fn source_pixel(&self, x: u32, y: u32) -> (u32, u32) {
+        let (x, y) = self
+            .transform
+            .image_position((x as f32 + 0.5, y as f32 + 0.5), self.original.dimensions());
+
+        (x as u32, y as u32)
+    }
+
+    /// Change the exposure by the given number of steps
+    pub(crate) fn adjust_exposure(&mut self, steps: f32) {
+        self.tone_mapping.exposure += steps * EXPOSURE_STEP;
+        self.update_image();
+    }
+
+    /// Change the gamma by the given number of steps
+    pub(crate) fn adjust_gamma(&mut self, steps: f32) {
+        self.tone_mapping.gamma =
+            (self.tone_mapping.gamma + steps * GAMMA_STEP).clamp(GAMMA_RANGE.0, GAMMA_RANGE.1);
+        self.update_image();
+    }
+
+    /// Map the next range of values to the displayed range: the configured range, the full range,
+    /// the range from the smallest to the largest value, and the range between the 1st and 99th
+    /// percentile
+    ///
+    /// The configured range, such as a window range, is part of the cycle, so it is shown again
+    /// after the alternatives.
+    pub(crate) fn cycle_value_range(&mut self) {
+        let mut ranges = vec![self.initial_tone_mapping.range];
+
+        for alternative in [
+            ValueRange::Full,
+            ValueRange::Auto,
+            ValueRange::Percentile(1.0, 99.0),
+        ] {
+            if !ranges.contains(&alternative) {
+                ranges.push(alternative);
+            }
+        }
+
+        let next = ranges
+            .iter()
+            .position(|range| *range == self.tone_mapping.range)
+            .map_or(0, |index| (index + 1) % ranges.len());
+
+        self.tone_mapping.range = ranges[next];
+        self.update_image();
+    }
+
+    /// Compress values outside of the displayed range with the next tone mapping operator
+    pub(crate) fn cycle_tone_map_operator(&mut self) {
+        self.tone_mapping.operator = self.tone_mapping.operator.next();
+        self.update_image();
+    }
+
+    /// Reset the tone mapping to the configured tone mapping
+    pub(crate) fn reset_tone_mapping(&mut self) {
+        self.tone_mapping = self.initial_tone_mapping;
+        self.update_image();
+    }
+
+    /// Map the original pixels to the displayed pixels
+    fn update_image(&mut self) {
+        self.bounds = self.original.bounds(&self.tone_mapping);
+        self.image = self.original.display(&self.tone_mapping, self.bounds);
+        self.update_managed();
+        self.dirty = true;
+    }
+
+    /// Convert the displayed pixels from the colour profile of the image to the output profile
+    ///
+    /// Images without a colour profile are assumed to have the colours of the output profile.
+    fn update_managed(&mut self) {
+        self.mipmaps = None;
+        self.managed = self
+            .metadata
+            .colour_profile
+            .as_ref()
+            .filter(|profile| Some(*profile) != self.output_profile.as_ref())
+            .and_then(|profile| profile.convert(&self.image, self.output_profile.as_ref()));
+    }
+
+    pub(crate) fn set_zoom(&mut self, zoom: Zoom) {
+        self.zoom = match zoom {
+            Zoom::Fit => Zoom::Fit,
+            Zoom::Factor(factor) => Zoom::Factor(factor.clamp(ZOOM_RANGE.0, ZOOM_RANGE.1)),
+        };
+        self.dirty = true;
+    }
+
+    /// Zoom in by a single step, relative to the current scale for a window of the given size
+    pub(crate) fn zoom_in(&mut self, window_size: (u32, u32)) {
+        let scale = self.scale(window_size);
+        self.set_zoom(Zoom::Factor(scale * ZOOM_STEP));
+    }
+
+    /// Zoom out by a single step, relative to the current scale for a window of the given size
+    pub(crate) fn zoom_out(&mut self, window_size: (u32, u32)) {
+        let scale = self.scale(window_size);
+        self.set_zoom(Zoom::Factor(scale / ZOOM_STEP));
+    }
+
+    /// The factor by which the image is scaled, when shown in a window of the given size
+    pub(crate) fn scale(&self, window_size: (u32, u32)) -> f32 {
+        match self.zoom {
+            Zoom::Fit => {
+                let (width, height) = self.image_size();
+                let horizontal = window_size.0 as f32 / width.max(1) as f32;
+                let vertical = window_size.1 as f32 / height.max(1) as f32;
+
+                horizontal.min(vertical).min(1.0).max(ZOOM_RANGE.0)
+            }
+            Zoom::Factor(factor) => factor,
+        }
+    }
+
+    /// The position of the shown image, in image pixels, which is shown at the center of the
+    /// window
+    pub(crate) fn center(&self) -> (f32, f32) {
+        self.center
+    }
+
+    /// Center the view on the given position of the shown image, in image pixels
+    pub(crate) fn pan_to(&mut self, x: f32, y: f32) {
+        let (width, height) = self.image_size();
+
+        self.center = (x.clamp(0.0, width as f32), y.clamp(0.0, height as f32));
+        self.dirty = true;
+    }
+
+    /// Rotate or flip the shown image by the given change of the transform, keeping the same
+    /// position of the image at the center of the window
+    pub(crate) fn transform_by(&mut self, change: fn(ViewTransform) -> ViewTransform) {
+        let dimensions = self.image.dimensions();
+        let center = self.transform.image_position(self.center, dimensions);
+
+        self.transform = change(self.transform);
+        self.center = self.transform.view_position(center, dimensions);
+        self.dirty = true;
+    }
+
+    /// Sample the image with the next filter, when it is scaled
+    pub(crate) fn cycle_filter(&mut self) {
+        self.filter = self.filter.next();
+        self.dirty = true;
+    }
+
+    /// Show, or hide, the pixel grid when zoomed in beyond the threshold
+    pub(crate) fn toggle_pixel_grid(&mut self) {
+        self.pixel_grid = !self.pixel_grid;
+        self.dirty = true;
+    }
+
+    /// Show the next alternative grid of blocks of pixels, or no grid
+    pub(crate) fn cycle_grid(&mut self) {
+        self.grid = (self.grid + 1) % self.grids.len();
+        self.dirty = true;
+    }
+
+    /// Show, or hide, the rulers along the edges of the window
+    pub(crate) fn toggle_ruler(&mut self) {
+        self.ruler = !self.ruler;
+        self.dirty = true;
+    }
+
+    /// Composite transparent pixels over the next alternative transparency
+    pub(crate) fn cycle_transparency(&mut self) {
+        self.transparency = (self.transparency + 1) % self.transparencies.len();
+        self.dirty = true;
+    }
+
+    /// Show the given channels, or all channels if they were already shown
+    pub(crate) fn toggle_channels(&mut self, channels: Channels) {
+        self.channels = self.channels.toggle(channels);
+        self.dirty = true;
+    }
+
+    /// Switch between showing a single colour channel as greyscale, or in its own colour
+    pub(crate) fn toggle_tint(&mut self) {
+        self.tinted = !self.tinted;
+        self.dirty = true;
+    }
+
+    /// Read the channels of the image in the order of the next alternative swizzle
+    pub(crate) fn cycle_swizzle(&mut self) {
+        self.swizzle = (self.swizzle + 1) % self.swizzles.len();
+        self.dirty = true;
+    }
+
+    /// Show single channels with the next colormap
+    pub(crate) fn cycle_colormap(&mut self) {
+        self.colormap = self.colormap.next();
+        self.dirty = true;
+    }
+
+    /// Show, or hide, the colour bar
+    pub(crate) fn toggle_colour_bar(&mut self) {
+        self.colour_bar = !self.colour_bar;
+        self.dirty = true;
+    }
+
+    /// Show, or hide, the histogram
+    pub(crate) fn toggle_histogram(&mut self) {
+        self.show_histogram = !self.show_histogram;
+        self.dirty = true;
+    }
+
+    /// Switch the histogram between a linear and a logarithmic scale
+    pub(crate) fn toggle_histogram_scale(&mut self) {
+        self.histogram_scale = match self.histogram_scale {
+            HistogramScale::Linear => HistogramScale::Log,
+            HistogramScale::Log => HistogramScale::Linear,
+        };
+        self.dirty = true;
+    }
+
+    /// Show, or hide, the info panel
+    pub(crate) fn toggle_info(&mut self) {
+        self.info = !self.info;
+        self.dirty = true;
+    }
+
+    /// Switch between converting the colours of the image to the output profile, and showing the
+    /// raw values
+    pub(crate) fn toggle_colour_management(&mut self) {
+        self.colour_management = !self.colour_management;
+        self.dirty = true;
+    }
+
+    /// The lines of the info panel: the dimensions of the image, its colour profile, and its key
+    /// EXIF fields
+    fn info_lines(&self) -> Vec<String> {
+        let (width, height) = self.original.dimensions();
+        let metadata = &self.metadata;
+        let mut lines = vec![format!("size {}x{}", width, height)];
+
+        if let Some(profile) = &metadata.colour_profile {
+            let state = match (self.colour_management, &self.managed) {
+                (false, _) => "  (unmanaged)",
+                (true, None) if Some(profile) != self.output_profile.as_ref() => "  (unsupported)",
+                _ => "",
+            };
+
+            lines.push(format!(
+                "profile {}{}",
+                profile.description().unwrap_or("embedded"),
+                state
+            ));
+        }
+
+        if !metadata.has_exif() {
+            lines.push("no exif metadata".to_string());
+            return lines;
+        }
+
+        if let Some(camera) = &metadata.camera {
+            lines.push(format!("camera {}", camera));
+        }
+
+        if let Some(lens) = &metadata.lens {
+            lines.push(format!("lens {}", lens));
+        }
+
+        let exposure = [
+            metadata.exposure_time.clone(),
+            metadata.f_number.clone(),
+            metadata.iso.map(|iso| format!("iso {}", iso)),
+            metadata.focal_length.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        if !exposure.is_empty() {
+            lines.push(format!("exposure {}", exposure.join("  ")));
+        }
+
+        if let Some(timestamp) = &metadata.timestamp {
+            lines.push(format!("taken {}", timestamp));
+        }
+
+        if let Some(gps) = &metadata.gps {
+            let altitude = gps
+                .altitude
+                .map(|altitude| format!("  {:.0} m", altitude))
+                .unwrap_or_default();
+
+            lines.push(format!(
+                "gps {:.5}, {:.5}{}",
+                gps.latitude, gps.longitude, altitude
+            ));
+        }
+
+        lines
+    }
+
+    /// The colormap with which the image is shown; colormaps only apply when a single channel is
+    /// shown
+    fn active_colormap(&self) -> Colormap {
+        let single_channel = match self.channels {
+            Channels::All => self.original.is_greyscale(),
+            Channels::Single(_) | Channels::Luminance => true,
+        };
+
+        if single_channel {
+            self.colormap
+        } else {
+            Colormap::Greyscale
+        }
+    }
+
+    /// Move the view by the given distance in window pixels
+    pub(crate) fn pan_by(&mut self, dx: f32, dy: f32, window_size: (u32, u32)) {
+        let scale = self.scale(window_size);
+        let (x, y) = self.center;
+
+        self.pan_to(x + dx / scale, y + dy / scale);
+    }
+
+    /// Redraws the frame for a window of the given size, if the frame is out of date
+    ///
+    /// Returns whether the frame has been redrawn.
+    pub(crate) fn update_frame(&mut self, window_size: (u32, u32)) -> bool {
+        if !self.dirty && self.frame.dimensions() == window_size {
+            return false;
+        }
+
+        if self.show_histogram && self.histogram.is_none() {
+            self.histogram = Some(Histogram::of(&self.original));
+        }
+
+        let (pixels, managed) = self.shown_pixels(self.active_colormap());
+        let area = self.filter.at_scale(self.scale(window_size)) == Filter::Area;
+
+        if area && self.mipmaps.as_ref().map(|(managed, _)| *managed) != Some(managed) {
+            let mipmaps = Mipmaps::new(pixels);
+            self.mipmaps = Some((managed, mipmaps));
+        }
+
+        self.frame = self.draw(window_size);
+        self.dirty = false;
+
+        true
+    }
+
+    /// The last drawn frame
+    pub(crate) fn frame(&self) -> &RgbaImage {
+        &self.frame
+    }
+
+    /// The displayed pixels which are shown with the given colormap, and whether they are
+    /// converted to the output profile
+    ///
+    /// Colours are converted to the output profile, but single channels and colormaps show the raw
+    /// values.
+    fn shown_pixels(&self, colormap: Colormap) -> (&RgbaImage, bool) {
+        match (&self.managed, self.channels, colormap) {
+            (Some(managed), Channels::All, Colormap::Greyscale) if self.colour_management => {
+                (managed, true)
+            }
+            _ => (&self.image, false),
+        }
+    }
+
+    /// The position of the top left corner of the shown image within a window of the given size,
+    /// in window pixels
+    fn origin(&self, (width, height): (u32, u32)) -> (f32, f32) {
+        let scale = self.scale((width, height));
+        let (center_x, center_y) = self.center;
+
+        (
+            width as f32 / 2.0 - center_x * scale,
+            height as f32 / 2.0 - center_y * scale,
+        )
+    }
+
+    /// Whether the pixel grid is drawn in a window of the given size: if it is enabled, and the
+    /// image is zoomed in at least as far as the threshold
+    fn shows_pixel_grid(&self, window_size: (u32, u32)) -> bool {
+        self.pixel_grid && self.scale(window_size) >= self.pixel_grid_threshold
+    }
+
+    /// The positions of the vertical and of the horizontal lines of the grid within a window of
+    /// the given size, in whole window pixels
+    fn grid_lines(&self, grid: Grid, window_size: (u32, u32)) -> (Vec<f32>, Vec<f32>) {
+        let scale = self.scale(window_size);
+        let origin = self.origin(window_size);
+        let (columns, rows) = grid.lines(&self.transform, self.image.dimensions());
+        let to_window = |lines: Vec<f32>, origin: f32| {
+            lines
+                .into_iter()
+                .map(|line| (origin + line * scale).floor())
+                .collect::<Vec<_>>()
+        };
+
+        (to_window(columns, origin.0), to_window(rows, origin.1))
+    }
+
+    /// Draws the image as seen through the camera, sampled with the filter
+    fn draw(&self, window_size: (u32, u32)) -> RgbaImage {
+        let (width, height) = window_size;
+        let (image_width, image_height) = self.image_size();
+        let dimensions = self.image.dimensions();
+        let scale = self.scale(window_size);
+        let (center_x, center_y) = self.center;
+
+        // The position of the shown image at the center of a window pixel, if it is covered by
+        // the image
+        let to_image = |position: u32, window_extent: u32, center: f32, extent: u32| {
+            let coordinate = (position as f32 + 0.5 - window_extent as f32 / 2.0) / scale + center;
+
+            (coordinate >= 0.0 && coordinate < extent as f32).then_some(coordinate)
+        };
+
+        let columns = (0..width)
+            .map(|x| to_image(x, width, center_x, image_width))
+            .collect::<Vec<_>>();
+
+        let mut frame = RgbaImage::from_pixel(width, height, self.background);
+        let transparency = &self.transparencies[self.transparency];
+        let swizzle = self.swizzles[self.swizzle];
+        let colormap = self.active_colormap();
+        let table = (colormap != Colormap::Greyscale).then(|| colormap.table());
+        let (image, managed) = self.shown_pixels(colormap);
+        let mipmaps = self
+            .mipmaps
+            .as_ref()
+            .filter(|(mipmaps_managed, _)| *mipmaps_managed == managed)
+            .map(|(_, mipmaps)| mipmaps);
+        let sampler = Sampler::new(image, mipmaps, self.filter, scale);
+
+        for y in 0..height {
+            let Some(image_y) = to_image(y, height, center_y, image_height) else {
+                continue;
+            };
+
+            for (x, image_x) in columns.iter().enumerate() {
+                if let Some(image_x) = image_x {
+                    let position = self
+                        .transform
+                        .image_position((*image_x, image_y), dimensions);
+                    let pixel = swizzle.apply(sampler.sample(position));
+                    let pixel = match &table {
+                        Some(table) => {
+                            let shown = self.channels.apply(pixel, false);
+                            let mut colour = table[usize::from(shown[0])];
+                            colour[3] = shown[3];
+                            colour
+                        }
+                        None => self.channels.apply(pixel, self.tinted),
+                    };
+                    let x = x as u32;
+
+                    let pixel = composite(pixel, transparency.colour_at(x, y));
+
+                    frame.put_pixel(x, y, pixel);
+                }
+            }
+        }
+
+        let origin = self.origin(window_size);
+        let extent = (image_width as f32 * scale, image_height as f32 * scale);
+        let area = [origin.0, origin.1, origin.0 + extent.0, origin.1 + extent.1];
+
+        if self.shows_pixel_grid(window_size) {
+            let (columns, rows) = self.grid_lines(Grid::square(1), window_size);
+
+            overlay::draw_grid(&mut frame, &columns, &rows, area, None);
+        }
+
+        if let Some(grid) = self.grids[self.grid] {
+            let (columns, rows) = self.grid_lines(grid, window_size);
+
+            overlay::draw_grid(&mut frame, &columns, &rows, area, Some(GRID_COLOUR));
+        }
+
+        if self.ruler {
+            overlay::draw_ruler(&mut frame, origin, scale, self.image_size());
+        }
+
+        if self.colour_bar {
+            overlay::draw_colour_bar(&mut frame, colormap, &self.tone_mapping, self.bounds);
+        }
+
+        if let (true, Some(histogram)) = (self.show_histogram, &self.histogram) {
+            // The histograms of the channels which are shown, as they are read by the swizzle
+            let histograms = match self.channels {
+                Channels::All => [Channel::Red, Channel::Green, Channel::Blue]
+                    .iter()
+                    .zip(HISTOGRAM_COLOURS)
+                    .map(|(channel, colour)| (histogram.channel(swizzle.source(*channel)), colour))
+                    .collect(),
+                Channels::Single(channel) => {
+                    let colour = match channel {
+                        Channel::Alpha => HISTOGRAM_GREY,
+                        _ => HISTOGRAM_COLOURS[channel.index()],
+                    };
+
+                    vec![(histogram.channel(swizzle.source(channel)), colour)]
+                }
+                Channels::Luminance => vec![(histogram.luminance(), HISTOGRAM_GREY)],
+            };
+
+            overlay::draw_histogram(&mut frame, &histograms, self.histogram_scale);
+        }
+
+        if self.info {
+            overlay::draw_info(&mut frame, &self.info_lines());
+        }
+
+        frame
+    }
+}
+
+/// Composite the pixel over the opaque backdrop
+fn composite(pixel: Rgba<u8>, backdrop: Rgba<u8>) -> Rgba<u8> {
+    let alpha = u32::from(pixel[3]);
+    let blend = |channel: usize| {
+        let value =
+            u32::from(pixel[channel]) * alpha + u32::from(backdrop[channel]) * (255 - alpha);
+        ((value + 127) / 255) as u8
+    };
+
+    Rgba([blend(0), blend(1), blend(2), 255])
+}
+
+fn image_center((width, height): (u32, u32)) -> (f32, f32) {
+    (width as f32 / 2.0, height as f32 / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{composite, Transparency, View, Zoom, BLACK, WHITE};
+    use crate::config::ConfigBuilder;
+    use crate::grid::Grid;
+    use crate::metadata::Metadata;
+    use crate::tonemap::ValueRange;
+    use crate::transform::ViewTransform;
+    use imagecrate::{DynamicImage, Rgba, RgbaImage};
+
+    /// A red pixel, which is half transparent
+    const HALF_RED: Rgba<u8> = Rgba([255, 0, 0, 128]);
+
+    fn composited(transparency: Transparency, x: u32, y: u32) -> Rgba<u8> {
+        composite(HALF_RED, transparency.colour_at(x, y))
+    }
+
+    #[test]
+    fn composite_over_checkerboard() {
+        let checkerboard = Transparency::default();
+
+        // the squares of the default checkerboard are 8 pixels in size, starting with the light
+        // square in the top left corner
+        assert_eq!(composited(checkerboard, 0, 0), Rgba([230, 102, 102, 255]));
+        assert_eq!(composited(checkerboard, 7, 7), Rgba([230, 102, 102, 255]));
+        assert_eq!(composited(checkerboard, 8, 0), Rgba([204, 76, 76, 255]));
+        assert_eq!(composited(checkerboard, 0, 8), Rgba([204, 76, 76, 255]));
+        assert_eq!(composited(checkerboard, 8, 8), Rgba([230, 102, 102, 255]));
+    }
+
+    #[test]
+    fn composite_over_solid_colour() {
+        assert_eq!(
+            composited(Transparency::Solid(BLACK), 3, 5),
+            Rgba([128, 0, 0, 255])
+        );
+        assert_eq!(
+            composited(Transparency::Solid(WHITE), 3, 5),
+            Rgba([255, 127, 127, 255])
+        );
+        assert_eq!(
+            composited(Transparency::Solid(Rgba([0, 0, 255, 255])), 3, 5),
+            Rgba([128, 0, 127, 255])
+        );
+    }
+
+    #[test]
+    fn opaque_and_transparent_pixels() {
+        let backdrop = Rgba([10, 20, 30, 255]);
+
+        assert_eq!(
+            composite(Rgba([1, 2, 3, 255]), backdrop),
+            Rgba([1, 2, 3, 255])
+        );
+        assert_eq!(composite(Rgba([1, 2, 3, 0]), backdrop), backdrop);
+    }
+
+    const WINDOW: (u32, u32) = (100, 100);
+
+    /// A view of a 4x2 image, zoomed in by the given factor
+    fn view(zoom: f32, config: ConfigBuilder) -> View {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(4, 2));
+        let mut view = View::new(image, Metadata::default(), &config.build());
+        view.set_zoom(Zoom::Factor(zoom));
+        view
+    }
+
+    fn lines(view: &View, grid: Grid) -> (Vec<f32>, Vec<f32>) {
+        let (mut columns, mut rows) = view.grid_lines(grid, WINDOW);
+        columns.sort_by(f32::total_cmp);
+        rows.sort_by(f32::total_cmp);
+
+        (columns, rows)
+    }
+
+    #[test]
+    fn grid_lines_when_zoomed() {
+        // the image is centered, so its top left corner is at (50 - 2 * 10, 50 - 1 * 10)
+        let view = view(10.0, ConfigBuilder::from_path(""));
+
+        assert_eq!(
+            lines(&view, Grid::square(1)),
+            (vec![30.0, 40.0, 50.0, 60.0, 70.0], vec![40.0, 50.0, 60.0])
+        );
+        assert_eq!(
+            lines(&view, Grid::square(2)),
+            (vec![30.0, 50.0, 70.0], vec![40.0, 60.0])
+        );
+
+        // lines are drawn at whole window pixels
+        let view = self::view(2.5, ConfigBuilder::from_path(""));
+
+        assert_eq!(
+            lines(&view, Grid::square(1)),
+            (vec![45.0, 47.0, 50.0, 52.0, 55.0], vec![47.0, 50.0, 52.0])
+        );
+    }
+
+    #[test]
+    fn grid_lines_when_panned() {
+        let mut view = view(10.0, ConfigBuilder::from_path(""));
+        view.pan_to(0.0, 0.0);
+
+        assert_eq!(
+            lines(&view, Grid::square(1)),
+            (vec![50.0, 60.0, 70.0, 80.0, 90.0], vec![50.0, 60.0, 70.0])
+        );
+    }
+
+    #[test]
+    fn grid_lines_when_rotated() {
+        let mut view = view(10.0, ConfigBuilder::from_path(""));
+        view.transform_by(ViewTransform::rotated_clockwise);
+        view.pan_to(1.0, 2.0);
+
+        // the shown image is 2x4, with its top left corner at (50 - 1 * 10, 50 - 2 * 10)
+        assert_eq!(
+            lines(&view, Grid::square(1)),
+            (vec![40.0, 50.0, 60.0], vec![30.0, 40.0, 50.0, 60.0, 70.0])
+        );
+
+        // the blocks are 2 pixels wide and 1 pixel high in the image, so they are 1 pixel wide
+        // and 2 pixels high when shown rotated
+        assert_eq!(
+            lines(&view, Grid::new(2, 1)),
+            (vec![40.0, 50.0, 60.0], vec![30.0, 50.0, 70.0])
+        );
+    }
+
+    #[test]
+    fn pixel_grid_threshold() {
+        // by default, the pixel grid is shown from a zoom factor of 8
+        assert!(!view(7.9, ConfigBuilder::from_path("")).shows_pixel_grid(WINDOW));
+        assert!(view(8.0, ConfigBuilder::from_path("")).shows_pixel_grid(WINDOW));
+
+        let threshold = || ConfigBuilder::from_path("").pixel_grid_threshold(4.0);
+        assert!(!view(3.9, threshold()).shows_pixel_grid(WINDOW));
+        assert!(view(4.0, threshold()).shows_pixel_grid(WINDOW));
+
+        let mut view = view(16.0, ConfigBuilder::from_path(""));
+        view.toggle_pixel_grid();
+        assert!(!view.shows_pixel_grid(WINDOW));
+
+        let disabled = ConfigBuilder::from_path("").show_pixel_grid(false);
+        assert!(!self::view(16.0, disabled).shows_pixel_grid(WINDOW));
+    }
+
+    fn cycled_ranges(config: ConfigBuilder) -> Vec<ValueRange> {
+        let mut view = view(1.0, config);
+
+        (0..5)
+            .map(|_| {
+                view.cycle_value_range();
+                view.tone_mapping.range
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cycle_value_ranges() {
+        let percentile = ValueRange::Percentile(1.0, 99.0);
+
+        assert_eq!(
+            cycled_ranges(ConfigBuilder::from_path("")),
+            [
+                ValueRange::Auto,
+                percentile,
+                ValueRange::Full,
+                ValueRange::Auto,
+                percentile
+            ]
+        );
+    }
+
+    #[test]
+    fn cycle_value_ranges_through_the_configured_window() {
+        let window = ValueRange::Window {
+            level: 0.25,
+            width: 0.5,
+        };
+        let config = ConfigBuilder::from_path("").value_range(window);
+
+        assert_eq!(
+            cycled_ranges(config),
+            [
+                ValueRange::Full,
+                ValueRange::Auto,
+                ValueRange::Percentile(1.0, 99.0),
+                window,
+                ValueRange::Full
+            ]
+        );
+    }
+}
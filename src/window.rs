@@ -0,0 +1,252 @@
+//! Window behaviour which is shared between the backends.
+
+use crate::channels::{Channel, Channels};
+use crate::config::Config;
+use crate::gallery::Gallery;
+use crate::handle::{Shared, WindowReady, WindowState};
+use crate::keys::Command;
+use crate::transform::ViewTransform;
+use crate::view::{View, Zoom};
+use crate::{Action, MVResult, Source};
+use raw_window_handle::RawWindowHandle;
+
+/// Fraction of the window size by which a single pan step moves the view
+const PAN_STEP: f32 = 0.1;
+
+/// Operations which each backend implements for its window
+pub(crate) trait WindowControl {
+    fn view(&self) -> &View;
+
+    fn view_mut(&mut self) -> &mut View;
+
+    /// Request the window to draw the view again
+    fn request_redraw(&mut self);
+
+    fn title(&self) -> String;
+
+    fn set_title(&mut self, title: &str);
+
+    fn fullscreen(&self) -> bool;
+
+    fn set_fullscreen(&mut self, value: bool);
+
+    /// The inner size of the window, in physical pixels
+    fn size(&self) -> (u32, u32);
+
+    /// Resize the inner size of the window, in physical pixels
+    fn resize(&mut self, width: u32, height: u32);
+
+    /// Move the outer position of the window, in physical pixels
+    fn move_to(&mut self, x: i32, y: i32);
+
+    fn set_visible(&mut self, value: bool);
+
+    fn scale_factor(&self) -> f64;
+
+    fn focused(&self) -> bool;
+
+    /// Bring the window to the front, and give it input focus
+    fn focus(&mut self);
+
+    /// The platform specific identifier of the window, if the platform exposes one
+    fn window_id(&self) -> Option<u64>;
+
+    /// The images between which can be moved, if the window shows more than one image
+    fn gallery_mut(&mut self) -> Option<&mut Gallery>;
+}
+
+/// Whether the window should continue to be shown, or should be closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Flow {
+    Continue,
+    Exit,
+}
+
+/// The current state of the window, which can be queried by the handles
+pub(crate) fn state<W: WindowControl>(window: &W) -> WindowState {
+    let size = window.size();
+    let view = window.view();
+
+    WindowState {
+        size,
+        scale_factor: window.scale_factor(),
+        focused: window.focused(),
+        fullscreen: window.fullscreen(),
+        title: window.title(),
+        image_size: view.image_size(),
+        zoom: view.scale(size),
+        center: view.center(),
+    }
+}
+
+/// Show another image of the gallery, if the window shows a gallery
+fn step_gallery<W: WindowControl>(window: &mut W, offset: isize) {
+    let Some(gallery) = window.gallery_mut() else {
+        return;
+    };
+
+    if let Some((image, metadata)) = gallery.step(offset) {
+        let title = gallery.title();
+        let source = Source::ByPath(gallery.path().to_path_buf());
+
+        window.view_mut().set_image(image);
+        window.view_mut().set_metadata(metadata);
+        window.view_mut().set_source(Some(source));
+        window.set_title(&title);
+    }
+}
+
+/// Signal that the first frame of the window has been presented
+pub(crate) fn notify_ready<W: WindowControl>(window: &W, config: &Config, shared: &Shared) {
+    let ready = WindowReady {
+        window_id: window.window_id(),
+        size: window.size(),
+    };
+
+    shared.set_ready(ready.clone());
+
+    if let Some(callback) = config.on_ready() {
+        callback(&ready);
+    }
+}
+
+/// The identifier of a window which other programs can use to find it: the X11 window ID, or the
+/// `HWND` on Windows
+pub(crate) fn platform_window_id(handle: RawWindowHandle) -> Option<u64> {
+    match handle {
+        // c_ulong is 32 bits wide on some platforms
+        #[allow(clippy::useless_conversion)]
+        RawWindowHandle::Xlib(handle) => Some(u64::from(handle.window)),
+        RawWindowHandle::Xcb(handle) => Some(u64::from(handle.window)),
+        RawWindowHandle::Win32(handle) => Some(handle.hwnd as usize as u64),
+        _ => None,
+    }
+}
+
+/// Apply an action received from a handle, and publish the resulting state of the window
+pub(crate) fn handle_action<W: WindowControl>(
+    window: &mut W,
+    action: Action,
+    shared: &Shared,
+) -> MVResult<Flow> {
+    match action {
+        Action::Close => return Ok(Flow::Exit),
+        Action::SetImage(image, metadata, source) => {
+            window.view_mut().set_image(image);
+            window.view_mut().set_metadata(*metadata);
+            window.view_mut().set_source(source);
+            window.request_redraw();
+        }
+        Action::SetTitle(title) => window.set_title(&title),
+        Action::SetFullscreen(value) => window.set_fullscreen(value),
+        Action::ToggleFullscreen => window.set_fullscreen(!window.fullscreen()),
+        Action::Resize(width, height) => window.resize(width, height),
+        Action::Move(x, y) => window.move_to(x, y),
+        Action::SetVisible(value) => window.set_visible(value),
+        Action::Focus => window.focus(),
+        Action::SetZoom(zoom) => {
+            window.view_mut().set_zoom(zoom);
+            window.request_redraw();
+        }
+        Action::PanTo(x, y) => {
+            window.view_mut().pan_to(x, y);
+            window.request_redraw();
+        }
+        Action::Screenshot(reply) => {
+            let size = window.size();
+            let view = window.view_mut();
+            view.update_frame(size);
+
+            let _ = reply.send(view.frame().clone());
+        }
+        Action::Pixel(x, y, reply) => {
+            let _ = reply.send(window.view().pixel(x, y));
+        }
+        Action::Image(reply) => {
+            let _ = reply.send(window.view().transformed_image());
+        }
+        Action::Source(reply) => {
+            let _ = reply.send(window.view().source().cloned());
+        }
+        Action::Sync(reply) => {
+            shared.set_state(state(window));
+            let _ = reply.send(());
+        }
+    }
+
+    shared.set_state(state(window));
+
+    Ok(Flow::Continue)
+}
+
+/// Apply a command triggered by a key press
+pub(crate) fn handle_command<W: WindowControl>(window: &mut W, command: Command) -> Flow {
+    let size = window.size();
+    let pan_step = (size.0 as f32 * PAN_STEP, size.1 as f32 * PAN_STEP);
+
+    match command {
+        Command::Quit => return Flow::Exit,
+        Command::ToggleFullscreen => window.set_fullscreen(!window.fullscreen()),
+        Command::LeaveFullscreenOrQuit if window.fullscreen() => window.set_fullscreen(false),
+        Command::LeaveFullscreenOrQuit => return Flow::Exit,
+        Command::ZoomIn => window.view_mut().zoom_in(size),
+        Command::ZoomOut => window.view_mut().zoom_out(size),
+        Command::ZoomToFit => window.view_mut().set_zoom(Zoom::Fit),
+        Command::ZoomActualSize => window.view_mut().set_zoom(Zoom::Factor(1.0)),
+        Command::PanLeft => window.view_mut().pan_by(-pan_step.0, 0.0, size),
+        Command::PanRight => window.view_mut().pan_by(pan_step.0, 0.0, size),
+        Command::PanUp => window.view_mut().pan_by(0.0, -pan_step.1, size),
+        Command::PanDown => window.view_mut().pan_by(0.0, pan_step.1, size),
+        Command::NextImage => step_gallery(window, 1),
+        Command::PreviousImage => step_gallery(window, -1),
+        Command::CycleTransparency => window.view_mut().cycle_transparency(),
+        Command::ToggleRedChannel => window
+            .view_mut()
+            .toggle_channels(Channels::Single(Channel::Red)),
+        Command::ToggleGreenChannel => window
+            .view_mut()
+            .toggle_channels(Channels::Single(Channel::Green)),
+        Command::ToggleBlueChannel => window
+            .view_mut()
+            .toggle_channels(Channels::Single(Channel::Blue)),
+        Command::ToggleAlphaChannel => window
+            .view_mut()
+            .toggle_channels(Channels::Single(Channel::Alpha)),
+        Command::ToggleLuminance => window.view_mut().toggle_channels(Channels::Luminance),
+        Command::ToggleChannelTint => window.view_mut().toggle_tint(),
+        Command::CycleSwizzle => window.view_mut().cycle_swizzle(),
+        Command::ExposureUp => window.view_mut().adjust_exposure(1.0),
+        Command::ExposureDown => window.view_mut().adjust_exposure(-1.0),
+        Command::GammaUp => window.view_mut().adjust_gamma(1.0),
+        Command::GammaDown => window.view_mut().adjust_gamma(-1.0),
+        Command::CycleValueRange => window.view_mut().cycle_value_range(),
+        Command::CycleToneMapOperator => window.view_mut().cycle_tone_map_operator(),
+        Command::ResetToneMapping => window.view_mut().reset_tone_mapping(),
+        Command::CycleColormap => window.view_mut().cycle_colormap(),
+        Command::ToggleColourBar => window.view_mut().toggle_colour_bar(),
+        Command::ToggleHistogram => window.view_mut().toggle_histogram(),
+        Command::ToggleHistogramScale => window.view_mut().toggle_histogram_scale(),
+        Command::ToggleInfo => window.view_mut().toggle_info(),
+        Command::ToggleColourManagement => window.view_mut().toggle_colour_management(),
+        Command::RotateClockwise => window
+            .view_mut()
+            .transform_by(ViewTransform::rotated_clockwise),
+        Command::RotateCounterClockwise => window
+            .view_mut()
+            .transform_by(ViewTransform::rotated_counter_clockwise),
+        Command::FlipHorizontal => window
+            .view_mut()
+            .transform_by(ViewTransform::flipped_horizontally),
+        Command::FlipVertical => window
+            .view_mut()
+            .transform_by(ViewTransform::flipped_vertically),
+        Command::CycleFilter => window.view_mut().cycle_filter(),
+        Command::TogglePixelGrid => window.view_mut().toggle_pixel_grid(),
+        Command::CycleGrid => window.view_mut().cycle_grid(),
+        Command::ToggleRuler => window.view_mut().toggle_ruler(),
+    }
+
+    window.request_redraw();
+
+    Flow::Continue
+}
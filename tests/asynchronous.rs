@@ -0,0 +1,36 @@
+#![cfg(feature = "async")]
+
+use common::input;
+use futures::StreamExt;
+use miniview::config::ConfigBuilder;
+use miniview::{MiniView, ViewEvent};
+
+mod common;
+
+#[test]
+fn show_and_close_async() {
+    futures::executor::block_on(async {
+        let config = ConfigBuilder::from_path(input())
+            .set_lazy_window(true)
+            .build();
+
+        let view = MiniView::show_async(config)
+            .await
+            .expect("unable to create miniview");
+
+        let handle = view.handle();
+        assert!(handle.is_open());
+
+        let mut events = handle.events();
+        assert!(handle.request_close().is_ok());
+
+        let mut last = None;
+        while let Some(event) = events.next().await {
+            last = Some(event);
+        }
+        assert_eq!(last, Some(ViewEvent::Closed));
+
+        handle.closed().await;
+        assert!(view.closed().await.is_ok());
+    });
+}
@@ -0,0 +1,47 @@
+use miniview::{Channel, Channels, Swizzle};
+
+#[test]
+fn parse_channels() {
+    assert_eq!("all".parse::<Channels>().unwrap(), Channels::All);
+    assert_eq!(
+        "red".parse::<Channels>().unwrap(),
+        Channels::Single(Channel::Red)
+    );
+    assert_eq!(
+        "a".parse::<Channels>().unwrap(),
+        Channels::Single(Channel::Alpha)
+    );
+    assert_eq!(
+        "luminance".parse::<Channels>().unwrap(),
+        Channels::Luminance
+    );
+    assert!("cyan".parse::<Channels>().is_err());
+}
+
+#[test]
+fn channels_round_trip() {
+    for name in ["all", "red", "green", "blue", "alpha", "luminance"] {
+        assert_eq!(name.parse::<Channels>().unwrap().to_string(), name);
+    }
+}
+
+#[test]
+fn parse_swizzle() {
+    assert_eq!("rgba".parse::<Swizzle>().unwrap(), Swizzle::RGBA);
+    assert_eq!("bgra".parse::<Swizzle>().unwrap(), Swizzle::BGRA);
+
+    // the alpha channel is read as is, if it is left out
+    assert_eq!("bgr".parse::<Swizzle>().unwrap(), Swizzle::BGRA);
+    assert_eq!(
+        "argb".parse::<Swizzle>().unwrap(),
+        Swizzle::new([Channel::Alpha, Channel::Red, Channel::Green, Channel::Blue])
+    );
+    assert_eq!("argb".parse::<Swizzle>().unwrap().to_string(), "argb");
+}
+
+#[test]
+fn reject_invalid_swizzle() {
+    assert!("bg".parse::<Swizzle>().is_err());
+    assert!("rgbaa".parse::<Swizzle>().is_err());
+    assert!("xyz".parse::<Swizzle>().is_err());
+}
@@ -47,3 +47,214 @@ mod from_stdin {
             .success();
     }
 }
+
+mod ready {
+    use super::*;
+
+    #[test]
+    fn print_ready_line() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--ready", "--close-after", "10"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with("ready window_id="));
+    }
+}
+
+mod detach {
+    use super::*;
+    ide!();
+
+    /// Stop the background process with the given process ID, so it doesn't outlive the test
+    fn stop(pid: &str) {
+        // The process has already exited if the window could not be shown
+        let _ = std::process::Command::new("kill").arg(pid.trim()).status();
+    }
+
+    #[test]
+    fn print_pid_and_return() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--detach", "--print-pid"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(output.status.success());
+
+        let pid = String::from_utf8_lossy(&output.stdout);
+        assert!(pid.trim().parse::<u32>().is_ok());
+
+        stop(&pid);
+    }
+
+    #[test]
+    fn report_invalid_path() {
+        let _ = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args(["does-not-exist.png", "--detach"])
+            .assert()
+            .failure();
+    }
+
+    #[parameterized(option = { "--ready", "--commands-from-stdin" })]
+    fn reject_options_which_use_stdio(option: &str) {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--detach", option])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+    }
+
+    #[test]
+    fn report_invalid_stdin_bytes() {
+        let _ = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args(["--from-stdin-bytes", "--detach"])
+            .write_stdin("not an image")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn report_unrecognized_stdin() {
+        let _ = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args(["-", "--detach"])
+            .write_stdin(vec![0xff, 0x00, 0xfe, 0x01])
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn stdin_image_from_dash() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args(["-", "--detach", "--print-pid"])
+            .write_stdin(std::fs::read(input()).expect("unable to read test image"))
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(output.status.success());
+
+        stop(&String::from_utf8_lossy(&output.stdout));
+    }
+}
+
+mod info {
+    use super::*;
+
+    #[test]
+    fn print_histogram() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--info"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+
+        assert_eq!(stdout.lines().next(), Some("dimensions: 217x447"));
+        assert!(stdout.contains("\nluminance: mean "));
+
+        // a row for each of the 256 bins follows the header
+        let (_, bins) = stdout
+            .split_once("bin\tred\tgreen\tblue\talpha\tluminance\n")
+            .expect("histogram header not found");
+        let rows = bins.lines().collect::<Vec<_>>();
+
+        assert_eq!(rows.len(), 256);
+        assert!(rows[0].starts_with("0\t"));
+        assert!(rows[255].starts_with("255\t"));
+        assert_eq!(rows[255].split('\t').count(), 6);
+    }
+
+    #[test]
+    fn print_exif_metadata() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--info"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let (metadata, _) = stdout.split_once("\nred: ").unwrap();
+
+        assert!(metadata.lines().any(|line| line == "orientation: Normal"));
+        assert!(metadata
+            .lines()
+            .any(|line| line == "colour profile: sRGB IEC61966-2.1"));
+    }
+}
+
+mod save_as {
+    use super::*;
+
+    #[test]
+    fn write_transformed_image() {
+        let path =
+            std::env::temp_dir().join(format!("miniview-save-as-{}.png", std::process::id()));
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--rotate", "90", "--flip-vertical", "--save-as"])
+            .arg(&path)
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(output.status.success());
+
+        let expected = image::open(input()).unwrap().flipv().rotate90().to_rgb8();
+        let saved = image::open(&path).unwrap().to_rgb8();
+
+        assert_eq!(saved.dimensions(), (447, 217));
+        assert_eq!(saved, expected);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reject_invalid_rotation() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--rotate", "45", "--save-as", "unused.png"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn reject_invalid_filter() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--filter", "lanczos", "--save-as", "unused.png"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid filter 'lanczos'"));
+    }
+
+    #[test]
+    fn reject_invalid_grid() {
+        let output = Command::cargo_bin("miniview")
+            .expect("MiniView binary not found")
+            .args([input(), "--grid", "16x", "--save-as", "unused.png"])
+            .output()
+            .expect("unable to run miniview");
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid grid '16x'"));
+    }
+}
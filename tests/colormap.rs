@@ -0,0 +1,51 @@
+use miniview::{Colormap, ConfigBuilder};
+
+mod common;
+use common::input;
+
+const COLORMAPS: [&str; 7] = [
+    "greyscale",
+    "viridis",
+    "magma",
+    "inferno",
+    "turbo",
+    "coolwarm",
+    "rdbu",
+];
+
+#[test]
+fn parse_colormap() {
+    assert_eq!("viridis".parse::<Colormap>().unwrap(), Colormap::Viridis);
+    assert_eq!("rdbu".parse::<Colormap>().unwrap(), Colormap::RdBu);
+    assert_eq!(
+        "grayscale".parse::<Colormap>().unwrap(),
+        Colormap::Greyscale
+    );
+    assert!("jet".parse::<Colormap>().is_err());
+}
+
+#[test]
+fn colormap_round_trip() {
+    for name in COLORMAPS {
+        assert_eq!(name.parse::<Colormap>().unwrap().to_string(), name);
+    }
+}
+
+#[test]
+fn greyscale_by_default() {
+    let config = ConfigBuilder::from_path(input()).build();
+
+    assert_eq!(config.colormap(), Colormap::Greyscale);
+    assert!(!config.colour_bar());
+}
+
+#[test]
+fn colormap_and_colour_bar() {
+    let config = ConfigBuilder::from_path(input())
+        .colormap(Colormap::Inferno)
+        .show_colour_bar(true)
+        .build();
+
+    assert_eq!(config.colormap(), Colormap::Inferno);
+    assert!(config.colour_bar());
+}
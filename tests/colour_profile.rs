@@ -0,0 +1,176 @@
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage, Rgba, RgbaImage};
+use miniview::{ColourProfile, Source};
+use std::io::Cursor;
+
+mod common;
+use common::input;
+
+/// The colorants of the sRGB primaries and the D50 white point, relative to the D50 illuminant
+const RED: [f64; 3] = [0.4361, 0.2225, 0.0139];
+const GREEN: [f64; 3] = [0.3851, 0.7169, 0.0971];
+const BLUE: [f64; 3] = [0.1431, 0.0606, 0.7141];
+const WHITE: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// A version 2 display profile with the given primaries and a gamma of 2.2; the description is
+/// written as a version 4 `mluc` tag if `multilingual` is set
+fn profile(primaries: [[f64; 3]; 3], description: &str, multilingual: bool) -> Vec<u8> {
+    let xyz = |xyz: [f64; 3]| {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+
+        for value in xyz {
+            tag.extend_from_slice(&((value * 65536.0).round() as i32).to_be_bytes());
+        }
+
+        tag
+    };
+    let curve = b"curv\0\0\0\0\0\0\0\x01\x02\x33\0\0".to_vec();
+    let description = if multilingual {
+        let text = description
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        let mut tag = b"mluc\0\0\0\0\0\0\0\x01\0\0\0\x0cenUS".to_vec();
+        tag.extend_from_slice(&(text.len() as u32).to_be_bytes());
+        tag.extend_from_slice(&28u32.to_be_bytes());
+        tag.extend_from_slice(&text);
+        tag
+    } else {
+        let mut tag = b"desc\0\0\0\0".to_vec();
+        tag.extend_from_slice(&(description.len() as u32 + 1).to_be_bytes());
+        tag.extend_from_slice(description.as_bytes());
+        tag.push(0);
+        tag
+    };
+
+    let [red, green, blue] = primaries;
+    let tags: [(&[u8; 4], Vec<u8>); 8] = [
+        (b"desc", description),
+        (b"wtpt", xyz(WHITE)),
+        (b"rXYZ", xyz(red)),
+        (b"gXYZ", xyz(green)),
+        (b"bXYZ", xyz(blue)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let start = 128 + 4 + tags.len() * 12;
+
+    for (signature, tag) in tags {
+        table.extend_from_slice(signature);
+        table.extend_from_slice(&((start + data.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(&tag);
+        data.resize((data.len() + 3) / 4 * 4, 0);
+    }
+
+    let mut header = vec![0; 128];
+    header[0..4].copy_from_slice(&((start + data.len()) as u32).to_be_bytes());
+    header[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+    header[12..24].copy_from_slice(b"mntrRGB XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+
+    [header, table, data].concat()
+}
+
+/// A profile of which the red and green primaries are swapped, compared to sRGB
+fn swapped() -> ColourProfile {
+    ColourProfile::from_bytes(profile([GREEN, RED, BLUE], "Swapped", false)).unwrap()
+}
+
+/// A JPEG of a red pixel, with the given ICC profile in an APP2 segment
+fn jpeg_with_profile(profile: &[u8]) -> Vec<u8> {
+    let mut jpeg = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([255, 0, 0])))
+        .write_to(&mut Cursor::new(&mut jpeg), ImageOutputFormat::Jpeg(100))
+        .unwrap();
+
+    let mut segment = vec![0xff, 0xe2];
+    segment.extend_from_slice(&(profile.len() as u16 + 16).to_be_bytes());
+    segment.extend_from_slice(b"ICC_PROFILE\0\x01\x01");
+    segment.extend_from_slice(profile);
+
+    // directly after the start of image marker
+    jpeg.splice(2..2, segment);
+    jpeg
+}
+
+#[test]
+fn read_description() {
+    assert_eq!(swapped().description(), Some("Swapped"));
+
+    let multilingual = profile([RED, GREEN, BLUE], "Multilingual", true);
+    let profile = ColourProfile::from_bytes(multilingual.clone()).unwrap();
+
+    assert_eq!(profile.description(), Some("Multilingual"));
+    assert_eq!(profile.as_bytes(), multilingual.as_slice());
+}
+
+#[test]
+fn reject_invalid_profiles() {
+    assert!(ColourProfile::from_bytes(b"not a profile".to_vec()).is_none());
+    assert!(ColourProfile::from_bytes(vec![0; 256]).is_none());
+    assert!(ColourProfile::open(input()).is_err());
+}
+
+#[test]
+fn convert_to_srgb() {
+    let image = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 128]));
+    let converted = swapped().convert(&image, None).unwrap();
+
+    let [red, green, blue, alpha] = converted.get_pixel(0, 0).0;
+    assert!(
+        red < 8 && green > 247 && blue < 8,
+        "{:?}",
+        converted.get_pixel(0, 0)
+    );
+    assert_eq!(alpha, 128);
+
+    // converted to the colours of an output profile, from a profile with the sRGB primaries
+    let standard =
+        ColourProfile::from_bytes(profile([RED, GREEN, BLUE], "Standard", false)).unwrap();
+    let converted = standard.convert(&converted, Some(&swapped())).unwrap();
+    let [red, green, ..] = converted.get_pixel(0, 0).0;
+    assert!(red > 247 && green < 8, "{:?}", converted.get_pixel(0, 0));
+}
+
+#[test]
+fn read_embedded_profile() {
+    let source = Source::Bytes(jpeg_with_profile(swapped().as_bytes()));
+    let metadata = source.metadata().unwrap();
+
+    assert_eq!(metadata.colour_profile, Some(swapped()));
+    assert!(!metadata.has_exif());
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::new(1, 1))
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .unwrap();
+
+    assert!(Source::Bytes(png)
+        .metadata()
+        .unwrap()
+        .colour_profile
+        .is_none());
+}
+
+#[test]
+fn srgb_photo_is_shown_as_is() {
+    let source = Source::ByPath(input().into());
+    let profile = source.metadata().unwrap().colour_profile.unwrap();
+
+    assert_eq!(profile.description(), Some("sRGB IEC61966-2.1"));
+
+    let image = source.open().unwrap().to_rgba8();
+    let converted = profile.convert(&image, None).unwrap();
+    let difference = image
+        .pixels()
+        .zip(converted.pixels())
+        .flat_map(|(before, after)| before.0.into_iter().zip(after.0))
+        .map(|(before, after)| before.abs_diff(after))
+        .max();
+
+    assert!(difference <= Some(2), "{:?}", difference);
+}
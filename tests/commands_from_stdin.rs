@@ -0,0 +1,42 @@
+use assert_cmd::Command;
+use common::input;
+
+mod common;
+
+#[test]
+fn run_script() {
+    let screenshot =
+        std::env::temp_dir().join(format!("miniview-commands-{}.png", std::process::id()));
+
+    let script = format!(
+        "# a scripted demo\n\
+        zoom 200\n\
+        title scripted\n\
+        wait 50\n\
+        load {}\n\
+        screenshot {}\n\
+        close\n",
+        input(),
+        screenshot.display()
+    );
+
+    let _ = Command::cargo_bin("miniview")
+        .expect("MiniView binary not found")
+        .args([input(), "--commands-from-stdin"])
+        .write_stdin(script)
+        .assert()
+        .success();
+
+    assert!(image::open(&screenshot).is_ok());
+    std::fs::remove_file(screenshot).unwrap();
+}
+
+#[test]
+fn reject_invalid_command_line() {
+    let _ = Command::cargo_bin("miniview")
+        .expect("MiniView binary not found")
+        .args(["--from-stdin-path", "--commands-from-stdin"])
+        .write_stdin("close\n")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,91 @@
+use common::input;
+use miniview::config::{ConfigBuilder, DropBehavior};
+use miniview::MiniView;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod common;
+
+#[test]
+fn close_and_join_on_drop() {
+    let config = ConfigBuilder::from_path(input())
+        .set_lazy_window(true)
+        .drop_behavior(DropBehavior::CloseAndJoin)
+        .build();
+
+    let mut controls = MiniView::show(config).expect("unable to create miniview");
+    let handle = controls.handle();
+
+    while !handle.is_open() {
+        assert!(!controls.try_wait().expect("unable to show miniview"));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    drop(controls);
+
+    assert!(!handle.is_open());
+}
+
+#[test]
+fn detach_on_drop() {
+    let config = ConfigBuilder::from_path(input())
+        .set_lazy_window(true)
+        .drop_behavior(DropBehavior::Detach)
+        .build();
+
+    let mut controls = MiniView::show(config).expect("unable to create miniview");
+    let handle = controls.handle();
+
+    while !handle.is_open() {
+        assert!(!controls.try_wait().expect("unable to show miniview"));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    drop(controls);
+
+    // the window is still shown by the detached thread, until it is closed
+    assert!(handle.is_open());
+
+    handle.request_close().expect("unable to close miniview");
+
+    let started = Instant::now();
+    while handle.is_open() {
+        assert!(started.elapsed() < Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn wait_for_user_on_drop() {
+    let config = ConfigBuilder::from_path(input())
+        .set_lazy_window(true)
+        .drop_behavior(DropBehavior::WaitForUser)
+        .build();
+
+    let mut controls = MiniView::show(config).expect("unable to create miniview");
+    let handle = controls.handle();
+
+    while !handle.is_open() {
+        assert!(!controls.try_wait().expect("unable to show miniview"));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // the window is closed as if by the user, some time after the controls are dropped
+    let closed = Arc::new(AtomicBool::new(false));
+    let user = {
+        let (handle, closed) = (handle.clone(), Arc::clone(&closed));
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            closed.store(true, Ordering::SeqCst);
+            handle.request_close()
+        })
+    };
+
+    drop(controls);
+
+    assert!(closed.load(Ordering::SeqCst));
+    assert!(!handle.is_open());
+
+    user.join().unwrap().expect("unable to close miniview");
+}
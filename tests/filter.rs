@@ -0,0 +1,28 @@
+use miniview::errors::SettingsError;
+use miniview::{ConfigBuilder, Filter};
+
+mod common;
+use common::input;
+
+#[test]
+fn parse_filter() {
+    assert_eq!("auto".parse::<Filter>().unwrap(), Filter::Auto);
+    assert_eq!("nearest".parse::<Filter>().unwrap(), Filter::Nearest);
+    assert_eq!("bilinear".parse::<Filter>().unwrap(), Filter::Bilinear);
+    assert_eq!("area".parse::<Filter>().unwrap(), Filter::Area);
+    assert!(matches!(
+        "lanczos".parse::<Filter>(),
+        Err(SettingsError::InvalidFilter(value)) if value == "lanczos"
+    ));
+}
+
+#[test]
+fn auto_by_default() {
+    let config = ConfigBuilder::from_path(input()).build();
+    assert_eq!(config.filter(), Filter::Auto);
+
+    let config = ConfigBuilder::from_path(input())
+        .filter(Filter::Nearest)
+        .build();
+    assert_eq!(config.filter(), Filter::Nearest);
+}
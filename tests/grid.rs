@@ -0,0 +1,53 @@
+use miniview::errors::SettingsError;
+use miniview::{ConfigBuilder, Grid};
+
+mod common;
+use common::input;
+
+#[test]
+fn parse_grid() {
+    assert_eq!("16".parse::<Grid>().unwrap(), Grid::square(16));
+    assert_eq!("64x32".parse::<Grid>().unwrap(), Grid::new(64, 32));
+
+    let grid = "64x32".parse::<Grid>().unwrap();
+    assert_eq!((grid.width(), grid.height()), (64, 32));
+
+    for invalid in ["0", "16x", "x16", "-8", "8x0", "large"] {
+        assert!(matches!(
+            invalid.parse::<Grid>(),
+            Err(SettingsError::InvalidGrid(value)) if value == invalid
+        ));
+    }
+}
+
+#[test]
+fn round_trip() {
+    for grid in [Grid::square(8), Grid::new(64, 32)] {
+        assert_eq!(grid.to_string().parse::<Grid>().unwrap(), grid);
+    }
+
+    assert_eq!(Grid::square(16).to_string(), "16");
+    assert_eq!(Grid::new(64, 32).to_string(), "64x32");
+}
+
+#[test]
+fn pixel_grid_without_blocks_by_default() {
+    let config = ConfigBuilder::from_path(input()).build();
+
+    assert!(config.pixel_grid());
+    assert_eq!(config.pixel_grid_threshold(), 8.0);
+    assert_eq!(config.grid(), None);
+    assert!(!config.ruler());
+
+    let config = ConfigBuilder::from_path(input())
+        .show_pixel_grid(false)
+        .pixel_grid_threshold(4.0)
+        .grid(Grid::square(16))
+        .show_ruler(true)
+        .build();
+
+    assert!(!config.pixel_grid());
+    assert_eq!(config.pixel_grid_threshold(), 4.0);
+    assert_eq!(config.grid(), Some(Grid::square(16)));
+    assert!(config.ruler());
+}
@@ -0,0 +1,59 @@
+use common::input;
+use miniview::config::ConfigBuilder;
+use miniview::MiniView;
+use std::time::Duration;
+
+mod common;
+
+#[test]
+fn control_from_worker_threads() {
+    let config = ConfigBuilder::from_path(input())
+        .set_lazy_window(true)
+        .build();
+
+    let mut controls = MiniView::show(config).expect("unable to create miniview");
+    let image = image::open(input()).expect("unable to open test image");
+
+    let workers = (0..4)
+        .map(|i| {
+            let handle = controls.handle();
+            let image = image.clone();
+
+            std::thread::spawn(move || {
+                assert!(handle.set_title(format!("worker {}", i)).is_ok());
+                assert!(handle.set_image(image.fliph()).is_ok());
+                assert!(handle.set_fullscreen(i % 2 == 0).is_ok());
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for worker in workers {
+        worker.join().expect("worker panicked");
+    }
+
+    let handle = controls.handle();
+    assert!(handle.resize(320, 240).is_ok());
+    assert!(handle.move_to(10, 10).is_ok());
+    assert!(handle.set_visible(false).is_ok());
+    assert!(handle.set_visible(true).is_ok());
+    assert!(handle.focus().is_ok());
+    assert!(handle.toggle_fullscreen().is_ok());
+
+    assert!(!controls.wait_timeout(Duration::from_millis(100)).unwrap());
+    assert!(handle.window_size().is_some());
+    assert!(handle.scale_factor().is_some_and(|factor| factor > 0.0));
+
+    assert!(!controls.try_wait().unwrap());
+    assert!(controls.handle().is_open());
+
+    let handle = controls.handle();
+    std::thread::spawn(move || handle.request_close())
+        .join()
+        .expect("worker panicked")
+        .expect("unable to request close");
+
+    assert!(controls.wait_timeout(Duration::from_secs(5)).unwrap());
+    assert!(!controls.handle().is_open());
+    assert!(controls.handle().window_size().is_none());
+    assert!(controls.wait_for_exit().is_ok());
+}
@@ -0,0 +1,91 @@
+use image::{DynamicImage, GrayImage, ImageBuffer, ImageOutputFormat, Luma, Rgb};
+use miniview::histogram::BINS;
+use miniview::{Channel, Histogram, HistogramScale, Source};
+
+fn grey(values: &[u8]) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_raw(values.len() as u32, 1, values.to_vec()).unwrap())
+}
+
+#[test]
+fn bins_of_8_bit_values() {
+    let histogram = Histogram::new(&grey(&[0, 128, 255, 255]));
+    let red = histogram.channel(Channel::Red);
+
+    assert_eq!(red.bins().len(), BINS);
+    assert_eq!(red.count(), 4);
+    assert_eq!(red.bins()[0], 1);
+    assert_eq!(red.bins()[128], 1);
+    assert_eq!(red.bins()[255], 2);
+    assert_eq!(red.clipped_low(), 1);
+    assert_eq!(red.clipped_high(), 2);
+
+    // greyscale images have equal colour channels, and an opaque alpha channel
+    assert_eq!(histogram.channel(Channel::Blue), red);
+    assert_eq!(histogram.luminance().bins()[128], 1);
+    assert_eq!(histogram.channel(Channel::Alpha).bins()[255], 4);
+}
+
+#[test]
+fn mean_of_normalized_values() {
+    let histogram = Histogram::new(&grey(&[0, 255]));
+
+    assert_eq!(histogram.channel(Channel::Green).mean(), Some(0.5));
+
+    let image = DynamicImage::ImageLuma16(
+        ImageBuffer::<Luma<u16>, _>::from_raw(2, 1, vec![0, u16::MAX]).unwrap(),
+    );
+    let histogram = Histogram::new(&image);
+
+    assert_eq!(histogram.channel(Channel::Red).mean(), Some(0.5));
+    assert_eq!(histogram.channel(Channel::Red).bins()[255], 1);
+}
+
+#[test]
+fn values_outside_of_the_displayed_range_are_clipped() {
+    let image = DynamicImage::ImageRgb32F(
+        ImageBuffer::<Rgb<f32>, _>::from_raw(
+            3,
+            1,
+            vec![-0.5, 0.5, 4.0, 0.25, f32::NAN, 1.0, 2.0, 0.5, 0.0],
+        )
+        .unwrap(),
+    );
+    let histogram = Histogram::new(&image);
+
+    let red = histogram.channel(Channel::Red);
+    assert_eq!(red.clipped_low(), 1);
+    assert_eq!(red.clipped_high(), 1);
+    assert_eq!(red.bins()[0], 1);
+    assert_eq!(red.bins()[255], 1);
+
+    // values which are not a number are left out
+    let green = histogram.channel(Channel::Green);
+    assert_eq!(green.count(), 2);
+    assert_eq!(green.mean(), Some(0.5));
+}
+
+#[test]
+fn histogram_of_source() {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    grey(&[10, 20, 20])
+        .write_to(&mut bytes, ImageOutputFormat::Png)
+        .unwrap();
+
+    let histogram = Source::Bytes(bytes.into_inner()).histogram().unwrap();
+
+    assert_eq!(histogram.luminance().bins()[10], 1);
+    assert_eq!(histogram.luminance().bins()[20], 2);
+}
+
+#[test]
+fn parse_histogram_scale() {
+    assert_eq!(
+        "linear".parse::<HistogramScale>().unwrap(),
+        HistogramScale::Linear
+    );
+    assert_eq!(
+        "log".parse::<HistogramScale>().unwrap(),
+        HistogramScale::Log
+    );
+    assert!("sqrt".parse::<HistogramScale>().is_err());
+}
@@ -0,0 +1,28 @@
+use common::input;
+use miniview::config::ConfigBuilder;
+use miniview::MiniView;
+use std::time::Duration;
+
+mod common;
+
+// Only a single window can be created per process, so each integration test which creates a
+// window lives in its own test binary.
+#[test]
+fn run_on_current_thread_and_close_from_worker() {
+    let config = ConfigBuilder::from_path(input())
+        .set_lazy_window(true)
+        .build();
+
+    let result = MiniView::run_with_handle(config, |handle| {
+        std::thread::spawn(move || {
+            while !handle.is_open() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            std::thread::sleep(Duration::from_millis(100));
+            assert!(handle.request_close().is_ok());
+        });
+    });
+
+    assert!(result.is_ok());
+}
@@ -0,0 +1,122 @@
+use image::{DynamicImage, GenericImageView, ImageOutputFormat, Rgb, RgbImage};
+use miniview::metadata::{Metadata, Orientation};
+use miniview::Source;
+use std::io::Cursor;
+
+mod common;
+use common::input;
+
+/// A 2x1 image with a red and a blue pixel
+fn image() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(2, 1, |x, _| match x {
+        0 => Rgb([255, 0, 0]),
+        _ => Rgb([0, 0, 255]),
+    }))
+}
+
+fn red(image: &DynamicImage, x: u32, y: u32) -> bool {
+    image.get_pixel(x, y)[0] > 128
+}
+
+/// A JPEG of `image()` with an EXIF segment which only records the given orientation
+fn jpeg_with_orientation(orientation: u16) -> Vec<u8> {
+    let mut jpeg = Vec::new();
+    image()
+        .write_to(&mut Cursor::new(&mut jpeg), ImageOutputFormat::Jpeg(100))
+        .unwrap();
+
+    // a big endian TIFF header, followed by an IFD with a single SHORT entry
+    let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+    exif.extend_from_slice(&orientation.to_be_bytes());
+    exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+    let mut segment = vec![0xff, 0xe1];
+    segment.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(&exif);
+
+    // directly after the start of image marker
+    jpeg.splice(2..2, segment);
+    jpeg
+}
+
+#[test]
+fn orientation_from_exif() {
+    assert_eq!(Orientation::from_exif(1), Some(Orientation::Normal));
+    assert_eq!(Orientation::from_exif(6), Some(Orientation::Rotate90));
+    assert_eq!(Orientation::from_exif(8), Some(Orientation::Rotate270));
+    assert_eq!(Orientation::from_exif(0), None);
+    assert_eq!(Orientation::from_exif(9), None);
+}
+
+#[test]
+fn apply_orientation() {
+    let flipped = Orientation::FlipHorizontal.apply(image());
+    assert_eq!(flipped.dimensions(), (2, 1));
+    assert!(!red(&flipped, 0, 0) && red(&flipped, 1, 0));
+
+    let rotated = Orientation::Rotate90.apply(image());
+    assert_eq!(rotated.dimensions(), (1, 2));
+    assert!(red(&rotated, 0, 0));
+
+    let rotated = Orientation::Rotate270.apply(image());
+    assert_eq!(rotated.dimensions(), (1, 2));
+    assert!(red(&rotated, 0, 1));
+
+    // mirrored along the top-left to bottom-right diagonal
+    let transposed = Orientation::Transpose.apply(image());
+    assert_eq!(transposed.dimensions(), (1, 2));
+    assert!(red(&transposed, 0, 0));
+
+    // mirrored along the top-right to bottom-left diagonal
+    let transversed = Orientation::Transverse.apply(image());
+    assert_eq!(transversed.dimensions(), (1, 2));
+    assert!(red(&transversed, 0, 1));
+
+    let normal = Orientation::Normal.apply(image());
+    assert!(red(&normal, 0, 0));
+}
+
+#[test]
+fn open_applies_the_exif_orientation() {
+    let source = Source::Bytes(jpeg_with_orientation(6));
+
+    assert_eq!(
+        source.metadata().unwrap().orientation,
+        Some(Orientation::Rotate90)
+    );
+    assert_eq!(source.open().unwrap().dimensions(), (1, 2));
+    assert_eq!(source.open_as_stored().unwrap().dimensions(), (2, 1));
+}
+
+#[test]
+fn open_with_metadata() {
+    let source = Source::Bytes(jpeg_with_orientation(6));
+
+    let (image, metadata) = source.open_with_metadata(true).unwrap();
+    assert_eq!(image.dimensions(), (1, 2));
+    assert_eq!(metadata.orientation, Some(Orientation::Rotate90));
+
+    let (image, metadata) = source.open_with_metadata(false).unwrap();
+    assert_eq!(image.dimensions(), (2, 1));
+    assert_eq!(metadata.orientation, Some(Orientation::Rotate90));
+}
+
+#[test]
+fn images_without_exif_have_no_metadata() {
+    let mut png = Vec::new();
+    image()
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .unwrap();
+
+    assert!(Metadata::from_bytes(&png).is_empty());
+    assert!(Source::Bytes(png).metadata().unwrap().is_empty());
+    assert!(Metadata::from_bytes(b"not an image").is_empty());
+}
+
+#[test]
+fn metadata_of_photo() {
+    let metadata = Source::ByPath(input().into()).metadata().unwrap();
+
+    assert!(!metadata.is_empty());
+    assert_eq!(metadata.orientation, Some(Orientation::Normal));
+}
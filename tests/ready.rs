@@ -0,0 +1,35 @@
+use common::input;
+use miniview::config::ConfigBuilder;
+use miniview::MiniView;
+use std::sync::mpsc;
+use std::time::Duration;
+
+mod common;
+
+#[test]
+fn ready_after_first_frame() {
+    let (sender, receiver) = mpsc::channel();
+    let sender = std::sync::Mutex::new(sender);
+
+    let config = ConfigBuilder::from_path(input())
+        .on_ready(move |ready| {
+            let _ = sender.lock().unwrap().send(ready.clone());
+        })
+        .build();
+
+    let controls = MiniView::show(config).expect("unable to create miniview");
+
+    let ready = controls
+        .handle()
+        .wait_ready(Duration::from_secs(10))
+        .expect("window did not become ready");
+    assert!(ready.size.0 > 0 && ready.size.1 > 0);
+
+    let notified = receiver
+        .recv_timeout(Duration::from_secs(1))
+        .expect("callback was not called");
+    assert_eq!(notified, ready);
+    assert_eq!(controls.handle().ready(), Some(ready));
+
+    controls.close().expect("unable to close window");
+}
@@ -0,0 +1,148 @@
+#![cfg(unix)]
+
+use miniview::errors::{MiniViewError, RemoteError};
+use miniview::remote::{self, Request};
+use std::path::PathBuf;
+
+#[test]
+fn parse_line_requests() {
+    assert_eq!(
+        Request::from_line("load image.png").unwrap(),
+        Request::Load {
+            path: PathBuf::from("image.png")
+        }
+    );
+    assert_eq!(
+        Request::from_line("zoom 200%").unwrap(),
+        Request::Zoom {
+            percent: Some(200.0)
+        }
+    );
+    assert_eq!(
+        Request::from_line("zoom fit").unwrap(),
+        Request::Zoom { percent: None }
+    );
+    assert_eq!(
+        Request::from_line("pan 100 50.5").unwrap(),
+        Request::Pan { x: 100.0, y: 50.5 }
+    );
+    assert_eq!(
+        Request::from_line("title  my window ").unwrap(),
+        Request::Title {
+            title: "my window".to_string()
+        }
+    );
+    assert_eq!(
+        Request::from_line("save rotated.png").unwrap(),
+        Request::Save {
+            path: PathBuf::from("rotated.png")
+        }
+    );
+    assert_eq!(Request::from_line("close").unwrap(), Request::Close);
+}
+
+#[test]
+fn parse_json_requests() {
+    assert_eq!(
+        Request::from_line(r#"{"command": "zoom", "percent": 50}"#).unwrap(),
+        Request::Zoom {
+            percent: Some(50.0)
+        }
+    );
+    assert_eq!(
+        Request::from_line(r#"{"command": "state"}"#).unwrap(),
+        Request::State
+    );
+    assert!(Request::from_line(r#"{"command": "title", "title": "a", "font": "b"}"#).is_err());
+}
+
+#[test]
+fn reject_invalid_requests() {
+    assert!(Request::from_line("rotate 90").is_err());
+    assert!(Request::from_line("load").is_err());
+    assert!(Request::from_line("pan 100").is_err());
+    assert!(Request::from_line("save").is_err());
+    assert!(Request::from_line("zoom big").is_err());
+}
+
+#[test]
+fn resolve_relative_paths() {
+    let request = Request::Load {
+        path: PathBuf::from("image.png"),
+    };
+
+    assert_eq!(
+        request.with_base_directory("/tmp"),
+        Request::Load {
+            path: PathBuf::from("/tmp/image.png")
+        }
+    );
+}
+
+#[test]
+fn send_without_listener() {
+    let path = std::env::temp_dir().join("miniview-test-no-listener.sock");
+    let result = remote::send(&path, &Request::State);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Remote(RemoteError::UnableToConnect(_)))
+    ));
+}
+
+#[test]
+fn runtime_socket_per_key() {
+    use miniview::Source;
+    use std::os::unix::fs::PermissionsExt;
+
+    let runtime_dir = std::env::temp_dir().join(format!("miniview-test-{}", std::process::id()));
+    std::env::set_var("XDG_RUNTIME_DIR", &runtime_dir);
+
+    let path = remote::runtime_socket_path("my key/..").unwrap();
+    assert_eq!(path, runtime_dir.join("miniview").join("my_key_...sock"));
+
+    let mode = std::fs::metadata(path.parent().unwrap())
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    let source = Source::ByPath(PathBuf::from("image.png"));
+    assert!(!remote::hand_off(&path, &source).unwrap());
+
+    std::fs::remove_dir_all(runtime_dir).unwrap();
+}
+
+#[test]
+fn bind_claims_the_path() {
+    let path = std::env::temp_dir().join(format!("miniview-test-bind-{}.sock", std::process::id()));
+
+    let socket = remote::bind(&path).unwrap();
+
+    // Another instance can't claim the path, even before requests are applied
+    assert!(matches!(
+        remote::bind(&path),
+        Err(MiniViewError::Remote(RemoteError::AddressInUse(_)))
+    ));
+
+    drop(socket);
+    assert!(!path.exists());
+}
+
+#[test]
+fn refuse_to_hand_off_a_gallery() {
+    use miniview::Source;
+
+    let path = std::env::temp_dir().join(format!(
+        "miniview-test-hand-off-gallery-{}.sock",
+        std::process::id()
+    ));
+    let _socket = remote::bind(&path).unwrap();
+
+    let source = Source::ByPaths(vec![PathBuf::from("a.png"), PathBuf::from("b.png")]);
+
+    assert!(matches!(
+        remote::hand_off(&path, &source),
+        Err(MiniViewError::Remote(RemoteError::UnableToHandOffGallery))
+    ));
+}
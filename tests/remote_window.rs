@@ -0,0 +1,39 @@
+#![cfg(unix)]
+
+use common::input;
+use miniview::config::ConfigBuilder;
+use miniview::remote::{self, Request};
+use miniview::MiniView;
+
+mod common;
+
+#[test]
+fn control_over_socket() {
+    let config = ConfigBuilder::from_path(input())
+        .set_lazy_window(true)
+        .build();
+
+    let controls = MiniView::show(config).expect("unable to create miniview");
+
+    let path = std::env::temp_dir().join(format!("miniview-test-{}.sock", std::process::id()));
+    let listener = remote::listen(&path, controls.handle()).expect("unable to listen");
+
+    let response = remote::send(&path, &Request::from_line("zoom 200").unwrap()).unwrap();
+    assert!(response.ok);
+    assert_eq!(response.state.unwrap().zoom_percent, 200.0);
+
+    let response = remote::send(&path, &Request::from_line("title remote").unwrap()).unwrap();
+    assert_eq!(response.state.unwrap().title, "remote");
+
+    let response = remote::send(&path, &Request::from_line("load missing.png").unwrap()).unwrap();
+    assert!(!response.ok);
+    assert!(response.error.is_some());
+
+    let response = remote::send(&path, &Request::Close).unwrap();
+    assert!(response.ok);
+
+    controls.wait_for_exit().expect("unable to wait for exit");
+
+    drop(listener);
+    assert!(!path.exists());
+}
@@ -0,0 +1,188 @@
+#![cfg(feature = "serde")]
+
+use miniview::config::{Backend, Config, DropBehavior};
+use miniview::{
+    Channel, Channels, Colormap, ColourProfile, ConfigBuilder, Filter, Grid, HistogramScale,
+    Rotation, Source, Swizzle, ToneMapOperator, Transparency, ValueRange, Zoom,
+};
+use std::path::PathBuf;
+
+mod common;
+use common::input;
+
+/// The sRGB profile embedded in the photo
+fn profile() -> ColourProfile {
+    Source::ByPath(input().into())
+        .metadata()
+        .unwrap()
+        .colour_profile
+        .unwrap()
+}
+
+fn config() -> Config {
+    ConfigBuilder::from_path(input())
+        .set_fullscreen(true)
+        .window_name("fixture")
+        .drop_behavior(DropBehavior::CloseAndJoin)
+        .background(image::Rgba([16, 32, 48, 255]))
+        .transparency(Transparency::Solid(image::Rgba([255, 255, 255, 255])))
+        .channels(Channels::Single(Channel::Blue))
+        .swizzle(Swizzle::BGRA)
+        .value_range(ValueRange::Percentile(1.0, 99.0))
+        .exposure(2.0)
+        .tone_map_operator(ToneMapOperator::Reinhard)
+        .colormap(Colormap::RdBu)
+        .show_colour_bar(true)
+        .show_histogram(true)
+        .histogram_scale(HistogramScale::Log)
+        .show_info(true)
+        .apply_orientation(false)
+        .colour_management(false)
+        .output_profile(profile())
+        .rotation(Rotation::Rotate90)
+        .flip_vertical(true)
+        .filter(Filter::Bilinear)
+        .pixel_grid_threshold(4.0)
+        .grid(Grid::new(64, 32))
+        .show_ruler(true)
+        .initial_zoom(Zoom::Factor(2.0))
+        .backend(Backend::Pixels)
+        .build()
+}
+
+#[test]
+fn round_trip_config() {
+    let json = serde_json::to_string(&config()).unwrap();
+    let config: Config = serde_json::from_str(&json).unwrap();
+
+    assert!(config.fullscreen());
+    assert_eq!(config.window_name(), "fixture");
+    assert_eq!(config.drop_behavior(), DropBehavior::CloseAndJoin);
+    assert_eq!(config.background().0, [16, 32, 48, 255]);
+    assert_eq!(
+        config.transparency(),
+        Transparency::Solid(image::Rgba([255, 255, 255, 255]))
+    );
+    assert_eq!(config.channels(), Channels::Single(Channel::Blue));
+    assert_eq!(config.swizzle(), Swizzle::BGRA);
+    assert_eq!(
+        config.tone_mapping().range,
+        ValueRange::Percentile(1.0, 99.0)
+    );
+    assert_eq!(config.tone_mapping().exposure, 2.0);
+    assert_eq!(config.tone_mapping().operator, ToneMapOperator::Reinhard);
+    assert_eq!(config.colormap(), Colormap::RdBu);
+    assert!(config.colour_bar());
+    assert!(config.histogram());
+    assert_eq!(config.histogram_scale(), HistogramScale::Log);
+    assert!(config.info());
+    assert!(!config.apply_orientation());
+    assert!(!config.colour_management());
+    assert_eq!(config.output_profile(), Some(&profile()));
+    assert_eq!(config.transform().rotation, Rotation::Rotate90);
+    assert!(!config.transform().flip_horizontal);
+    assert!(config.transform().flip_vertical);
+    assert_eq!(config.filter(), Filter::Bilinear);
+    assert_eq!(config.pixel_grid_threshold(), 4.0);
+    assert_eq!(config.grid(), Some(Grid::new(64, 32)));
+    assert!(config.ruler());
+    assert_eq!(config.initial_zoom(), Zoom::Factor(2.0));
+    assert_eq!(config.backend(), Some(Backend::Pixels));
+    assert!(matches!(config.source(), Source::ByPath(path) if path == &PathBuf::from(input())));
+}
+
+#[test]
+fn serialized_form() {
+    let value = serde_json::to_value(config()).unwrap();
+
+    assert_eq!(value["version"], 1);
+    assert_eq!(value["background"], "#102030ff");
+    assert_eq!(value["initial_zoom"], serde_json::json!({ "factor": 2.0 }));
+    assert_eq!(
+        value["transparency"],
+        serde_json::json!({ "solid": "#ffffffff" })
+    );
+    assert_eq!(value["channels"], "blue");
+    assert_eq!(value["swizzle"], "bgra");
+    assert_eq!(
+        value["tone_mapping"],
+        serde_json::json!({
+            "range": "percentile:1,99",
+            "exposure": 2.0,
+            "gamma": 1.0,
+            "operator": "reinhard"
+        })
+    );
+    assert_eq!(value["colormap"], "rdbu");
+    assert_eq!(value["colour_bar"], true);
+    assert_eq!(value["histogram_scale"], "log");
+    assert_eq!(value["info"], true);
+    assert_eq!(value["apply_orientation"], false);
+    assert_eq!(value["colour_management"], false);
+    assert!(value["output_profile"].is_string());
+    assert_eq!(
+        value["transform"],
+        serde_json::json!({
+            "rotation": 90,
+            "flip_horizontal": false,
+            "flip_vertical": true
+        })
+    );
+    assert_eq!(value["filter"], "bilinear");
+    assert_eq!(value["grid"], "64x32");
+    assert_eq!(value["drop_behavior"], "close_and_join");
+    assert_eq!(value["backend"], "pixels");
+    assert_eq!(value["source"], serde_json::json!({ "by_path": input() }));
+}
+
+#[test]
+fn key_bindings() {
+    let mut value = serde_json::to_value(config()).unwrap();
+    value["keys"] = serde_json::json!({ "space": "quit", "q": "none" });
+
+    let config: Config = serde_json::from_value(value.clone()).unwrap();
+    let value = serde_json::to_value(config).unwrap();
+
+    assert_eq!(
+        value["keys"],
+        serde_json::json!({ "q": "none", "space": "quit" })
+    );
+}
+
+#[test]
+fn bytes_as_base64() {
+    let source = Source::Bytes(vec![0, 1, 2, 255]);
+    let json = serde_json::to_string(&source).unwrap();
+
+    assert_eq!(json, r#"{"bytes":"AAEC/w=="}"#);
+
+    let source: Source = serde_json::from_str(&json).unwrap();
+    assert!(matches!(source, Source::Bytes(bytes) if bytes == vec![0, 1, 2, 255]));
+}
+
+#[test]
+fn reject_unknown_fields() {
+    let mut value = serde_json::to_value(config()).unwrap();
+    value["full_screen"] = serde_json::json!(true);
+
+    assert!(serde_json::from_value::<Config>(value).is_err());
+}
+
+#[test]
+fn reject_unsupported_version() {
+    let mut value = serde_json::to_value(config()).unwrap();
+    value["version"] = serde_json::json!(2);
+
+    let err = serde_json::from_value::<Config>(value).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unsupported configuration version"));
+}
+
+#[test]
+fn reject_missing_version() {
+    let mut value = serde_json::to_value(config()).unwrap();
+    value.as_object_mut().unwrap().remove("version");
+
+    assert!(serde_json::from_value::<Config>(value).is_err());
+}
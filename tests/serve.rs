@@ -0,0 +1,235 @@
+#![cfg(unix)]
+
+use common::input;
+use miniview::errors::{MiniViewError, RemoteError};
+use miniview::serve::{self, Address, Frame, FrameImage, PixelFormat};
+use std::io::Cursor;
+
+mod common;
+
+fn raw_frame(format: PixelFormat, data: Vec<u8>) -> Frame {
+    Frame {
+        window: "raw".to_string(),
+        image: FrameImage::Raw {
+            width: 2,
+            height: 1,
+            format,
+            data,
+        },
+    }
+}
+
+#[test]
+fn frames_round_trip() {
+    let frames = vec![
+        Frame {
+            window: "encoded ✓".to_string(),
+            image: FrameImage::Encoded(std::fs::read(input()).unwrap()),
+        },
+        raw_frame(PixelFormat::Bgr8, vec![1, 2, 3, 4, 5, 6]),
+    ];
+
+    let mut bytes = Vec::new();
+    for frame in &frames {
+        frame.write_to(&mut bytes).unwrap();
+    }
+
+    let mut reader = Cursor::new(bytes);
+    assert_eq!(
+        Frame::read_from(&mut reader).unwrap().as_ref(),
+        Some(&frames[0])
+    );
+    assert_eq!(
+        Frame::read_from(&mut reader).unwrap().as_ref(),
+        Some(&frames[1])
+    );
+    assert_eq!(Frame::read_from(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn decode_raw_frames() {
+    let image = raw_frame(PixelFormat::Bgr8, vec![1, 2, 3, 4, 5, 6])
+        .decode()
+        .unwrap();
+    assert_eq!(image.to_rgb8().into_raw(), vec![3, 2, 1, 6, 5, 4]);
+
+    let image = raw_frame(PixelFormat::Luma8, vec![7, 8]).decode().unwrap();
+    assert_eq!(image.to_luma8().into_raw(), vec![7, 8]);
+
+    assert!(raw_frame(PixelFormat::Rgba8, vec![0; 4]).decode().is_err());
+}
+
+#[test]
+fn reject_malformed_frames() {
+    let mut reader = Cursor::new(b"PNG\0\0\0".to_vec());
+
+    assert!(matches!(
+        Frame::read_from(&mut reader),
+        Err(MiniViewError::Remote(RemoteError::MalformedFrame(_)))
+    ));
+}
+
+#[test]
+fn reject_huge_frames() {
+    let header = |width: u32, height: u32, length: u32| {
+        let mut bytes = b"MVF1\0\x04huge\x01".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.push(PixelFormat::Rgba8 as u8);
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes
+    };
+
+    for bytes in [
+        header(u32::MAX, u32::MAX, 16),
+        header(serve::MAX_DIMENSION + 1, 1, 16),
+        header(1, 1, serve::MAX_DATA_LENGTH + 1),
+    ] {
+        assert!(matches!(
+            Frame::read_from(&mut Cursor::new(bytes)),
+            Err(MiniViewError::Remote(RemoteError::MalformedFrame(_)))
+        ));
+    }
+
+    // frames which are built directly are not limited, so their size may overflow
+    let frame = Frame {
+        window: "huge".to_string(),
+        image: FrameImage::Raw {
+            width: u32::MAX,
+            height: u32::MAX,
+            format: PixelFormat::Rgba8,
+            data: vec![0; 16],
+        },
+    };
+
+    assert!(matches!(
+        frame.decode(),
+        Err(MiniViewError::Remote(RemoteError::MalformedFrame(_)))
+    ));
+}
+
+#[test]
+fn reply_with_error_when_window_does_not_start() {
+    let path = std::env::temp_dir().join(format!("miniview-serve-{}.sock", std::process::id()));
+    let address = Address::Unix(path.clone());
+
+    let server = {
+        let address = address.clone();
+        std::thread::spawn(move || serve::serve(&address, "false"))
+    };
+
+    while !path.exists() {
+        assert!(!server.is_finished());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    let result = serve::send(&address, &raw_frame(PixelFormat::Rgb8, vec![0; 6]));
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Remote(RemoteError::Rejected(_)))
+    ));
+
+    let result = serve::send(&address, &raw_frame(PixelFormat::Rgb8, vec![0; 5]));
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Remote(RemoteError::Rejected(_)))
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn leave_files_which_are_not_sockets() {
+    let path = std::env::temp_dir().join(format!("miniview-serve-{}.txt", std::process::id()));
+    std::fs::write(&path, "notes").unwrap();
+
+    let result = serve::serve(&Address::Unix(path.clone()), "false");
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Remote(RemoteError::NotASocket(_)))
+    ));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "notes");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reject_non_local_addresses() {
+    let result = serve::serve(&Address::Tcp("0.0.0.0:0".parse().unwrap()), "false");
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Remote(RemoteError::NonLocalAddress(_)))
+    ));
+}
+
+#[test]
+fn start_windows_concurrently() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    // A window process which never listens, and exits after a while
+    let directory = std::env::temp_dir();
+    let program = directory.join(format!("miniview-serve-slow-{}.sh", std::process::id()));
+    std::fs::write(&program, "#!/bin/sh\nsleep 2\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = directory.join(format!("miniview-serve-slow-{}.sock", std::process::id()));
+    let address = Address::Unix(path.clone());
+
+    let server = {
+        let (address, program) = (address.clone(), program.clone());
+        std::thread::spawn(move || serve::serve(&address, program))
+    };
+
+    while !path.exists() {
+        assert!(!server.is_finished());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let started = Instant::now();
+    let clients = ["first", "second"].map(|name| {
+        let address = address.clone();
+        let mut frame = raw_frame(PixelFormat::Rgb8, vec![0; 6]);
+        frame.window = name.to_string();
+
+        std::thread::spawn(move || serve::send(&address, &frame))
+    });
+
+    for client in clients {
+        assert!(client.join().unwrap().is_err());
+    }
+
+    // Starting one window does not hold up the other
+    assert!(started.elapsed() < Duration::from_secs(4));
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(program).unwrap();
+}
+
+#[test]
+fn stop_serving() {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let path =
+        std::env::temp_dir().join(format!("miniview-serve-stop-{}.sock", std::process::id()));
+    let address = Address::Unix(path.clone());
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let server = {
+        let (address, stop) = (address.clone(), Arc::clone(&stop));
+        std::thread::spawn(move || serve::serve_until(&address, "miniview", &stop))
+    };
+
+    while !path.exists() {
+        assert!(!server.is_finished());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    serve::stop(&address, &stop);
+
+    assert!(server.join().unwrap().is_ok());
+    assert!(!path.exists());
+}
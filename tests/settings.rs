@@ -0,0 +1,656 @@
+use image::Rgba;
+use miniview::config::{Backend, DropBehavior};
+use miniview::errors::SettingsError;
+use miniview::{
+    Channel, Channels, Colormap, ConfigBuilder, Filter, Grid, HistogramScale, MiniView,
+    MiniViewError, Rotation, Source, Swizzle, ToneMapOperator, ToneMapping, Transparency,
+    ValueRange, ViewTransform, Zoom,
+};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+mod common;
+use common::input;
+
+// The environment is shared by the tests, which run in parallel
+static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+const VARIABLES: &[&str] = &[
+    "MINIVIEW_CONFIG",
+    "MINIVIEW_FULLSCREEN",
+    "MINIVIEW_ALLOW_WINDOW_RESIZING",
+    "MINIVIEW_LAZY_WINDOW",
+    "MINIVIEW_WINDOW_NAME",
+    "MINIVIEW_DROP_BEHAVIOR",
+    "MINIVIEW_BACKGROUND",
+    "MINIVIEW_TRANSPARENCY",
+    "MINIVIEW_CHECKERBOARD_SIZE",
+    "MINIVIEW_CHECKERBOARD_COLOURS",
+    "MINIVIEW_CHANNELS",
+    "MINIVIEW_TINT",
+    "MINIVIEW_SWIZZLE",
+    "MINIVIEW_RANGE",
+    "MINIVIEW_EXPOSURE",
+    "MINIVIEW_GAMMA",
+    "MINIVIEW_TONE_MAPPING",
+    "MINIVIEW_COLORMAP",
+    "MINIVIEW_COLOUR_BAR",
+    "MINIVIEW_HISTOGRAM",
+    "MINIVIEW_HISTOGRAM_SCALE",
+    "MINIVIEW_INFO",
+    "MINIVIEW_APPLY_ORIENTATION",
+    "MINIVIEW_COLOUR_MANAGEMENT",
+    "MINIVIEW_OUTPUT_PROFILE",
+    "MINIVIEW_ROTATION",
+    "MINIVIEW_FLIP_HORIZONTAL",
+    "MINIVIEW_FLIP_VERTICAL",
+    "MINIVIEW_FILTER",
+    "MINIVIEW_PIXEL_GRID",
+    "MINIVIEW_PIXEL_GRID_THRESHOLD",
+    "MINIVIEW_GRID",
+    "MINIVIEW_RULER",
+    "MINIVIEW_FIT",
+    "MINIVIEW_BACKEND",
+];
+
+/// Load the configuration with the given configuration file and environment variables
+fn load(file: &str, variables: &[(&str, &str)]) -> Result<ConfigBuilder, MiniViewError> {
+    let _guard = ENVIRONMENT.lock().unwrap_or_else(|err| err.into_inner());
+
+    let path = std::env::temp_dir().join(format!(
+        "miniview-settings-{}-{:?}.toml",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    std::fs::write(&path, file).expect("unable to write configuration file");
+
+    for variable in VARIABLES {
+        std::env::remove_var(variable);
+    }
+
+    std::env::set_var("MINIVIEW_CONFIG", &path);
+
+    for (name, value) in variables {
+        std::env::set_var(name, value);
+    }
+
+    let builder = ConfigBuilder::from_env_and_file(Source::ByPath(PathBuf::from(input())));
+
+    let _ = std::fs::remove_file(&path);
+
+    builder
+}
+
+#[test]
+fn defaults_without_settings() {
+    let config = load("", &[]).unwrap().build();
+
+    assert!(!config.fullscreen());
+    assert_eq!(config.window_name(), "miniview");
+    assert_eq!(config.initial_zoom(), Zoom::Fit);
+    assert_eq!(config.backend(), None);
+}
+
+#[test]
+fn settings_from_file() {
+    let file = r##"
+        fullscreen = true
+        allow_window_resizing = true
+        window_name = "preview"
+        drop_behavior = "wait_for_user"
+        background = "#102030"
+        fit = "actual_size"
+
+        [keys]
+        space = "quit"
+        q = "none"
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert!(config.fullscreen());
+    assert!(config.resizable_window());
+    assert_eq!(config.window_name(), "preview");
+    assert_eq!(config.drop_behavior(), DropBehavior::WaitForUser);
+    assert_eq!(config.background().0, [0x10, 0x20, 0x30, 0xff]);
+    assert_eq!(config.initial_zoom(), Zoom::Factor(1.0));
+}
+
+#[test]
+fn environment_takes_precedence_over_file() {
+    let file = r##"
+        fullscreen = true
+        window_name = "preview"
+        background = "#102030"
+    "##;
+
+    let variables = [
+        ("MINIVIEW_FULLSCREEN", "false"),
+        ("MINIVIEW_WINDOW_NAME", "from the environment"),
+        ("MINIVIEW_BACKGROUND", "#ffffff80"),
+        ("MINIVIEW_FIT", "actual_size"),
+    ];
+
+    let config = load(file, &variables).unwrap().build();
+
+    assert!(!config.fullscreen());
+    assert_eq!(config.window_name(), "from the environment");
+    assert_eq!(config.background().0, [0xff, 0xff, 0xff, 0x80]);
+    assert_eq!(config.initial_zoom(), Zoom::Factor(1.0));
+}
+
+#[test]
+fn builder_takes_precedence_over_settings() {
+    let config = load("fullscreen = true", &[("MINIVIEW_WINDOW_NAME", "env")])
+        .unwrap()
+        .set_fullscreen(false)
+        .window_name("builder")
+        .build();
+
+    assert!(!config.fullscreen());
+    assert_eq!(config.window_name(), "builder");
+}
+
+#[test]
+fn command_line_takes_precedence_over_file() {
+    let directory = std::env::temp_dir();
+    let file = directory.join(format!("miniview-settings-cli-{}.toml", std::process::id()));
+    std::fs::write(&file, "flip_horizontal = true\nflip_vertical = true").unwrap();
+
+    let save_as = |flags: &[&str]| {
+        let path = directory.join(format!(
+            "miniview-settings-cli-{}-{}.png",
+            std::process::id(),
+            flags.join("")
+        ));
+
+        let mut command = assert_cmd::Command::cargo_bin("miniview").unwrap();
+
+        for variable in VARIABLES {
+            command.env_remove(variable);
+        }
+
+        let output = command
+            .env("MINIVIEW_CONFIG", &file)
+            .arg(input())
+            .args(flags)
+            .arg("--save-as")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let saved = image::open(&path).unwrap().to_rgb8();
+        std::fs::remove_file(path).unwrap();
+        saved
+    };
+
+    let original = image::open(input()).unwrap();
+
+    // the file flips the image both ways, unless the command line switches flipping off
+    assert_eq!(save_as(&[]), original.fliph().flipv().to_rgb8());
+    assert_eq!(
+        save_as(&["--no-flip-horizontal"]),
+        original.flipv().to_rgb8()
+    );
+
+    // the last of two opposite flags wins
+    assert_eq!(
+        save_as(&[
+            "--no-flip-horizontal",
+            "--flip-horizontal",
+            "--no-flip-vertical"
+        ]),
+        original.fliph().to_rgb8()
+    );
+
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn transparency_from_file() {
+    let file = r##"
+        checkerboard_size = 16
+        checkerboard_colours = ["#000000", "#ffffff"]
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert_eq!(
+        config.transparency(),
+        Transparency::Checkerboard {
+            size: 16,
+            colours: [Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255])],
+        }
+    );
+
+    let config = load("transparency = \"white\"", &[]).unwrap().build();
+    assert_eq!(
+        config.transparency(),
+        Transparency::Solid(Rgba([255, 255, 255, 255]))
+    );
+}
+
+#[test]
+fn transparency_from_environment() {
+    let variables = [
+        ("MINIVIEW_TRANSPARENCY", "checkerboard"),
+        ("MINIVIEW_CHECKERBOARD_SIZE", "4"),
+        ("MINIVIEW_CHECKERBOARD_COLOURS", "#101010, #202020"),
+    ];
+
+    let config = load("transparency = \"black\"", &variables)
+        .unwrap()
+        .build();
+
+    assert_eq!(
+        config.transparency(),
+        Transparency::Checkerboard {
+            size: 4,
+            colours: [Rgba([16, 16, 16, 255]), Rgba([32, 32, 32, 255])],
+        }
+    );
+}
+
+#[test]
+fn channels_from_file_and_environment() {
+    let file = r##"
+        channels = "green"
+        tint = true
+        swizzle = "bgr"
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert_eq!(config.channels(), Channels::Single(Channel::Green));
+    assert!(config.tinted());
+    assert_eq!(config.swizzle(), Swizzle::BGRA);
+
+    let variables = [
+        ("MINIVIEW_CHANNELS", "luminance"),
+        ("MINIVIEW_SWIZZLE", "rgba"),
+    ];
+    let config = load(file, &variables).unwrap().build();
+
+    assert_eq!(config.channels(), Channels::Luminance);
+    assert_eq!(config.swizzle(), Swizzle::RGBA);
+}
+
+#[test]
+fn tone_mapping_from_file_and_environment() {
+    let file = r##"
+        range = "percentile:1,99"
+        exposure = 1.5
+        tone_mapping = "reinhard"
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert_eq!(
+        config.tone_mapping(),
+        ToneMapping {
+            range: ValueRange::Percentile(1.0, 99.0),
+            exposure: 1.5,
+            gamma: 1.0,
+            operator: ToneMapOperator::Reinhard,
+        }
+    );
+
+    let variables = [
+        ("MINIVIEW_RANGE", "auto"),
+        ("MINIVIEW_GAMMA", "2.2"),
+        ("MINIVIEW_TONE_MAPPING", "aces"),
+    ];
+    let config = load(file, &variables).unwrap().build();
+
+    assert_eq!(
+        config.tone_mapping(),
+        ToneMapping {
+            range: ValueRange::Auto,
+            exposure: 1.5,
+            gamma: 2.2,
+            operator: ToneMapOperator::Aces,
+        }
+    );
+}
+
+#[test]
+fn colormap_from_file_and_environment() {
+    let file = r##"
+        colormap = "magma"
+        colour_bar = true
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert_eq!(config.colormap(), Colormap::Magma);
+    assert!(config.colour_bar());
+
+    let variables = [
+        ("MINIVIEW_COLORMAP", "coolwarm"),
+        ("MINIVIEW_COLOUR_BAR", "false"),
+    ];
+    let config = load(file, &variables).unwrap().build();
+
+    assert_eq!(config.colormap(), Colormap::Coolwarm);
+    assert!(!config.colour_bar());
+
+    let result = load("colormap = \"jet\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn histogram_from_file_and_environment() {
+    let file = r##"
+        histogram = true
+        histogram_scale = "log"
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert!(config.histogram());
+    assert_eq!(config.histogram_scale(), HistogramScale::Log);
+
+    let variables = [
+        ("MINIVIEW_HISTOGRAM", "0"),
+        ("MINIVIEW_HISTOGRAM_SCALE", "linear"),
+    ];
+    let config = load(file, &variables).unwrap().build();
+
+    assert!(!config.histogram());
+    assert_eq!(config.histogram_scale(), HistogramScale::Linear);
+}
+
+#[test]
+fn info_and_orientation_from_file_and_environment() {
+    let config = load("", &[]).unwrap().build();
+
+    assert!(!config.info());
+    assert!(config.apply_orientation());
+
+    let file = r##"
+        info = true
+        apply_orientation = false
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert!(config.info());
+    assert!(!config.apply_orientation());
+
+    let variables = [
+        ("MINIVIEW_INFO", "false"),
+        ("MINIVIEW_APPLY_ORIENTATION", "true"),
+    ];
+    let config = load(file, &variables).unwrap().build();
+
+    assert!(!config.info());
+    assert!(config.apply_orientation());
+}
+
+#[test]
+fn colour_management_from_file_and_environment() {
+    let config = load("", &[]).unwrap().build();
+
+    assert!(config.colour_management());
+    assert!(config.output_profile().is_none());
+
+    // the sRGB profile embedded in the photo
+    let profile = Source::ByPath(input().into())
+        .metadata()
+        .unwrap()
+        .colour_profile
+        .unwrap();
+    let path = std::env::temp_dir().join(format!("miniview-profile-{}.icc", std::process::id()));
+    std::fs::write(&path, profile.as_bytes()).unwrap();
+
+    let file = format!(
+        "colour_management = false\noutput_profile = {:?}",
+        path.display().to_string()
+    );
+    let config = load(&file, &[]).unwrap().build();
+
+    assert!(!config.colour_management());
+    assert_eq!(config.output_profile(), Some(&profile));
+
+    let variables = [
+        ("MINIVIEW_COLOUR_MANAGEMENT", "on"),
+        ("MINIVIEW_OUTPUT_PROFILE", input()),
+    ];
+    let result = load("", &variables);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidEnvironmentVariable(name, _))) if name == "MINIVIEW_OUTPUT_PROFILE"
+    ));
+
+    let result = load(&format!("output_profile = {:?}", input()), &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+
+    let config = load("", &variables[..1]).unwrap().build();
+    assert!(config.colour_management());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn transform_from_file_and_environment() {
+    let config = load("", &[]).unwrap().build();
+
+    assert_eq!(config.transform(), ViewTransform::default());
+
+    let file = r##"
+        rotation = -90
+        flip_horizontal = true
+    "##;
+
+    let config = load(file, &[]).unwrap().build();
+
+    assert_eq!(
+        config.transform(),
+        ViewTransform {
+            rotation: Rotation::Rotate270,
+            flip_horizontal: true,
+            flip_vertical: false,
+        }
+    );
+
+    let variables = [
+        ("MINIVIEW_ROTATION", "180"),
+        ("MINIVIEW_FLIP_HORIZONTAL", "no"),
+        ("MINIVIEW_FLIP_VERTICAL", "yes"),
+    ];
+    let config = load(file, &variables).unwrap().build();
+
+    assert_eq!(config.transform().rotation, Rotation::Rotate180);
+    assert!(!config.transform().flip_horizontal);
+    assert!(config.transform().flip_vertical);
+
+    let result = load("rotation = 45", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+
+    let result = load("", &[("MINIVIEW_ROTATION", "45")]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidEnvironmentVariable(name, _))) if name == "MINIVIEW_ROTATION"
+    ));
+}
+
+#[test]
+fn filter_from_file_and_environment() {
+    let config = load("filter = \"area\"", &[]).unwrap().build();
+
+    assert_eq!(config.filter(), Filter::Area);
+
+    let config = load("filter = \"area\"", &[("MINIVIEW_FILTER", "nearest")])
+        .unwrap()
+        .build();
+
+    assert_eq!(config.filter(), Filter::Nearest);
+
+    let result = load("filter = \"lanczos\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn grids_from_file_and_environment() {
+    let file = "pixel_grid = false\npixel_grid_threshold = 4.0\ngrid = \"64x32\"\nruler = true";
+    let config = load(file, &[]).unwrap().build();
+
+    assert!(!config.pixel_grid());
+    assert_eq!(config.pixel_grid_threshold(), 4.0);
+    assert_eq!(config.grid(), Some(Grid::new(64, 32)));
+    assert!(config.ruler());
+
+    let config = load(
+        file,
+        &[
+            ("MINIVIEW_PIXEL_GRID", "true"),
+            ("MINIVIEW_PIXEL_GRID_THRESHOLD", "16"),
+            ("MINIVIEW_GRID", "8"),
+            ("MINIVIEW_RULER", "false"),
+        ],
+    )
+    .unwrap()
+    .build();
+
+    assert!(config.pixel_grid());
+    assert_eq!(config.pixel_grid_threshold(), 16.0);
+    assert_eq!(config.grid(), Some(Grid::square(8)));
+    assert!(!config.ruler());
+
+    let result = load("grid = \"0\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn reject_invalid_tone_mapping() {
+    let result = load("range = \"percentile:99,1\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+
+    let result = load("", &[("MINIVIEW_EXPOSURE", "bright")]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(
+            SettingsError::InvalidEnvironmentVariable(_, _)
+        ))
+    ));
+}
+
+#[test]
+fn reject_invalid_swizzle() {
+    let result = load("swizzle = \"bgrx\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn reject_unknown_setting() {
+    let result = load("full_screen = true", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn reject_unknown_command() {
+    let result = load("[keys]\nspace = \"explode\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn reject_invalid_colour() {
+    let result = load("background = \"red\"", &[]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::InvalidFile(_, _)))
+    ));
+}
+
+#[test]
+fn reject_invalid_environment_variable() {
+    let result = load("", &[("MINIVIEW_FULLSCREEN", "maybe")]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(
+            SettingsError::InvalidEnvironmentVariable(_, _)
+        ))
+    ));
+}
+
+#[test]
+fn reject_missing_configuration_file() {
+    let _guard = ENVIRONMENT.lock().unwrap_or_else(|err| err.into_inner());
+
+    std::env::set_var("MINIVIEW_CONFIG", "does-not-exist.toml");
+    let result = ConfigBuilder::from_env_and_file(Source::ByPath(PathBuf::from(input())));
+    std::env::remove_var("MINIVIEW_CONFIG");
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::Settings(SettingsError::UnableToReadFile(_)))
+    ));
+}
+
+#[test]
+fn backend_from_settings() {
+    let config = load("backend = \"pixels\"", &[]).unwrap().build();
+    assert_eq!(config.backend(), Some(Backend::Pixels));
+
+    let config = load("", &[("MINIVIEW_BACKEND", "piston_window")])
+        .unwrap()
+        .build();
+    assert_eq!(config.backend(), Some(Backend::PistonWindow));
+}
+
+#[test]
+fn reject_backend_which_was_not_compiled() {
+    let other = match Backend::compiled() {
+        Backend::Pixels => Backend::PistonWindow,
+        Backend::PistonWindow => Backend::Pixels,
+    };
+
+    let config = ConfigBuilder::from_path(input()).backend(other).build();
+
+    assert!(matches!(
+        MiniView::show(config),
+        Err(MiniViewError::BackendNotAvailable(_, _))
+    ));
+}
@@ -0,0 +1,104 @@
+#![cfg(unix)]
+
+use common::input;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+mod common;
+
+fn close_on_signal(signal: &str, exit_code: i32) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_miniview"))
+        .args([input(), "--ready"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("unable to start miniview");
+
+    let mut line = String::new();
+    let stdout = child.stdout.take().unwrap();
+    BufReader::new(stdout).read_line(&mut line).unwrap();
+
+    if !line.starts_with("ready") {
+        let _ = child.kill();
+        panic!("window did not become ready");
+    }
+
+    let status = Command::new("kill")
+        .args([&format!("-{}", signal), &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(exit_code));
+}
+
+#[test]
+fn close_on_sigterm() {
+    close_on_signal("TERM", 143);
+}
+
+#[test]
+fn close_on_sigint() {
+    close_on_signal("INT", 130);
+}
+
+#[test]
+fn reload_the_shown_image_on_sighup() {
+    use miniview::remote::{self, Request};
+    use std::time::{Duration, Instant};
+
+    let directory = std::env::temp_dir();
+    let socket = directory.join(format!("miniview-signals-{}.sock", std::process::id()));
+    let image = directory.join(format!("miniview-signals-{}.png", std::process::id()));
+    image::RgbImage::new(3, 2).save(&image).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_miniview"))
+        .args([input(), "--ready", "--listen"])
+        .arg(&socket)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("unable to start miniview");
+
+    let mut line = String::new();
+    let stdout = child.stdout.take().unwrap();
+    BufReader::new(stdout).read_line(&mut line).unwrap();
+
+    if !line.starts_with("ready") {
+        let _ = child.kill();
+        panic!("window did not become ready");
+    }
+
+    let request = Request::Load {
+        path: image.clone(),
+    };
+    assert!(remote::send(&socket, &request).unwrap().ok);
+
+    // The second image changes, and the signal reloads it rather than the image shown at startup
+    image::RgbImage::new(5, 4).save(&image).unwrap();
+
+    let status = Command::new("kill")
+        .args(["-HUP", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let started = Instant::now();
+    let image_size = loop {
+        let state = remote::send(&socket, &Request::State)
+            .unwrap()
+            .state
+            .unwrap();
+
+        if state.image_size == (5, 4) || started.elapsed() > Duration::from_secs(5) {
+            break state.image_size;
+        }
+
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    let _ = remote::send(&socket, &Request::Close);
+    child.wait().unwrap();
+    std::fs::remove_file(image).unwrap();
+
+    assert_eq!(image_size, (5, 4));
+}
@@ -0,0 +1,158 @@
+use base64::Engine;
+use miniview::errors::{ImportError, MiniViewError};
+use miniview::io::detect_source;
+use miniview::Source;
+use std::path::PathBuf;
+
+mod common;
+use common::input;
+
+fn image_bytes() -> Vec<u8> {
+    std::fs::read(input()).expect("unable to read test image")
+}
+
+fn base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[test]
+fn detect_image_bytes() {
+    let bytes = image_bytes();
+
+    match detect_source(bytes.clone()).unwrap() {
+        Source::Bytes(detected) => assert_eq!(detected, bytes),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+#[test]
+fn detect_path() {
+    let text = format!("{}\n", input());
+
+    match detect_source(text.into_bytes()).unwrap() {
+        Source::ByPath(path) => assert_eq!(path, PathBuf::from(input())),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+#[test]
+fn detect_list_of_paths() {
+    let text = format!("{}\n\n{}\n", input(), input());
+
+    match detect_source(text.into_bytes()).unwrap() {
+        Source::ByPaths(paths) => assert_eq!(paths, vec![PathBuf::from(input()); 2]),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+#[test]
+fn detect_base64_image() {
+    let bytes = image_bytes();
+
+    // base64 tools usually wrap lines
+    let encoded = base64(&bytes)
+        .as_bytes()
+        .chunks(76)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match detect_source(encoded.into_bytes()).unwrap() {
+        Source::Bytes(detected) => assert_eq!(detected, bytes),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+#[test]
+fn detect_data_uri() {
+    let bytes = image_bytes();
+    let uri = format!("data:image/jpeg;base64,{}", base64(&bytes));
+
+    match detect_source(uri.into_bytes()).unwrap() {
+        Source::Bytes(detected) => assert_eq!(detected, bytes),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+#[test]
+fn reject_data_uri_without_base64() {
+    let result = detect_source(b"data:text/plain,hello".to_vec());
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::FailedToImport(
+            ImportError::OnStdinUnrecognizedContent
+        ))
+    ));
+}
+
+#[test]
+fn reject_binary_content() {
+    let result = detect_source(vec![0xff, 0x00, 0xfe, 0x01]);
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::FailedToImport(
+            ImportError::OnStdinUnrecognizedContent
+        ))
+    ));
+}
+
+#[test]
+fn reject_whitespace() {
+    let result = detect_source(b" \n\t\n".to_vec());
+
+    assert!(matches!(
+        result,
+        Err(MiniViewError::FailedToImport(
+            ImportError::OnStdinPathWasEmpty
+        ))
+    ));
+}
+
+#[test]
+fn fall_back_to_missing_path() {
+    match detect_source(b"does-not-exist.png".to_vec()).unwrap() {
+        Source::ByPath(path) => assert_eq!(path, PathBuf::from("does-not-exist.png")),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+/// A copy of the test image in the current directory, whose name starts like a magic number
+struct ImageNamed(PathBuf);
+
+impl ImageNamed {
+    fn new(prefix: &str, extension: &str) -> Self {
+        let path = PathBuf::from(format!("{}-{}.{}", prefix, std::process::id(), extension));
+        std::fs::copy(input(), &path).expect("unable to copy test image");
+        ImageNamed(path)
+    }
+}
+
+impl Drop for ImageNamed {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn detect_path_which_starts_like_a_pnm_image() {
+    let image = ImageNamed::new("P1000123", "JPG");
+    let text = image.0.to_str().unwrap().to_string();
+
+    match detect_source(text.into_bytes()).unwrap() {
+        Source::ByPath(path) => assert_eq!(path, image.0),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
+
+#[test]
+fn detect_path_which_starts_like_a_bmp_image() {
+    let image = ImageNamed::new("BMW", "png");
+    let text = image.0.to_str().unwrap().to_string();
+
+    match detect_source(text.into_bytes()).unwrap() {
+        Source::ByPath(path) => assert_eq!(path, image.0),
+        source => panic!("unexpected source: {:?}", source),
+    }
+}
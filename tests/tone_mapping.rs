@@ -0,0 +1,95 @@
+use miniview::{ConfigBuilder, ToneMapOperator, ToneMapping, ValueRange};
+
+mod common;
+use common::input;
+
+#[test]
+fn parse_value_range() {
+    assert_eq!("full".parse::<ValueRange>().unwrap(), ValueRange::Full);
+    assert_eq!("auto".parse::<ValueRange>().unwrap(), ValueRange::Auto);
+    assert_eq!(
+        "percentile:1,99".parse::<ValueRange>().unwrap(),
+        ValueRange::Percentile(1.0, 99.0)
+    );
+    assert_eq!(
+        "window:0.5,0.25".parse::<ValueRange>().unwrap(),
+        ValueRange::Window {
+            level: 0.5,
+            width: 0.25
+        }
+    );
+}
+
+#[test]
+fn reject_invalid_value_range() {
+    for value in [
+        "",
+        "none",
+        "percentile",
+        "percentile:99,1",
+        "percentile:1,101",
+        "percentile:1",
+        "window:0.5,0",
+        "window:a,b",
+    ] {
+        assert!(value.parse::<ValueRange>().is_err(), "{}", value);
+    }
+}
+
+#[test]
+fn value_range_round_trip() {
+    for name in ["full", "auto", "percentile:2.5,97.5", "window:0.5,0.25"] {
+        assert_eq!(name.parse::<ValueRange>().unwrap().to_string(), name);
+    }
+}
+
+#[test]
+fn parse_tone_map_operator() {
+    assert_eq!(
+        "clamp".parse::<ToneMapOperator>().unwrap(),
+        ToneMapOperator::Clamp
+    );
+    assert_eq!(
+        "reinhard".parse::<ToneMapOperator>().unwrap(),
+        ToneMapOperator::Reinhard
+    );
+    assert_eq!(
+        "aces".parse::<ToneMapOperator>().unwrap(),
+        ToneMapOperator::Aces
+    );
+    assert!("filmic".parse::<ToneMapOperator>().is_err());
+}
+
+#[test]
+fn images_are_shown_as_they_are_by_default() {
+    let config = ConfigBuilder::from_path(input()).build();
+
+    assert_eq!(config.tone_mapping(), ToneMapping::default());
+    assert_eq!(config.tone_mapping().range, ValueRange::Full);
+    assert_eq!(config.tone_mapping().exposure, 0.0);
+    assert_eq!(config.tone_mapping().gamma, 1.0);
+    assert_eq!(config.tone_mapping().operator, ToneMapOperator::Clamp);
+}
+
+#[test]
+fn tone_mapping_components() {
+    let config = ConfigBuilder::from_path(input())
+        .tone_mapping(ToneMapping {
+            gamma: 2.2,
+            ..ToneMapping::default()
+        })
+        .value_range(ValueRange::Auto)
+        .exposure(-1.5)
+        .tone_map_operator(ToneMapOperator::Aces)
+        .build();
+
+    assert_eq!(
+        config.tone_mapping(),
+        ToneMapping {
+            range: ValueRange::Auto,
+            exposure: -1.5,
+            gamma: 2.2,
+            operator: ToneMapOperator::Aces,
+        }
+    );
+}
@@ -0,0 +1,113 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use miniview::errors::SettingsError;
+use miniview::{Rotation, ViewTransform};
+
+/// A 3x2 image of which each pixel has a different value
+fn image() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| {
+        Rgb([x as u8 * 10, y as u8 * 10, 0])
+    }))
+}
+
+/// A change of the transform, and the same change applied to the shown image
+type Change = (
+    fn(ViewTransform) -> ViewTransform,
+    fn(&DynamicImage) -> DynamicImage,
+);
+
+fn transform(rotation: Rotation, flip_horizontal: bool, flip_vertical: bool) -> ViewTransform {
+    ViewTransform {
+        rotation,
+        flip_horizontal,
+        flip_vertical,
+    }
+}
+
+#[test]
+fn parse_rotation() {
+    assert_eq!("0".parse::<Rotation>().unwrap(), Rotation::Normal);
+    assert_eq!("90".parse::<Rotation>().unwrap(), Rotation::Rotate90);
+    assert_eq!("180°".parse::<Rotation>().unwrap(), Rotation::Rotate180);
+    assert_eq!("-90".parse::<Rotation>().unwrap(), Rotation::Rotate270);
+    assert_eq!("450".parse::<Rotation>().unwrap(), Rotation::Rotate90);
+    assert_eq!(Rotation::Rotate270.degrees(), 270);
+
+    assert!(matches!(
+        "45".parse::<Rotation>(),
+        Err(SettingsError::InvalidRotation(value)) if value == "45"
+    ));
+    assert!("left".parse::<Rotation>().is_err());
+}
+
+#[test]
+fn flip_before_rotation() {
+    let image = image();
+
+    assert!(ViewTransform::default().is_identity());
+    assert_eq!(ViewTransform::default().apply(image.clone()), image);
+    assert_eq!(
+        transform(Rotation::Rotate90, false, false).apply(image.clone()),
+        image.rotate90()
+    );
+    assert_eq!(
+        transform(Rotation::Rotate90, true, false).apply(image.clone()),
+        image.fliph().rotate90()
+    );
+    assert_eq!(
+        transform(Rotation::Rotate270, false, true).apply(image.clone()),
+        image.flipv().rotate270()
+    );
+
+    // flipping both ways is a half turn
+    let both = transform(Rotation::Normal, true, true);
+    assert!(!both.is_identity());
+    assert_eq!(both.apply(image.clone()), image.rotate180());
+    assert!(transform(Rotation::Rotate180, true, true).is_identity());
+}
+
+#[test]
+fn compose_changes_of_the_shown_image() {
+    let image = image();
+    let changes: [Change; 4] = [
+        (ViewTransform::rotated_clockwise, DynamicImage::rotate90),
+        (
+            ViewTransform::rotated_counter_clockwise,
+            DynamicImage::rotate270,
+        ),
+        (ViewTransform::flipped_horizontally, DynamicImage::fliph),
+        (ViewTransform::flipped_vertically, DynamicImage::flipv),
+    ];
+
+    // each sequence of three changes, applied to the transform, shows the same image as applying
+    // the changes to the image one after the other
+    for first in 0..changes.len() {
+        for second in 0..changes.len() {
+            for third in 0..changes.len() {
+                let mut transform = transform(Rotation::Rotate90, false, true);
+                let mut expected = transform.apply(image.clone());
+
+                for index in [first, second, third] {
+                    let (change, apply) = changes[index];
+                    transform = change(transform);
+                    expected = apply(&expected);
+                }
+
+                assert_eq!(transform.apply(image.clone()), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn rotation_swaps_dimensions() {
+    let rotated = transform(Rotation::Rotate270, true, false).apply(image());
+
+    assert_eq!(rotated.dimensions(), (2, 3));
+    assert_eq!(
+        ViewTransform::default()
+            .rotated_clockwise()
+            .rotated_clockwise()
+            .rotation,
+        Rotation::Rotate180
+    );
+}
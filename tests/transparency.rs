@@ -0,0 +1,32 @@
+use image::Rgba;
+use miniview::Transparency;
+
+#[test]
+fn parse_named_backdrops() {
+    assert_eq!(
+        "checkerboard".parse::<Transparency>().unwrap(),
+        Transparency::default()
+    );
+    assert_eq!(
+        "black".parse::<Transparency>().unwrap(),
+        Transparency::Solid(Rgba([0, 0, 0, 255]))
+    );
+    assert_eq!(
+        "white".parse::<Transparency>().unwrap(),
+        Transparency::Solid(Rgba([255, 255, 255, 255]))
+    );
+}
+
+#[test]
+fn parse_colour() {
+    assert_eq!(
+        "#ff8000".parse::<Transparency>().unwrap(),
+        Transparency::Solid(Rgba([255, 128, 0, 255]))
+    );
+}
+
+#[test]
+fn reject_unknown_backdrop() {
+    assert!("grey".parse::<Transparency>().is_err());
+    assert!("#ff80".parse::<Transparency>().is_err());
+}